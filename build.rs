@@ -0,0 +1,69 @@
+use std::{fs, path::Path, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    // HEAD only names the branch, so a new commit changes the branch ref instead
+    for path in git_ref_paths() {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=CMUX_TARGET={}", target);
+
+    // Source tarballs have no .git, so the commit is optional
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=CMUX_GIT_COMMIT={}", commit);
+
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (krate, var) in [
+        ("ratatui", "CMUX_RATATUI_VERSION"),
+        ("crossterm", "CMUX_CROSSTERM_VERSION"),
+    ] {
+        println!(
+            "cargo:rustc-env={}={}",
+            var,
+            locked_version(&lock, krate).unwrap_or("unknown")
+        );
+    }
+}
+
+/// Files that move when the commit changes: `HEAD` on a checkout, and the
+/// branch's loose ref or `packed-refs` on a new commit. Missing files (no
+/// `.git` directory, as in source tarballs and worktrees) are left out, as
+/// cargo would rerun forever.
+fn git_ref_paths() -> Vec<String> {
+    let head = fs::read_to_string(".git/HEAD").unwrap_or_default();
+    let branch_ref = head
+        .trim()
+        .strip_prefix("ref: ")
+        .map(|name| format!(".git/{}", name));
+    [".git/HEAD".to_string()]
+        .into_iter()
+        .chain(branch_ref)
+        .chain([".git/packed-refs".to_string()])
+        .filter(|path| Path::new(path).exists())
+        .collect()
+}
+
+/// Version of `krate` recorded in Cargo.lock (first match if several are locked).
+fn locked_version<'a>(lock: &'a str, krate: &str) -> Option<&'a str> {
+    let name_line = format!("name = \"{}\"", krate);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name_line {
+            return lines
+                .next()?
+                .trim()
+                .strip_prefix("version = \"")?
+                .strip_suffix('"');
+        }
+    }
+    None
+}
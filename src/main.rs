@@ -1,7 +1,7 @@
 #![allow(clippy::uninlined_format_args)]
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use crossterm::{
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
@@ -17,28 +17,59 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use regex::{Regex, RegexBuilder};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     io::{self, IsTerminal, Write},
-    path::PathBuf,
-    process::{Command, Output},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 use sysinfo::System;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 // Trait for executing tmux commands - allows for mocking in tests
 trait TmuxExecutor {
     fn execute_command(&self, args: &[&str]) -> Result<Output>;
 }
 
+/// `tmux` without our `CMUX_DEPTH`, so a server we start doesn't hand it to
+/// every shell and make unrelated cmux runs look nested.
+fn tmux_command() -> Command {
+    let mut cmd = Command::new("tmux");
+    cmd.env_remove("CMUX_DEPTH");
+    cmd
+}
+
 // Default implementation that executes real tmux commands
 struct DefaultTmuxExecutor;
 
 impl TmuxExecutor for DefaultTmuxExecutor {
     fn execute_command(&self, args: &[&str]) -> Result<Output> {
-        Command::new("tmux")
+        tmux_command()
+            .args(args)
+            .output()
+            .context("Failed to execute tmux command")
+    }
+}
+
+// Talks to the tmux server behind a specific socket (`tmux -S <path>`)
+struct SocketTmuxExecutor {
+    socket: PathBuf,
+}
+
+impl TmuxExecutor for SocketTmuxExecutor {
+    fn execute_command(&self, args: &[&str]) -> Result<Output> {
+        tmux_command()
+            .arg("-S")
+            .arg(&self.socket)
             .args(args)
             .output()
             .context("Failed to execute tmux command")
@@ -50,6 +81,32 @@ impl TmuxExecutor for DefaultTmuxExecutor {
 #[command(about = "A mobile-friendly tmux wrapper", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Print diagnostic warnings to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Ask for confirmation before every destructive command
+    #[arg(long, global = true)]
+    confirm: bool,
+
+    /// Reuse session listings fetched within the last 500ms (for scripted bursts)
+    #[arg(long, global = true)]
+    cache: bool,
+
+    /// Pick a session from a numbered list instead of the full-screen TUI
+    #[arg(long)]
+    plain: bool,
+
+    /// Start the TUI without measuring every session; memory and CPU fill in
+    /// for the selected sessions as you move
+    #[arg(long, conflicts_with = "plain")]
+    fast: bool,
+
+    /// Switch this tmux client to the picked session and exit instead of
+    /// attaching (what `cmux popup` runs)
+    #[arg(long, hide = true, conflicts_with = "plain")]
+    switch_client: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -58,13 +115,74 @@ struct Cli {
 enum Commands {
     /// List all tmux sessions
     #[command(visible_alias = "ls")]
-    List,
+    List {
+        /// Print only session names, each terminated by a NUL byte (for `xargs -0`)
+        #[arg(long, conflicts_with_all = ["json", "output"])]
+        null: bool,
+        /// How to print the sessions
+        #[arg(
+            long,
+            visible_alias = "format",
+            value_enum,
+            default_value_t = OutputFormat::Table
+        )]
+        output: OutputFormat,
+        /// Shorthand for `--output json`
+        #[arg(long, hide = true, conflicts_with = "output")]
+        json: bool,
+        /// Comma-separated fields to include in JSON output (e.g. name,cpu)
+        #[arg(long, value_name = "FIELDS")]
+        only: Option<String>,
+        /// List sessions from every tmux server socket, not just the default one
+        #[arg(long, conflicts_with = "null")]
+        all_servers: bool,
+        /// Only list sessions whose name starts with the configured `session_prefix`
+        #[arg(long)]
+        global_filter: bool,
+        /// Order the sessions by this key instead of tmux's order
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
+        /// Redraw the list every SECS seconds (default 2) until Ctrl+C
+        #[arg(
+            long,
+            value_name = "SECS",
+            num_args = 0..=1,
+            default_missing_value = "2",
+            value_parser = parse_interval_secs,
+            conflicts_with = "null"
+        )]
+        watch: Option<u64>,
+    },
 
     /// Attach to a tmux session
     #[command(visible_alias = "a")]
     Attach {
         /// Session name to attach to
         session: Option<String>,
+        /// Attach as a read-only client (`attach-session -r`)
+        #[arg(long)]
+        read_only: bool,
+        /// Don't shrink the session to this terminal (`-f ignore-size`, tmux 3.0+)
+        #[arg(long)]
+        no_resize: bool,
+        /// Detach every other client from the session (`attach-session -d`)
+        #[arg(long)]
+        exclusive: bool,
+        /// Attach to the session working in the current directory, creating one there if none is
+        #[arg(long, conflicts_with = "session")]
+        here: bool,
+        /// Print the resolved `tmux attach-session` command instead of running it
+        #[arg(long)]
+        print_cmd: bool,
+        /// Extra arguments appended to `tmux attach-session` as-is (not validated)
+        #[arg(last = true, allow_hyphen_values = true, value_name = "TMUX_ARGS")]
+        tmux_args: Vec<String>,
+    },
+
+    /// Switch the current tmux client to a session (`tmux switch-client`)
+    Switch {
+        /// Session name or alias to switch to
+        session: String,
     },
 
     /// Create a new tmux session
@@ -72,6 +190,36 @@ enum Commands {
     New {
         /// Session name for the new session
         name: Option<String>,
+        /// Create the session in the same group as an existing session (shares its windows)
+        #[arg(long, value_name = "EXISTING")]
+        group: Option<String>,
+        /// Shell to run in the session's first window instead of tmux's default-shell
+        #[arg(long, value_name = "PATH", conflicts_with = "group")]
+        shell: Option<PathBuf>,
+        /// Remove the `clean_env_vars` from the session's environment
+        #[arg(long, conflicts_with = "group")]
+        clean_env: bool,
+        /// Create the session detached and print `{"name": ...}` for scripts
+        #[arg(long, conflicts_with_all = ["shell", "clean_env"])]
+        json: bool,
+        /// Create the session detached with this many windows
+        #[arg(
+            long,
+            value_parser = clap::value_parser!(u16).range(1..),
+            conflicts_with_all = ["group", "shell", "clean_env"]
+        )]
+        windows: Option<u16>,
+        /// Comma-separated names for the windows (implies --windows when given alone)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_name = "NAMES",
+            conflicts_with_all = ["group", "shell", "clean_env"]
+        )]
+        window_names: Vec<String>,
+        /// Create the session even if `max_sessions` are already running
+        #[arg(long)]
+        force: bool,
     },
 
     /// Kill a tmux session
@@ -85,15 +233,79 @@ enum Commands {
     #[command(visible_alias = "r")]
     Rename {
         /// Current session name
-        old_name: String,
+        #[arg(required_unless_present_any = ["prefix", "replace"])]
+        old_name: Option<String>,
+        /// New session name
+        #[arg(required_unless_present_any = ["prefix", "replace"])]
+        new_name: Option<String>,
+        /// Prepend PREFIX to every session name that doesn't already start with it
+        #[arg(
+            long,
+            value_name = "PREFIX",
+            conflicts_with_all = ["old_name", "new_name", "replace"]
+        )]
+        prefix: Option<String>,
+        /// Replace FROM with TO in every session name containing it
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["FROM", "TO"],
+            conflicts_with_all = ["old_name", "new_name"]
+        )]
+        replace: Option<Vec<String>>,
+        /// With --prefix or --replace, only rename sessions whose name contains TEXT
+        #[arg(long = "match", value_name = "TEXT", conflicts_with_all = ["old_name", "new_name"])]
+        only_matching: Option<String>,
+        /// With --prefix or --replace, rename without asking first
+        #[arg(short, long, conflicts_with_all = ["old_name", "new_name"])]
+        yes: bool,
+    },
+
+    /// Rename the tmux session this command is run from
+    RenameCurrent {
         /// New session name
         new_name: String,
     },
 
+    /// Save a snapshot of the current sessions
+    Save {
+        /// Snapshot file path (defaults to ~/.cmux_snapshot.json)
+        file: Option<PathBuf>,
+        /// Also record each window's pane layout so restore can recreate splits
+        #[arg(long)]
+        with_layout: bool,
+    },
+
     /// Restore sessions from snapshot
     Restore {
         /// Snapshot file path
         file: Option<PathBuf>,
+        /// Print what would be created or skipped without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Recreate sessions that already exist instead of skipping them
+        #[arg(long)]
+        force: bool,
+        /// Don't ask before replacing existing sessions
+        #[arg(short, long, requires = "force")]
+        yes: bool,
+    },
+
+    /// Print a JSON Schema describing the snapshot file format
+    SnapshotSchema,
+
+    /// Combine two snapshots into one, de-duplicating sessions by name
+    SnapshotMerge {
+        /// First snapshot file
+        a: PathBuf,
+        /// Second snapshot file
+        b: PathBuf,
+        /// Where to write the merged snapshot (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Which snapshot wins when both contain a session with the same name
+        #[arg(long, value_enum, default_value_t = SnapshotSide::A)]
+        prefer: SnapshotSide,
     },
 
     /// Create or manage session aliases
@@ -102,6 +314,29 @@ enum Commands {
         name: Option<String>,
         /// Session name to alias
         session: Option<String>,
+        /// Remove the alias NAME
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with_all = ["name", "session", "rename"]
+        )]
+        delete: Option<String>,
+        /// Rename the alias OLD to NEW, keeping its session
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["OLD", "NEW"],
+            conflicts_with_all = ["name", "session"]
+        )]
+        rename: Option<Vec<String>>,
+    },
+
+    /// Assign a color tag to a session (use "none" to clear)
+    Tag {
+        /// Session name
+        session: Option<String>,
+        /// Tag color (red, green, yellow, blue, magenta, cyan, white, gray)
+        color: Option<String>,
     },
 
     /// Configure remote hosts for SSH tmux listing
@@ -111,21 +346,156 @@ enum Commands {
     },
 
     /// Show live session overview
-    Top,
+    Top {
+        /// Quit automatically after this many seconds without a keypress
+        #[arg(long, value_name = "SECS")]
+        idle_timeout: Option<u64>,
+        /// Seconds between refreshes; 0 prints the overview once and exits
+        #[arg(long, value_name = "SECS", value_parser = parse_interval_secs)]
+        interval: Option<u64>,
+        /// Split the list into sections with per-section totals
+        #[arg(long, value_enum, default_value_t = GroupBy::None)]
+        group_by: GroupBy,
+        /// Print the overview once and exit (same as `--interval 0`)
+        #[arg(long, conflicts_with = "interval")]
+        once: bool,
+        /// With --once, print the sessions like `list --output` (CSV adds a timestamp column)
+        #[arg(long, value_enum, requires = "once", conflicts_with = "group_by")]
+        output: Option<OutputFormat>,
+        /// With --output csv, skip the header row (for appending to an existing file)
+        #[arg(long, requires = "output")]
+        no_header: bool,
+        /// Leave out the totals line a one-shot text dump ends with
+        #[arg(long)]
+        no_summary: bool,
+        /// Keep polling, but only redraw when sessions come, go, or change attached/activity state
+        #[arg(long, conflicts_with = "once")]
+        on_change: bool,
+    },
+
+    /// Exit 0 if a session exists, 1 otherwise (prints nothing unless --verbose)
+    Exists {
+        /// Exact session name
+        session: String,
+    },
 
     /// Show detailed session information
     Info {
         /// Session name
         session: Option<String>,
+        /// Also show the session's scrollback limit (history-limit)
+        #[arg(long)]
+        history: bool,
+        /// Stable `key<TAB>value` lines for scripts (format never changes)
+        #[arg(long, conflicts_with = "history")]
+        porcelain: bool,
+    },
+
+    /// List a session's windows
+    Windows {
+        /// Session name (defaults to the first session)
+        session: Option<String>,
+        /// How to print the windows
+        #[arg(
+            long,
+            visible_alias = "format",
+            value_enum,
+            default_value_t = OutputFormat::Table
+        )]
+        output: OutputFormat,
+    },
+
+    /// Set a session's scrollback limit (tmux history-limit)
+    SetHistory {
+        /// Session name
+        session: String,
+        /// Number of lines to keep; applies to panes created afterwards
+        lines: u64,
+    },
+
+    /// Turn tmux mouse mode on or off for a session (toggles when omitted)
+    Mouse {
+        /// Session name
+        session: String,
+        /// New state; flips the current value when left out
+        #[arg(value_enum)]
+        state: Option<Toggle>,
+    },
+
+    /// Print the last lines of a session's active pane
+    Capture {
+        /// Session name
+        session: Option<String>,
+        /// Number of lines to capture
+        #[arg(short = 'n', long, default_value_t = PREVIEW_LINES)]
+        lines: usize,
+        /// Don't print the truncation indicator when scrollback was cut off
+        #[arg(long)]
+        no_indicator: bool,
+    },
+
+    /// Print the preferences in effect, with defaults filled in, as JSON
+    Config,
+
+    /// Print aliases, tags, session order and preferences as one JSON backup
+    ConfigExport,
+
+    /// Merge a backup made by `config-export` into the current settings
+    ConfigImport {
+        /// Backup file to read (defaults to stdin)
+        file: Option<PathBuf>,
+    },
+
+    /// Type a command into the active pane of every session
+    Broadcast {
+        /// Command line to send (followed by Enter)
+        command: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// Kill all sessions with confirmation
     #[command(visible_alias = "ka")]
     KillAll,
 
+    /// Open the session picker in a tmux popup (run inside tmux 3.2+)
+    Popup {
+        /// Popup width, in cells or as a percentage of the terminal
+        #[arg(long, default_value = "80%")]
+        width: String,
+        /// Popup height, in cells or as a percentage of the terminal
+        #[arg(long, default_value = "80%")]
+        height: String,
+    },
+
+    /// Check that tmux and the cmux config are usable
+    Doctor {
+        /// Also flag sessions whose options differ from `session_expectations`
+        #[arg(long)]
+        sessions: bool,
+    },
+
     /// Show version information
     #[command(visible_alias = "v")]
-    Version,
+    Version {
+        /// Also report tmux, target, git commit and library versions (for bug reports)
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Time session listing with and without enrichment
+    #[command(hide = true)]
+    Bench {
+        /// Number of listings to time for each mode
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 10,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        iterations: u32,
+    },
 }
 
 #[derive(Subcommand)]
@@ -149,7 +519,7 @@ enum HostCommands {
     List,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct TmuxSession {
     name: String,
     windows: usize,
@@ -162,6 +532,16 @@ struct TmuxSession {
     activity: String,
     process_info: Option<ProcessInfo>,
     resource_info: Option<ResourceInfo>,
+    /// Socket name of the server the session lives on; only set by `list --all-servers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    server: Option<String>,
+    /// Values of the extra `list_format` fields, keyed by tmux variable name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extra_fields: BTreeMap<String, String>,
+    /// Windows captured by `save` so restore can rebuild them; empty elsewhere.
+    /// Not `windows`, which already holds the count.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    window_list: Vec<SnapshotWindow>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -178,23 +558,68 @@ struct HostsConfig {
     hosts: Vec<HostConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct ProcessInfo {
     pid: Option<u32>,
     command: String,
     user: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct ResourceInfo {
     memory_mb: f64,
     cpu_percent: f32,
+    /// Swapped-out memory; `None` where the platform doesn't report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    swap_kb: Option<u64>,
+    /// Open file descriptors; `None` where the platform doesn't report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    open_fds: Option<usize>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct SessionSnapshot {
     sessions: Vec<TmuxSession>,
     timestamp: String,
+    /// Window layouts by session name, present when saved with `--with-layout`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    layouts: BTreeMap<String, Vec<WindowLayout>>,
+    /// The manual TUI order at save time; empty when none was set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    order: Vec<String>,
+}
+
+/// One window's shape, enough to rebuild its splits on restore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+struct WindowLayout {
+    name: String,
+    panes: usize,
+    /// `#{window_layout}`, as accepted by `select-layout`
+    layout: String,
+}
+
+/// One window as saved in a snapshot, in index order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+struct SnapshotWindow {
+    name: String,
+    /// Working directory of the window's active pane
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+/// One window as shown by `cmux windows`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct TmuxWindow {
+    index: usize,
+    name: String,
+    panes: usize,
+    active: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PaneCapture {
+    lines: Vec<String>,
+    truncated: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -204,6 +629,180 @@ struct RemoteHostSessions {
     error: Option<String>,
 }
 
+/// Levels the TUI can drill into. The session list is the implicit bottom of
+/// `App::view_stack`; Esc/Backspace pop back towards it, and Esc there quits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Sessions,
+    /// Session list with the selected session's pane alongside
+    Preview,
+}
+
+/// Which sessions the TUI list shows, cycled with `f`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum ViewFilter {
+    #[default]
+    All,
+    Attached,
+    Detached,
+}
+
+impl ViewFilter {
+    fn next(self) -> Self {
+        match self {
+            ViewFilter::All => ViewFilter::Attached,
+            ViewFilter::Attached => ViewFilter::Detached,
+            ViewFilter::Detached => ViewFilter::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ViewFilter::All => "all",
+            ViewFilter::Attached => "attached only",
+            ViewFilter::Detached => "detached only",
+        }
+    }
+
+    fn matches(self, session: &TmuxSession) -> bool {
+        match self {
+            ViewFilter::All => true,
+            ViewFilter::Attached => session.attached,
+            ViewFilter::Detached => !session.attached,
+        }
+    }
+}
+
+/// Minimum idle time for a session to stay in the TUI list, cycled with `i`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum IdleFilter {
+    #[default]
+    Off,
+    FiveMinutes,
+    Hour,
+    Day,
+}
+
+impl IdleFilter {
+    fn next(self) -> Self {
+        match self {
+            IdleFilter::Off => IdleFilter::FiveMinutes,
+            IdleFilter::FiveMinutes => IdleFilter::Hour,
+            IdleFilter::Hour => IdleFilter::Day,
+            IdleFilter::Day => IdleFilter::Off,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            IdleFilter::Off => "off",
+            IdleFilter::FiveMinutes => "5m",
+            IdleFilter::Hour => "1h",
+            IdleFilter::Day => "1d",
+        }
+    }
+
+    fn threshold(self) -> Option<Duration> {
+        match self {
+            IdleFilter::Off => None,
+            IdleFilter::FiveMinutes => Some(Duration::from_secs(5 * 60)),
+            IdleFilter::Hour => Some(Duration::from_secs(60 * 60)),
+            IdleFilter::Day => Some(Duration::from_secs(24 * 60 * 60)),
+        }
+    }
+
+    /// Sessions whose activity time can't be read never count as idle.
+    fn matches(self, session: &TmuxSession, now: i64) -> bool {
+        match self.threshold() {
+            None => true,
+            Some(threshold) => session_idle(session, now).is_some_and(|idle| idle >= threshold),
+        }
+    }
+}
+
+/// Session orderings for `list --sort` and the TUI `S` key. Everything but
+/// the name puts the largest (or most recent) first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortKey {
+    /// Alphabetical, ignoring case
+    Name,
+    /// Most windows first
+    Windows,
+    /// Highest memory first
+    Memory,
+    /// Highest CPU first
+    Cpu,
+    /// Most recently active first
+    Activity,
+}
+
+impl SortKey {
+    /// Next TUI sort after `current`; `None` is the manual order.
+    fn cycle(current: Option<SortKey>) -> Option<SortKey> {
+        match current {
+            None => Some(SortKey::Name),
+            Some(SortKey::Name) => Some(SortKey::Windows),
+            Some(SortKey::Windows) => Some(SortKey::Memory),
+            Some(SortKey::Memory) => Some(SortKey::Cpu),
+            Some(SortKey::Cpu) => Some(SortKey::Activity),
+            Some(SortKey::Activity) => None,
+        }
+    }
+
+    /// `▲` for the ascending name sort, `▼` for the rest.
+    fn arrow(self) -> &'static str {
+        match self {
+            SortKey::Name => "▲",
+            _ => "▼",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Windows => "windows",
+            SortKey::Memory => "memory",
+            SortKey::Cpu => "cpu",
+            SortKey::Activity => "activity",
+        }
+    }
+}
+
+/// Sort `sessions` by `key`, keeping tmux's order between equal sessions.
+/// Sessions without the measurement (no `resource_info`, unreadable
+/// activity) go last.
+fn sort_sessions(sessions: &mut [TmuxSession], key: SortKey) {
+    fn descending_missing_last<T: PartialOrd>(a: Option<T>, b: Option<T>) -> std::cmp::Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    }
+    let memory = |s: &TmuxSession| s.resource_info.as_ref().map(|r| r.memory_mb);
+    let cpu = |s: &TmuxSession| s.resource_info.as_ref().map(|r| r.cpu_percent);
+    let activity = |s: &TmuxSession| s.activity.trim().parse::<i64>().ok();
+    match key {
+        SortKey::Name => sessions.sort_by_key(|s| s.name.to_lowercase()),
+        SortKey::Windows => sessions.sort_by_key(|s| std::cmp::Reverse(s.windows)),
+        SortKey::Memory => sessions.sort_by(|a, b| descending_missing_last(memory(a), memory(b))),
+        SortKey::Cpu => sessions.sort_by(|a, b| descending_missing_last(cpu(a), cpu(b))),
+        SortKey::Activity => {
+            sessions.sort_by(|a, b| descending_missing_last(activity(a), activity(b)))
+        }
+    }
+}
+
+/// How long since the session's last activity, given the current Unix time.
+fn session_idle(session: &TmuxSession, now: i64) -> Option<Duration> {
+    let activity: i64 = session.activity.trim().parse().ok()?;
+    Some(Duration::from_secs(
+        now.saturating_sub(activity).max(0) as u64
+    ))
+}
+
 #[derive(Debug, Clone)]
 enum SessionOrigin {
     Local,
@@ -222,16 +821,82 @@ enum ListEntry {
         title: String,
         host: Option<HostConfig>,
     },
-    Session(SessionEntry),
+    Session(Box<SessionEntry>),
+}
+
+impl ListEntry {
+    /// The session on this row when it is a local one.
+    fn local_session(&self) -> Option<&TmuxSession> {
+        match self {
+            ListEntry::Session(entry) if matches!(entry.origin, SessionOrigin::Local) => {
+                Some(&entry.session)
+            }
+            _ => None,
+        }
+    }
 }
 
-const AUTO_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Default for the `refresh_secs` preference.
+const DEFAULT_REFRESH_SECS: u64 = 5;
+/// Rows either side of the selection that `--fast` measures
+const LAZY_ENRICH_SPAN: usize = 3;
+/// Leading character that turns the TUI `/` filter into a regex
+const NAME_FILTER_REGEX_SIGIL: char = '~';
+/// Manual refreshes closer together than this are coalesced into one.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
 const STATUS_MESSAGE_TTL: Duration = Duration::from_secs(4);
 const SSH_LIST_TIMEOUT_SECS: u64 = 3;
 const SSH_ATTACH_TIMEOUT_SECS: u64 = 5;
 const SSH_ACTION_TIMEOUT_SECS: u64 = 5;
-const TMUX_LIST_FORMAT: &str =
-    "#{session_name}:#{session_windows}:#{session_attached}:#{session_created}:#{session_activity}";
+const SERVER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+const PREVIEW_LINES: usize = 20;
+const TRUNCATION_INDICATOR: &str = "… (scrollback above)";
+/// Tag colors accepted by `cmux tag`, with their TUI color and ANSI foreground code.
+const TAG_COLORS: &[(&str, Color, u8)] = &[
+    ("red", Color::Red, 31),
+    ("green", Color::Green, 32),
+    ("yellow", Color::Yellow, 33),
+    ("blue", Color::Blue, 34),
+    ("magenta", Color::Magenta, 35),
+    ("cyan", Color::Cyan, 36),
+    ("white", Color::White, 37),
+    ("gray", Color::Gray, 90),
+];
+const TAG_MARKER: &str = "■";
+const CURRENT_MARKER: &str = " (current)";
+/// How many cmux processes may be nested (cmux -> hook -> cmux ...) before we bail.
+const MAX_CMUX_DEPTH: usize = 5;
+/// Fields joined by `LIST_FIELD_SEPARATOR` rather than `:`, so a name
+/// containing `:` can't shift the other fields, and ended by `LIST_RECORD_END`.
+const TMUX_LIST_FORMAT: &str = "#{session_name}:|:#{session_windows}:|:#{session_attached}:|:#{session_created}:|:#{session_activity}|;|";
+/// Fields `TMUX_LIST_FORMAT` fills in; a custom `list_format` must include `session_name`.
+const CORE_LIST_FIELDS: &[&str] = &[
+    "session_name",
+    "session_windows",
+    "session_attached",
+    "session_created",
+    "session_activity",
+];
+/// Extra tmux session variables `list_format` may add; they land in `extra_fields`.
+const EXTRA_LIST_FIELDS: &[&str] = &[
+    "session_path",
+    "session_group",
+    "session_grouped",
+    "session_group_size",
+    "session_id",
+    "session_last_attached",
+    "session_alerts",
+    "session_marked",
+];
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static CACHE_ENABLED: AtomicBool = AtomicBool::new(false);
+static SESSION_CACHE: Mutex<Option<SessionCache>> = Mutex::new(None);
+const SESSION_CACHE_TTL: Duration = Duration::from_millis(500);
+
+fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
 
 struct App {
     sessions: Vec<TmuxSession>,
@@ -240,6 +905,27 @@ struct App {
     show_help: bool,
     #[allow(dead_code)]
     aliases: HashMap<String, String>,
+    tags: HashMap<String, String>,
+    session_order: Vec<String>,
+    view_filter: ViewFilter,
+    idle_filter: IdleFilter,
+    /// Sort cycled with `S`; `None` keeps the manual (Shift+↑/↓) order
+    sort: Option<SortKey>,
+    /// `/` filter text; only sessions whose name contains it are listed.
+    /// A leading `~` makes the rest a regex instead
+    name_filter: String,
+    name_filter_cursor: usize,
+    /// Last `~` pattern that compiled; kept while the typed one is invalid
+    name_filter_regex: Option<Regex>,
+    name_filter_error: Option<String>,
+    /// The `/` prompt is open and keys edit `name_filter`
+    editing_name_filter: bool,
+    columns: ColumnConfig,
+    fallback_process_info: bool,
+    /// Active window name per session, filled only when `columns.active_window` is on
+    active_windows: HashMap<String, String>,
+    /// Session the TUI was opened from, when run inside tmux
+    current_session: Option<String>,
     hosts: Vec<HostConfig>,
     show_new_session_popup: bool,
     new_session_input: String,
@@ -254,23 +940,68 @@ struct App {
     new_host_error: Option<String>,
     show_kill_confirm: bool,
     kill_confirm_target: Option<KillTarget>,
-    status_message: Option<String>,
-    status_message_expires: Option<Instant>,
+    status_message: Option<(String, Instant)>,
+    /// When `d` was last pressed, for the `dd` quick-kill
+    pending_delete: Option<Instant>,
+    quit_key: QuitKey,
+    quit_confirm: bool,
+    /// `K` asks before killing detached sessions too
+    confirm_kill: bool,
+    /// "Quit cmux? (y/N)" is showing and the next key answers it
+    quit_prompt: bool,
+    /// How quickly the second `d` must follow the first
+    double_press_window: Duration,
+    /// `session_prefix` from the config, applied to sessions created here
+    session_prefix: Option<String>,
+    view_stack: Vec<View>,
+    /// When tmux was last queried, for throttling manual refreshes
+    last_refresh_attempt: Option<Instant>,
+    /// A manual refresh arrived inside `MIN_REFRESH_INTERVAL` and is still owed
+    refresh_pending: bool,
+    preview: Option<PaneCapture>,
+    /// Measure only the sessions around the selection instead of all of them
+    lazy_enrich: bool,
     system: System,
 }
 
 impl App {
     fn new() -> Result<Self> {
+        Self::with_enrichment(false)
+    }
+
+    fn with_enrichment(lazy_enrich: bool) -> Result<Self> {
         let aliases = load_aliases()?;
+        let tags = load_tags()?;
+        let session_order = load_session_order()?;
         let hosts = load_hosts()?;
-        let mut system = System::new_all();
-        system.refresh_all();
+        let config = load_config()?;
+        let system = if lazy_enrich {
+            System::new()
+        } else {
+            let mut system = System::new_all();
+            system.refresh_all();
+            system
+        };
         let mut app = App {
             sessions: Vec::new(),
             remote_hosts: Vec::new(),
             selected: 0,
             show_help: false,
             aliases,
+            tags,
+            session_order,
+            view_filter: ViewFilter::default(),
+            idle_filter: IdleFilter::default(),
+            sort: config.default_sort,
+            name_filter: String::new(),
+            name_filter_cursor: 0,
+            name_filter_regex: None,
+            name_filter_error: None,
+            editing_name_filter: false,
+            columns: config.columns,
+            fallback_process_info: config.fallback_process_info,
+            active_windows: HashMap::new(),
+            current_session: current_tmux_session().ok(),
             hosts,
             show_new_session_popup: false,
             new_session_input: String::new(),
@@ -286,7 +1017,18 @@ impl App {
             show_kill_confirm: false,
             kill_confirm_target: None,
             status_message: None,
-            status_message_expires: None,
+            pending_delete: None,
+            quit_key: config.quit_key,
+            quit_confirm: config.quit_confirm,
+            confirm_kill: config.confirm_kill,
+            quit_prompt: false,
+            double_press_window: Duration::from_millis(config.double_press_ms),
+            session_prefix: config.session_prefix,
+            view_stack: Vec::new(),
+            last_refresh_attempt: None,
+            refresh_pending: false,
+            preview: None,
+            lazy_enrich,
             system,
         };
         app.refresh()?;
@@ -367,7 +1109,107 @@ impl App {
     }
 
     fn refresh(&mut self) -> Result<()> {
-        self.sessions = get_tmux_sessions_with_system(&mut self.system)?;
+        self.last_refresh_attempt = Some(Instant::now());
+        self.refresh_pending = false;
+        if self.lazy_enrich {
+            self.sessions = list_tmux_sessions_with_executor(&DefaultTmuxExecutor)?;
+        } else {
+            let options = self.enrich_options();
+            self.sessions = get_tmux_sessions_with_system(&mut self.system, options)?;
+        }
+        self.reload_after_sessions()?;
+        self.enrich_near_selection();
+        Ok(())
+    }
+
+    /// Measure swap and fds only when a column shows them.
+    fn enrich_options(&self) -> EnrichOptions {
+        EnrichOptions {
+            swap_and_fds: self.columns.swap || self.columns.fds,
+            fallback_process_info: self.fallback_process_info,
+        }
+    }
+
+    /// Local sessions within `LAZY_ENRICH_SPAN` rows of the selection that
+    /// have not been measured since the last refresh.
+    fn lazy_enrich_targets(&self) -> Vec<String> {
+        if !self.lazy_enrich {
+            return Vec::new();
+        }
+        let start = self.selected.saturating_sub(LAZY_ENRICH_SPAN);
+        self.build_entries()
+            .into_iter()
+            .skip(start)
+            .take(self.selected - start + LAZY_ENRICH_SPAN + 1)
+            .filter_map(|entry| {
+                entry
+                    .local_session()
+                    .filter(|session| session.resource_info.is_none())
+                    .map(|session| session.name.clone())
+            })
+            .collect()
+    }
+
+    /// With lazy enrichment on, fill in process and resource figures for the
+    /// sessions around the selection.
+    fn enrich_near_selection(&mut self) {
+        let targets = self.lazy_enrich_targets();
+        let options = self.enrich_options();
+        for session in &mut self.sessions {
+            if targets.contains(&session.name) {
+                // A session gone since the last refresh keeps its fallbacks
+                let _ =
+                    enrich_session_info(session, &DefaultTmuxExecutor, &mut self.system, options);
+            }
+        }
+    }
+
+    /// Refresh unless the last one was under `MIN_REFRESH_INTERVAL` ago, in
+    /// which case remember the request and return `false`; the event loop
+    /// honours it once the window has passed.
+    fn request_refresh(&mut self) -> Result<bool> {
+        if self.refresh_wait(Instant::now()).is_some() {
+            self.refresh_pending = true;
+            return Ok(false);
+        }
+        self.refresh()?;
+        Ok(true)
+    }
+
+    /// How long until a refresh is allowed, or `None` if it is allowed now.
+    fn refresh_wait(&self, now: Instant) -> Option<Duration> {
+        let elapsed = now.duration_since(self.last_refresh_attempt?);
+        MIN_REFRESH_INTERVAL
+            .checked_sub(elapsed)
+            .filter(|wait| !wait.is_zero())
+    }
+
+    /// Run a coalesced refresh if one is owed and the window has passed.
+    fn run_pending_refresh(&mut self) -> Result<bool> {
+        if !self.refresh_pending || self.refresh_wait(Instant::now()).is_some() {
+            return Ok(false);
+        }
+        self.refresh()?;
+        Ok(true)
+    }
+
+    /// Refresh after killing a local session, tolerating the server exiting.
+    fn refresh_after_kill(&mut self) -> Result<()> {
+        let sessions_before_kill = self.sessions.len();
+        let options = self.enrich_options();
+        self.sessions = sessions_after_kill(
+            get_tmux_sessions_with_system(&mut self.system, options),
+            sessions_before_kill,
+        )?;
+        self.reload_after_sessions()
+    }
+
+    fn reload_after_sessions(&mut self) -> Result<()> {
+        self.order_sessions();
+        if self.columns.active_window {
+            self.active_windows = active_window_names_with_executor(&DefaultTmuxExecutor);
+        }
+        self.tags = load_tags()?;
         self.hosts = load_hosts()?;
         self.remote_hosts = get_remote_sessions(&self.hosts);
         let entries_len = self.build_entries().len();
@@ -376,6 +1218,7 @@ impl App {
         } else if self.selected >= entries_len {
             self.selected = entries_len - 1;
         }
+        self.update_preview();
         Ok(())
     }
 
@@ -384,6 +1227,7 @@ impl App {
         if entries_len > 0 {
             self.selected = (self.selected + 1) % entries_len;
         }
+        self.update_preview();
     }
 
     fn previous(&mut self) {
@@ -395,12 +1239,250 @@ impl App {
                 self.selected - 1
             };
         }
+        self.update_preview();
+    }
+
+    /// Whether a `d` press is still pending; always clears it.
+    fn take_pending_delete(&mut self) -> bool {
+        self.pending_delete
+            .take()
+            .is_some_and(|pressed| pressed.elapsed() <= self.double_press_window)
     }
 
     fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
     }
 
+    /// Move the selection to the local session called `name`, if it is visible.
+    fn select_local_session(&mut self, name: &str) {
+        if let Some(row) = self.build_entries().iter().position(|entry| {
+            matches!(entry, ListEntry::Session(e) if matches!(e.origin, SessionOrigin::Local) && e.session.name == name)
+        }) {
+            self.selected = row;
+            self.update_preview();
+        }
+    }
+
+    /// Type-ahead: select the next session (wrapping) whose name starts with
+    /// `initial`, ignoring case. Returns whether one was found.
+    fn jump_to_initial(&mut self, initial: char) -> bool {
+        let entries = self.build_entries();
+        let len = entries.len();
+        let found = (1..=len)
+            .map(|offset| (self.selected + offset) % len)
+            .find(|&row| match &entries[row] {
+                ListEntry::Session(entry) => entry
+                    .session
+                    .name
+                    .chars()
+                    .next()
+                    .is_some_and(|first| first.to_lowercase().eq(initial.to_lowercase())),
+                _ => false,
+            });
+        match found {
+            Some(row) => {
+                self.selected = row;
+                self.update_preview();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Quit now, or ask first when `quit_confirm` is set.
+    fn request_quit(&mut self) -> InputResult {
+        if !self.quit_confirm {
+            return InputResult::Quit;
+        }
+        self.quit_prompt = true;
+        self.set_status_message("Quit cmux? (y/N)");
+        InputResult::Continue
+    }
+
+    fn cycle_view_filter(&mut self) {
+        self.view_filter = self.view_filter.next();
+        let entries_len = self.build_entries().len();
+        self.selected = self.selected.min(entries_len.saturating_sub(1));
+        self.update_preview();
+        self.set_status_message(format!("Showing {} sessions", self.view_filter.label()));
+    }
+
+    /// Put `sessions` in the active sort, or the manual order when there is none.
+    fn order_sessions(&mut self) {
+        match self.sort {
+            Some(key) => sort_sessions(&mut self.sessions, key),
+            None => {
+                // tmux lists sessions by name; start from that so sessions
+                // missing from the saved order land where tmux had them
+                self.sessions.sort_by(|a, b| a.name.cmp(&b.name));
+                apply_session_order(&mut self.sessions, &self.session_order);
+            }
+        }
+    }
+
+    fn cycle_sort(&mut self) {
+        let selected = self
+            .build_entries()
+            .get(self.selected)
+            .and_then(ListEntry::local_session)
+            .map(|session| session.name.clone());
+        self.sort = SortKey::cycle(self.sort);
+        self.order_sessions();
+        match selected {
+            Some(name) => self.select_local_session(&name),
+            None => self.update_preview(),
+        }
+        self.set_status_message(match self.sort {
+            Some(key) => format!("Sorted by {}", key.label()),
+            None => "Manual order".to_string(),
+        });
+    }
+
+    fn cycle_idle_filter(&mut self) {
+        self.idle_filter = self.idle_filter.next();
+        let entries_len = self.build_entries().len();
+        self.selected = self.selected.min(entries_len.saturating_sub(1));
+        self.update_preview();
+        self.set_status_message(match self.idle_filter {
+            IdleFilter::Off => "Showing sessions regardless of idle time".to_string(),
+            filter => format!("Showing sessions idle for at least {}", filter.label()),
+        });
+    }
+
+    /// Whether a session passes the attached/detached, idle and `/` name filters.
+    fn is_visible(&self, session: &TmuxSession, now: i64) -> bool {
+        self.view_filter.matches(session)
+            && self.idle_filter.matches(session, now)
+            && self.matches_name_filter(&session.name)
+    }
+
+    /// Case-insensitive substring match against the `/` filter, or a regex
+    /// match (also case-insensitive unless the pattern says `(?-i)`) when it
+    /// starts with `~`.
+    fn matches_name_filter(&self, name: &str) -> bool {
+        if self.name_filter.starts_with(NAME_FILTER_REGEX_SIGIL) {
+            return self
+                .name_filter_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(name));
+        }
+        self.name_filter.is_empty()
+            || name
+                .to_lowercase()
+                .contains(&self.name_filter.to_lowercase())
+    }
+
+    fn open_name_filter(&mut self) {
+        self.editing_name_filter = true;
+        self.name_filter_cursor = self.name_filter.chars().count();
+    }
+
+    /// Drop the `/` filter and go back to the full list.
+    fn clear_name_filter(&mut self) {
+        self.editing_name_filter = false;
+        self.name_filter.clear();
+        self.name_filter_cursor = 0;
+        self.name_filter_changed();
+    }
+
+    /// Recompile a `~` pattern and select the first session still listed
+    /// after the filter text changed.
+    fn name_filter_changed(&mut self) {
+        match self.name_filter.strip_prefix(NAME_FILTER_REGEX_SIGIL) {
+            Some(pattern) => match RegexBuilder::new(pattern).case_insensitive(true).build() {
+                Ok(regex) => {
+                    self.name_filter_regex = Some(regex);
+                    self.name_filter_error = None;
+                }
+                Err(err) => {
+                    // regex errors end with a one-line summary after the caret diagram
+                    let summary = err.to_string();
+                    let summary = summary.lines().last().unwrap_or_default();
+                    self.name_filter_error =
+                        Some(summary.trim_start_matches("error: ").to_string());
+                }
+            },
+            None => {
+                self.name_filter_regex = None;
+                self.name_filter_error = None;
+            }
+        }
+        self.selected = self
+            .build_entries()
+            .iter()
+            .position(|entry| matches!(entry, ListEntry::Session(_)))
+            .unwrap_or(0);
+        self.update_preview();
+    }
+
+    /// Move the selected local session up (`-1`) or down (`1`) and persist the new order.
+    fn move_selected_session(&mut self, delta: isize) -> Result<()> {
+        let Some(session) = self
+            .build_entries()
+            .get(self.selected)
+            .and_then(ListEntry::local_session)
+            .cloned()
+        else {
+            return Ok(());
+        };
+        let Some(index) = self.sessions.iter().position(|s| s.name == session.name) else {
+            return Ok(());
+        };
+        let Some(target) = index
+            .checked_add_signed(delta)
+            .filter(|target| *target < self.sessions.len())
+        else {
+            return Ok(());
+        };
+
+        self.sessions.swap(index, target);
+        // The neighbour may be hidden by the view filter, so find the row again
+        self.select_local_session(&session.name);
+        self.session_order = self.sessions.iter().map(|s| s.name.clone()).collect();
+        save_session_order(&self.session_order)
+    }
+
+    fn current_view(&self) -> View {
+        self.view_stack.last().copied().unwrap_or(View::Sessions)
+    }
+
+    fn push_view(&mut self, view: View) {
+        self.view_stack.push(view);
+        self.update_preview();
+    }
+
+    /// Go back one level; returns false when already at the session list.
+    fn pop_view(&mut self) -> bool {
+        let popped = self.view_stack.pop().is_some();
+        self.update_preview();
+        popped
+    }
+
+    fn show_preview(&self) -> bool {
+        self.current_view() == View::Preview
+    }
+
+    fn toggle_preview(&mut self) {
+        if self.show_preview() {
+            self.pop_view();
+        } else {
+            self.push_view(View::Preview);
+        }
+    }
+
+    /// Re-capture the selected local session's pane when the preview is visible.
+    fn update_preview(&mut self) {
+        if !self.show_preview() {
+            self.preview = None;
+            return;
+        }
+        self.preview = self
+            .build_entries()
+            .get(self.selected)
+            .and_then(ListEntry::local_session)
+            .and_then(|session| capture_pane(&session.name, PREVIEW_LINES).ok());
+    }
+
     fn show_new_session_popup(&mut self) {
         self.show_new_session_popup = true;
         self.new_session_input.clear();
@@ -422,12 +1504,56 @@ impl App {
         remove_char_before(&mut self.new_session_input, &mut self.new_session_cursor);
     }
 
-    fn show_new_host_popup(&mut self) {
-        self.show_new_host_popup = true;
-        self.new_host_name_input.clear();
-        self.new_host_name_cursor = 0;
-        self.new_host_host_input.clear();
-        self.new_host_host_cursor = 0;
+    /// Session names already present on the popup's target (local or a remote host).
+    fn new_session_target_names(&self) -> Vec<String> {
+        match &self.new_session_target {
+            NewSessionTarget::Local => self.sessions.iter().map(|s| s.name.clone()).collect(),
+            NewSessionTarget::Remote(host) => self
+                .remote_hosts
+                .iter()
+                .filter(|h| h.host.name == host.name)
+                .flat_map(|h| h.sessions.iter().map(|s| s.name.clone()))
+                .collect(),
+        }
+    }
+
+    /// `session_prefix` for the popup's target; remote sessions are created unprefixed.
+    fn new_session_prefix(&self) -> Option<&str> {
+        match self.new_session_target {
+            NewSessionTarget::Local => self.session_prefix.as_deref(),
+            NewSessionTarget::Remote(_) => None,
+        }
+    }
+
+    /// Returns a free alternative when the typed name, once prefixed as Enter
+    /// would, collides with an existing session.
+    fn new_session_name_suggestion(&self) -> Option<String> {
+        let name = self.new_session_input.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let name = apply_session_prefix(self.new_session_prefix(), name);
+        let existing = self.new_session_target_names();
+        if existing.contains(&name) {
+            Some(suggest_unique_name(&name, &existing))
+        } else {
+            None
+        }
+    }
+
+    fn accept_new_session_suggestion(&mut self) {
+        if let Some(suggestion) = self.new_session_name_suggestion() {
+            self.new_session_cursor = suggestion.chars().count();
+            self.new_session_input = suggestion;
+        }
+    }
+
+    fn show_new_host_popup(&mut self) {
+        self.show_new_host_popup = true;
+        self.new_host_name_input.clear();
+        self.new_host_name_cursor = 0;
+        self.new_host_host_input.clear();
+        self.new_host_host_cursor = 0;
         self.new_host_active_field = HostField::Host;
         self.new_host_error = None;
         self.show_new_session_popup = false;
@@ -494,11 +1620,12 @@ impl App {
             });
         }
 
-        for session in &self.sessions {
-            entries.push(ListEntry::Session(SessionEntry {
+        let now = chrono::Utc::now().timestamp();
+        for session in self.sessions.iter().filter(|s| self.is_visible(s, now)) {
+            entries.push(ListEntry::Session(Box::new(SessionEntry {
                 origin: SessionOrigin::Local,
                 session: session.clone(),
-            }));
+            })));
         }
 
         for host_sessions in &self.remote_hosts {
@@ -518,11 +1645,15 @@ impl App {
                 host: Some(host_sessions.host.clone()),
             });
 
-            for session in &host_sessions.sessions {
-                entries.push(ListEntry::Session(SessionEntry {
+            for session in host_sessions
+                .sessions
+                .iter()
+                .filter(|s| self.is_visible(s, now))
+            {
+                entries.push(ListEntry::Session(Box::new(SessionEntry {
                     origin: SessionOrigin::Remote(host_sessions.host.clone()),
                     session: session.clone(),
-                }));
+                })));
             }
         }
 
@@ -530,15 +1661,13 @@ impl App {
     }
 
     fn set_status_message(&mut self, message: impl Into<String>) {
-        self.status_message = Some(message.into());
-        self.status_message_expires = Some(Instant::now() + STATUS_MESSAGE_TTL);
+        self.status_message = Some((message.into(), Instant::now()));
     }
 
     fn clear_expired_status(&mut self) {
-        if let Some(expires) = self.status_message_expires {
-            if Instant::now() >= expires {
+        if let Some((_, set_at)) = self.status_message {
+            if set_at.elapsed() >= STATUS_MESSAGE_TTL {
                 self.status_message = None;
-                self.status_message_expires = None;
             }
         }
     }
@@ -546,81 +1675,677 @@ impl App {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    VERBOSE.store(cli.verbose, Ordering::Relaxed);
+    CACHE_ENABLED.store(cli.cache, Ordering::Relaxed);
+
+    // Only the cmux we run in a popup is handed the incremented depth
+    let depth = next_cmux_depth(std::env::var("CMUX_DEPTH").ok().as_deref())?;
 
     match cli.command {
-        None => run_tui()?,
-        Some(Commands::List) => list_sessions()?,
-        Some(Commands::Attach { session }) => attach_session(session)?,
-        Some(Commands::New { name }) => new_session(name)?,
-        Some(Commands::Kill { session }) => kill_session(session)?,
-        Some(Commands::Rename { old_name, new_name }) => rename_session(&old_name, &new_name)?,
-        Some(Commands::Restore { file }) => restore_sessions(file)?,
-        Some(Commands::Alias { name, session }) => manage_alias(name, session)?,
+        None if cli.plain => run_plain_picker()?,
+        None => run_tui(cli.fast, cli.switch_client)?,
+        Some(Commands::List {
+            null,
+            output,
+            json,
+            only,
+            all_servers,
+            global_filter,
+            sort,
+            watch,
+        }) => {
+            let output = if json { OutputFormat::Json } else { output };
+            list_sessions(
+                null,
+                output,
+                only,
+                ListSource {
+                    all_servers,
+                    global_filter,
+                    sort: match sort {
+                        Some(sort) => Some(sort),
+                        None => load_config()?.default_sort,
+                    },
+                    enrich: EnrichOptions::configured(),
+                },
+                watch,
+            )?
+        }
+        Some(Commands::Attach {
+            session,
+            read_only,
+            no_resize,
+            exclusive,
+            here,
+            print_cmd,
+            tmux_args,
+        }) => {
+            let options = AttachOptions {
+                read_only,
+                no_resize,
+                exclusive,
+                print_cmd,
+                extra_args: tmux_args,
+                size: None,
+            };
+            if here {
+                attach_here(&options)?
+            } else {
+                attach_session(session, &options)?
+            }
+        }
+        Some(Commands::Switch { session }) => switch_session(&session)?,
+        Some(Commands::New {
+            name,
+            group,
+            shell,
+            clean_env,
+            json,
+            windows,
+            window_names,
+            force,
+        }) => {
+            let plan = if windows.is_some() || !window_names.is_empty() {
+                Some(window_plan(windows, &window_names)?)
+            } else {
+                None
+            };
+            let config = load_config()?;
+            let prefix = config.session_prefix.as_deref();
+            let generated = name.is_none();
+            let name = match name {
+                Some(name) => apply_session_prefix(prefix, &name),
+                None => {
+                    let existing: Vec<String> =
+                        get_tmux_sessions()?.into_iter().map(|s| s.name).collect();
+                    default_session_name(&existing, prefix)
+                }
+            };
+            match group {
+                Some(group) => {
+                    let created = new_grouped_session(Some(name), &group, force)?;
+                    if json {
+                        println!("{}", serde_json::json!({ "name": created }));
+                    } else {
+                        println!("Created session '{}' grouped with '{}'", created, group);
+                    }
+                }
+                None if plan.is_some() => {
+                    let plan = plan.unwrap_or_default();
+                    new_session_with_windows_with_executor(
+                        &DefaultTmuxExecutor,
+                        &name,
+                        &plan,
+                        force,
+                    )?;
+                    if json {
+                        println!("{}", serde_json::json!({ "name": name }));
+                    } else {
+                        println!(
+                            "Created session '{}' with {} window(s) (detached)",
+                            name,
+                            plan.len()
+                        );
+                    }
+                }
+                None if json => {
+                    new_detached_session(&name, force)?;
+                    println!("{}", serde_json::json!({ "name": name }));
+                }
+                None => {
+                    if clean_env {
+                        new_clean_env_session(
+                            Some(name.clone()),
+                            shell.as_deref(),
+                            &config.clean_env_vars,
+                            force,
+                        )?
+                    } else {
+                        new_session(Some(name.clone()), shell.as_deref(), force)?
+                    }
+                    // Only once tmux has accepted the name; this prints after detaching
+                    if generated {
+                        println!("Created session: {}", name);
+                    }
+                }
+            }
+        }
+        Some(Commands::Kill { session }) => {
+            kill_session(session, cli.confirm || load_config()?.confirm_all)?
+        }
+        Some(Commands::Rename {
+            old_name,
+            new_name,
+            prefix,
+            replace,
+            only_matching,
+            yes,
+        }) => match (old_name, new_name) {
+            (Some(old_name), Some(new_name)) => rename_session(&old_name, &new_name)?,
+            _ => {
+                let rule = match (prefix, replace.as_deref()) {
+                    (Some(prefix), _) => BulkRename::Prefix(prefix),
+                    (None, Some([from, to])) => BulkRename::Replace(from.clone(), to.clone()),
+                    _ => unreachable!("clap requires old/new names, --prefix or --replace"),
+                };
+                bulk_rename_sessions(&rule, only_matching.as_deref(), yes)?
+            }
+        },
+        Some(Commands::RenameCurrent { new_name }) => {
+            rename_session(&current_tmux_session()?, &new_name)?
+        }
+        Some(Commands::Save { file, with_layout }) => {
+            let path = write_snapshot(file, with_layout)?;
+            println!("Snapshot saved to {}", path.display());
+        }
+        Some(Commands::Restore {
+            file,
+            dry_run,
+            force,
+            yes,
+        }) => restore_sessions(
+            file,
+            &RestoreOptions {
+                dry_run,
+                force,
+                yes,
+            },
+        )?,
+        Some(Commands::SnapshotSchema) => {
+            let schema = schemars::schema_for!(SessionSnapshot);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        Some(Commands::SnapshotMerge {
+            a,
+            b,
+            output,
+            prefer,
+        }) => snapshot_merge(&a, &b, output.as_deref(), prefer)?,
+        Some(Commands::Alias {
+            name,
+            session,
+            delete,
+            rename,
+        }) => {
+            let action = match (name, session, delete, rename.as_deref()) {
+                (_, _, Some(alias), _) => AliasAction::Delete(alias),
+                (_, _, _, Some([old, new])) => AliasAction::Rename(old.clone(), new.clone()),
+                (Some(alias), Some(session), _, _) => AliasAction::Create(alias, session),
+                (Some(alias), None, _, _) => AliasAction::Query(alias),
+                _ => AliasAction::List,
+            };
+            manage_alias(action)?
+        }
+        Some(Commands::Tag { session, color }) => manage_tags(session, color)?,
         Some(Commands::Host { command }) => manage_hosts(command)?,
-        Some(Commands::Top) => run_top_mode()?,
-        Some(Commands::Info { session }) => show_session_info(session)?,
-        Some(Commands::KillAll) => kill_all_sessions()?,
-        Some(Commands::Version) => {
+        Some(Commands::Top {
+            idle_timeout,
+            interval,
+            group_by,
+            once,
+            output,
+            no_header,
+            no_summary,
+            on_change,
+        }) => match interval {
+            _ if once => match output {
+                Some(output) => print_top_snapshot(output, !no_header, !no_summary)?,
+                None => print_top_once(group_by, !no_summary)?,
+            },
+            Some(0) => print_top_once(group_by, !no_summary)?,
+            interval => {
+                let config = load_config()?;
+                run_top_mode(
+                    idle_timeout.map(Duration::from_secs),
+                    match interval {
+                        Some(secs) => Duration::from_secs(secs),
+                        None => config.refresh_interval(),
+                    },
+                    group_by,
+                    on_change,
+                    config.auto_snapshot_interval(),
+                )?
+            }
+        },
+        Some(Commands::Info {
+            session,
+            history,
+            porcelain,
+        }) => show_session_info(session, history, porcelain)?,
+        Some(Commands::Windows { session, output }) => list_windows(session, output)?,
+        Some(Commands::Popup { width, height }) => run_popup(&width, &height, depth)?,
+        Some(Commands::SetHistory { session, lines }) => {
+            set_history_limit_with_executor(&DefaultTmuxExecutor, &session, lines)?;
+            println!("Set history-limit for '{}' to {} lines", session, lines);
+        }
+        Some(Commands::Mouse { session, state }) => {
+            let on = set_mouse_mode_with_executor(&DefaultTmuxExecutor, &session, state)?;
+            println!(
+                "Mouse mode for '{}' is now {}",
+                session,
+                if on { "on" } else { "off" }
+            );
+        }
+        Some(Commands::Capture {
+            session,
+            lines,
+            no_indicator,
+        }) => capture_session(session, lines, no_indicator)?,
+        Some(Commands::Exists { session }) => {
+            let exists = session_exists_with_executor(&DefaultTmuxExecutor, &session)?;
+            if is_verbose() {
+                eprintln!(
+                    "Session '{}' {}",
+                    session,
+                    if exists { "exists" } else { "does not exist" }
+                );
+            }
+            if !exists {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Broadcast { command, yes }) => broadcast_command(&command, yes)?,
+        Some(Commands::Config) => println!("{}", serde_json::to_string_pretty(&load_config()?)?),
+        Some(Commands::ConfigExport) => export_config()?,
+        Some(Commands::ConfigImport { file }) => import_config(file)?,
+        Some(Commands::KillAll) => kill_all_sessions(cli.confirm || load_config()?.confirm_all)?,
+        Some(Commands::Doctor { sessions }) => run_doctor(sessions)?,
+        Some(Commands::Version { full }) => {
             println!("cmux {}", env!("CARGO_PKG_VERSION"));
             println!("A mobile-friendly tmux session manager");
+            if full {
+                print_build_info();
+            }
         }
+        Some(Commands::Bench { iterations }) => run_bench(iterations)?,
     }
 
     Ok(())
 }
 
+/// Whether tmux stderr means there is simply no server (and so no sessions).
+fn is_no_server_error(stderr: &str) -> bool {
+    const NO_SERVER_MESSAGES: &[&str] = &[
+        "no server running",
+        "no sessions",
+        "no current client",
+        "can't find session",
+        "server not found",
+        "error connecting to",
+        "No such file or directory",
+        "server exited unexpectedly",
+    ];
+    NO_SERVER_MESSAGES
+        .iter()
+        .any(|message| stderr.contains(message))
+}
+
+/// Whether a local `list-sessions` raced the server shutting down, as it does
+/// after the last session is killed. Kept apart from `is_no_server_error`
+/// because ssh prints the same socket errors for an unreachable host.
+fn is_server_shutdown_error(stderr: &str) -> bool {
+    const SHUTDOWN_MESSAGES: &[&str] = &[
+        "server exited",
+        "lost server",
+        "Connection refused",
+        "Connection reset by peer",
+        "Broken pipe",
+    ];
+    SHUTDOWN_MESSAGES
+        .iter()
+        .any(|message| stderr.contains(message))
+}
+
+/// Resolve a refresh that directly follows a kill.
+///
+/// If the killed session was the last one, any failure is the tmux server
+/// shutting down and is treated as an empty session list.
+fn sessions_after_kill(
+    result: Result<Vec<TmuxSession>>,
+    sessions_before_kill: usize,
+) -> Result<Vec<TmuxSession>> {
+    match result {
+        Err(_) if sessions_before_kill <= 1 => Ok(Vec::new()),
+        other => other,
+    }
+}
+
+/// Depth for this invocation given the inherited `CMUX_DEPTH`, or an error once
+/// nesting looks like a hook or startup command re-invoking cmux in a loop.
+fn next_cmux_depth(inherited: Option<&str>) -> Result<usize> {
+    let depth = inherited
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if depth >= MAX_CMUX_DEPTH {
+        return Err(anyhow::anyhow!(
+            "cmux is nested {} levels deep (CMUX_DEPTH); refusing to continue. \
+             Check hooks or startup commands that run cmux.",
+            depth
+        ));
+    }
+    Ok(depth + 1)
+}
+
 fn get_tmux_sessions() -> Result<Vec<TmuxSession>> {
+    get_tmux_sessions_with_options(EnrichOptions::configured())
+}
+
+/// What enrichment measures beyond memory and CPU, and how it fills gaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EnrichOptions {
+    /// Swap and open fds per process, read from procfs; only the `columns.swap`
+    /// / `columns.fds` columns and JSON output show them
+    swap_and_fds: bool,
+    /// Credit sessions whose process wasn't found to `$USER` running `tmux`
+    /// (the `fallback_process_info` preference)
+    fallback_process_info: bool,
+}
+
+impl Default for EnrichOptions {
+    fn default() -> Self {
+        EnrichOptions {
+            swap_and_fds: false,
+            fallback_process_info: true,
+        }
+    }
+}
+
+impl EnrichOptions {
+    /// Defaults with the `fallback_process_info` preference applied. A broken
+    /// config is reported by the commands that need it, not here.
+    fn configured() -> Self {
+        EnrichOptions {
+            fallback_process_info: load_config()
+                .map_or(true, |config| config.fallback_process_info),
+            ..EnrichOptions::default()
+        }
+    }
+}
+
+fn get_tmux_sessions_with_options(options: EnrichOptions) -> Result<Vec<TmuxSession>> {
+    let caching = CACHE_ENABLED.load(Ordering::Relaxed);
+    let socket = tmux_socket_key();
+    if caching {
+        let cache = SESSION_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sessions) = cache
+            .as_ref()
+            .filter(|c| c.options == options)
+            .and_then(|c| c.get(&socket, Instant::now(), SESSION_CACHE_TTL))
+        {
+            return Ok(sessions);
+        }
+    }
+
+    let mut system = System::new_all();
+    system.refresh_all();
+    let sessions = get_tmux_sessions_with_system(&mut system, options)?;
+
+    if caching {
+        *SESSION_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = Some(SessionCache {
+            socket,
+            options,
+            fetched_at: Instant::now(),
+            sessions: sessions.clone(),
+        });
+    }
+    Ok(sessions)
+}
+
+/// Sessions from every server socket in the tmux socket directory, in socket
+/// name order, each tagged with its server. Sockets that don't answer are
+/// skipped with a warning.
+fn get_all_server_sessions(options: EnrichOptions) -> Result<Vec<TmuxSession>> {
+    let dir = tmux_socket_dir()?;
     let mut system = System::new_all();
     system.refresh_all();
-    get_tmux_sessions_with_system(&mut system)
+
+    let mut sessions = Vec::new();
+    for socket in discover_tmux_sockets(&dir) {
+        let server = socket
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let executor = SocketTmuxExecutor {
+            socket: socket.clone(),
+        };
+
+        let probe = executor.execute_command(&["list-sessions", "-F", "#{session_name}"]);
+        match probe {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                eprintln!(
+                    "warning: skipping tmux socket {}: {}",
+                    socket.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                continue;
+            }
+            Err(err) => {
+                eprintln!(
+                    "warning: skipping tmux socket {}: {}",
+                    socket.display(),
+                    err
+                );
+                continue;
+            }
+        }
+
+        match get_tmux_sessions_with_executor_and_system(&executor, &mut system, options) {
+            Ok(found) => sessions.extend(found.into_iter().map(|mut session| {
+                session.server = Some(server.clone());
+                session
+            })),
+            Err(err) => eprintln!(
+                "warning: skipping tmux socket {}: {}",
+                socket.display(),
+                err
+            ),
+        }
+    }
+    Ok(sessions)
+}
+
+/// Where tmux keeps this user's sockets: `$TMUX_TMPDIR/tmux-<uid>` (default `/tmp`).
+fn tmux_socket_dir() -> Result<PathBuf> {
+    let tmpdir = std::env::var("TMUX_TMPDIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| "/tmp".to_string());
+    let output = Command::new("id")
+        .arg("-u")
+        .output()
+        .context("Failed to determine user id for the tmux socket directory")?;
+    let uid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(PathBuf::from(tmpdir).join(format!("tmux-{}", uid)))
+}
+
+/// Socket files in `dir`, sorted by name. A missing directory means no servers.
+fn discover_tmux_sockets(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sockets: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| is_socket(&kind)))
+        .map(|entry| entry.path())
+        .collect();
+    sockets.sort();
+    sockets
+}
+
+#[cfg(unix)]
+fn is_socket(kind: &fs::FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    kind.is_socket()
+}
+
+#[cfg(not(unix))]
+fn is_socket(_kind: &fs::FileType) -> bool {
+    false
+}
+
+/// Session listing remembered by `--cache`, tied to the tmux server it came from.
+struct SessionCache {
+    socket: String,
+    options: EnrichOptions,
+    fetched_at: Instant,
+    sessions: Vec<TmuxSession>,
+}
+
+impl SessionCache {
+    fn get(&self, socket: &str, now: Instant, ttl: Duration) -> Option<Vec<TmuxSession>> {
+        if self.socket != socket || now.duration_since(self.fetched_at) > ttl {
+            return None;
+        }
+        Some(self.sessions.clone())
+    }
+}
+
+/// Identify the tmux server we talk to: the socket path from `$TMUX` when set.
+fn tmux_socket_key() -> String {
+    std::env::var("TMUX")
+        .ok()
+        .and_then(|tmux| tmux.split(',').next().map(str::to_string))
+        .filter(|socket| !socket.is_empty())
+        .unwrap_or_else(|| "default".to_string())
 }
 
-fn get_tmux_sessions_with_system(system: &mut System) -> Result<Vec<TmuxSession>> {
-    get_tmux_sessions_with_executor_and_system(&DefaultTmuxExecutor, system)
+/// Forget the cached listing; call before anything that adds, removes or renames sessions.
+fn invalidate_session_cache() {
+    *SESSION_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn get_tmux_sessions_with_system(
+    system: &mut System,
+    options: EnrichOptions,
+) -> Result<Vec<TmuxSession>> {
+    get_tmux_sessions_with_executor_and_system(&DefaultTmuxExecutor, system, options)
 }
 
 #[allow(dead_code)]
 fn get_tmux_sessions_with_executor(executor: &dyn TmuxExecutor) -> Result<Vec<TmuxSession>> {
     let mut system = System::new_all();
     system.refresh_all();
-    get_tmux_sessions_with_executor_and_system(executor, &mut system)
+    get_tmux_sessions_with_executor_and_system(executor, &mut system, EnrichOptions::default())
 }
 
 fn get_tmux_sessions_with_executor_and_system(
     executor: &dyn TmuxExecutor,
     system: &mut System,
+    options: EnrichOptions,
 ) -> Result<Vec<TmuxSession>> {
-    let output = executor.execute_command(&["list-sessions", "-F", TMUX_LIST_FORMAT])?;
+    let mut sessions = list_tmux_sessions_with_executor(executor)?;
+
+    // Enrich sessions with process and resource information
+    let diagnostics = enrich_sessions(&mut sessions, executor, system, options);
+    if !diagnostics.is_empty() && is_verbose() {
+        eprintln!(
+            "warning: {} of {} sessions could not be enriched (killed while listing?)",
+            diagnostics.len(),
+            sessions.len()
+        );
+        for diagnostic in &diagnostics {
+            eprintln!("  {}", diagnostic);
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Enrich every session, returning one message per session tmux couldn't
+/// answer for. A failing session keeps its fallback figures and never stops
+/// the rest from being enriched.
+fn enrich_sessions(
+    sessions: &mut [TmuxSession],
+    executor: &dyn TmuxExecutor,
+    system: &mut System,
+    options: EnrichOptions,
+) -> Vec<String> {
+    let mut diagnostics = Vec::new();
+    for session in sessions {
+        if let Err(err) = enrich_session_info(session, executor, system, options) {
+            diagnostics.push(format!("{}: {}", session.name, err));
+        }
+    }
+    diagnostics
+}
+
+/// Time `iterations` listings with and without enrichment and print
+/// min/median/p95 for each, for attaching to performance reports.
+fn run_bench(iterations: u32) -> Result<()> {
+    let executor = DefaultTmuxExecutor;
+    let mut system = System::new_all();
+    system.refresh_all();
+
+    let count = list_tmux_sessions_with_executor(&executor)?.len();
+    println!("{} sessions, {} runs each", count, iterations);
+
+    let mut plain = Vec::new();
+    let mut enriched = Vec::new();
+    for _ in 0..iterations {
+        let started = Instant::now();
+        list_tmux_sessions_with_executor(&executor)?;
+        plain.push(started.elapsed());
+
+        let started = Instant::now();
+        get_tmux_sessions_with_executor_and_system(
+            &executor,
+            &mut system,
+            EnrichOptions::configured(),
+        )?;
+        enriched.push(started.elapsed());
+    }
+
+    for (label, samples) in [("without enrichment", plain), ("with enrichment", enriched)] {
+        let [min, median, p95] = timing_percentiles(samples);
+        println!(
+            "{:<19} min {:>8.1}ms  median {:>8.1}ms  p95 {:>8.1}ms",
+            format!("{}:", label),
+            min.as_secs_f64() * 1000.0,
+            median.as_secs_f64() * 1000.0,
+            p95.as_secs_f64() * 1000.0
+        );
+    }
+    Ok(())
+}
+
+/// Min, median and 95th percentile (nearest rank) of `samples`.
+fn timing_percentiles(mut samples: Vec<Duration>) -> [Duration; 3] {
+    samples.sort();
+    let rank = |percent: usize| {
+        let index = (samples.len() * percent).div_ceil(100).saturating_sub(1);
+        samples.get(index).copied().unwrap_or_default()
+    };
+    [rank(0), rank(50), rank(95)]
+}
+
+/// List sessions without process or resource information.
+fn list_tmux_sessions_with_executor(executor: &dyn TmuxExecutor) -> Result<Vec<TmuxSession>> {
+    let list_format = load_config()
+        .map(|config| config.list_format)
+        .unwrap_or_default();
+    let custom_format = list_format_string(&list_format)?;
+    let format = custom_format.as_deref().unwrap_or(TMUX_LIST_FORMAT);
+    let output = executor.execute_command(&["list-sessions", "-F", format])?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        // Handle various tmux error messages for no server
-        if stderr.contains("no server running")
-            || stderr.contains("no sessions")
-            || stderr.contains("no current client")
-            || stderr.contains("can't find session")
-            || stderr.contains("server not found")
-            || stderr.contains("error connecting to")
-            || stderr.contains("No such file or directory")
-            || stderr.contains("server exited unexpectedly")
-        {
+        if is_no_server_error(&stderr) || is_server_shutdown_error(&stderr) {
             return Ok(Vec::new());
         }
         return Err(anyhow::anyhow!("tmux command failed: {}", stderr.trim()));
     }
 
-    let mut sessions = parse_tmux_sessions(&String::from_utf8_lossy(&output.stdout));
-
-    // Enrich sessions with process and resource information
-    for session in &mut sessions {
-        enrich_session_info(session, executor, system);
-    }
-
-    Ok(sessions)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(if custom_format.is_some() {
+        parse_tmux_sessions_with_fields(&stdout, &list_format)
+    } else {
+        parse_tmux_sessions(&stdout)
+    })
 }
 
 fn expand_tilde(path: &str) -> String {
-    if let Some(stripped) = path.strip_prefix("~/") {
+    if let Some(stripped) = path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
         if let Ok(home) = std::env::var("HOME") {
             return PathBuf::from(home)
                 .join(stripped)
@@ -631,6 +2356,42 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
+/// `C:\...`, `C:/...` or a `\\server\share` UNC path, as tmux reports
+/// them under WSL or Cygwin.
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    drive || path.starts_with("\\\\")
+}
+
+/// Replace a leading `home` in `path` with `~`, treating `/` and `\` alike.
+/// Paths outside `home`, including Windows paths under a POSIX home, are
+/// returned unchanged.
+fn collapse_home(path: &str, home: &str) -> String {
+    let same = |a: char, b: char| {
+        let is_sep = |c: char| c == '/' || c == '\\';
+        (is_sep(a) && is_sep(b)) || a == b
+    };
+    let home = home.trim_end_matches(['/', '\\']);
+    if home.is_empty() || path.chars().count() < home.chars().count() {
+        return path.to_string();
+    }
+    let split = path
+        .char_indices()
+        .nth(home.chars().count())
+        .map_or(path.len(), |(index, _)| index);
+    let (head, rest) = path.split_at(split);
+    let matches = head.chars().zip(home.chars()).all(|(a, b)| same(a, b));
+    if matches && (rest.is_empty() || rest.starts_with(['/', '\\'])) {
+        format!("~{}", rest)
+    } else {
+        path.to_string()
+    }
+}
+
 fn shell_quote(value: &str) -> String {
     let mut escaped = String::from("'");
     for ch in value.chars() {
@@ -668,15 +2429,7 @@ fn get_tmux_sessions_remote(host: &HostConfig) -> Result<Vec<TmuxSession>> {
     let output = cmd.output().context("Failed to execute ssh command")?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("no server running")
-            || stderr.contains("no sessions")
-            || stderr.contains("no current client")
-            || stderr.contains("can't find session")
-            || stderr.contains("server not found")
-            || stderr.contains("error connecting to")
-            || stderr.contains("No such file or directory")
-            || stderr.contains("server exited unexpectedly")
-        {
+        if is_no_server_error(&stderr) {
             return Ok(Vec::new());
         }
         return Err(anyhow::anyhow!("{}", stderr.trim()));
@@ -705,13 +2458,72 @@ fn get_remote_sessions(hosts: &[HostConfig]) -> Vec<RemoteHostSessions> {
         .collect()
 }
 
-fn parse_tmux_sessions(output: &str) -> Vec<TmuxSession> {
-    output
+// sysinfo 0.30 doesn't expose per-process swap or descriptor counts, so read
+// them from procfs where it exists.
+#[cfg(target_os = "linux")]
+fn process_swap_kb(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    parse_vm_swap_kb(&status)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_swap_kb(_pid: u32) -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn process_open_fds(pid: u32) -> Option<usize> {
+    Some(fs::read_dir(format!("/proc/{}/fd", pid)).ok()?.count())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_open_fds(_pid: u32) -> Option<usize> {
+    None
+}
+
+/// The `VmSwap:` value (in kB) from a `/proc/<pid>/status` file.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_vm_swap_kb(status: &str) -> Option<u64> {
+    status
         .lines()
+        .find_map(|line| line.strip_prefix("VmSwap:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn parse_tmux_sessions(output: &str) -> Vec<TmuxSession> {
+    let (sessions, rejected) = parse_tmux_sessions_with_diagnostics(output);
+    if !rejected.is_empty() && is_verbose() {
+        eprintln!(
+            "warning: {} of {} list-sessions lines could not be parsed (truncated output?)",
+            rejected.len(),
+            rejected.len() + sessions.len()
+        );
+        for line in &rejected {
+            eprintln!("  rejected: {:?}", line);
+        }
+    }
+    sessions
+}
+
+/// Like `parse_tmux_sessions`, but also returns the non-empty lines that
+/// didn't look like a session row.
+fn parse_tmux_sessions_with_diagnostics(output: &str) -> (Vec<TmuxSession>, Vec<String>) {
+    let mut rejected = Vec::new();
+    let sessions: Vec<TmuxSession> = list_records(output)
+        .into_iter()
         .filter_map(|line| {
-            let parts: Vec<&str> = line.split(':').collect();
+            let parts = session_record_fields(line);
             if parts.len() >= 5 {
                 let attached_clients = parts[2].parse::<usize>().unwrap_or(0);
+                // Some tmux builds leave #{session_activity} empty
+                let activity = if parts[4].trim().is_empty() {
+                    parts[3]
+                } else {
+                    parts[4]
+                };
                 Some(TmuxSession {
                     name: parts[0].to_string(),
                     windows: parts[1].parse().unwrap_or(0),
@@ -719,53 +2531,250 @@ fn parse_tmux_sessions(output: &str) -> Vec<TmuxSession> {
                     attached_clients,
                     attached_users: Vec::new(),
                     created: parts[3].to_string(),
-                    activity: parts[4].to_string(),
+                    activity: activity.to_string(),
                     process_info: None,
                     resource_info: None,
+                    server: None,
+                    extra_fields: BTreeMap::new(),
+                    window_list: Vec::new(),
                 })
             } else {
+                if !line.trim().is_empty() {
+                    rejected.push(line.to_string());
+                }
                 None
             }
         })
-        .collect()
+        .collect();
+
+    (dedup_sessions_by_name(sessions), rejected)
 }
 
-fn enrich_session_info(
-    session: &mut TmuxSession,
-    executor: &dyn TmuxExecutor,
-    system: &mut System,
-) {
-    // Get tmux server PID
-    if let Ok(output) =
-        executor.execute_command(&["list-sessions", "-t", &session.name, "-F", "#{session_id}"])
-    {
-        if output.status.success() {
-            let _session_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+/// Joins custom `list_format` fields. tmux rewrites tabs and other control
+/// characters in `-F` output, so this has to be printable.
+const LIST_FIELD_SEPARATOR: &str = ":|:";
+
+/// Ends each `list-sessions` record. Recent tmux prints a newline in a
+/// session name as `\n`, but older servers print it raw, so records are
+/// split on this rather than on line breaks.
+const LIST_RECORD_END: &str = "|;|";
+
+/// The fields of one `TMUX_LIST_FORMAT` record, name first. The other four
+/// are taken from the right so a separator inside the name stays in it.
+/// Records without `LIST_FIELD_SEPARATOR` use the older `:`-joined layout.
+fn session_record_fields(record: &str) -> Vec<&str> {
+    if !record.contains(LIST_FIELD_SEPARATOR) {
+        return record.split(':').collect();
+    }
+    let mut fields: Vec<&str> = record.rsplitn(5, LIST_FIELD_SEPARATOR).collect();
+    fields.reverse();
+    fields
+}
 
-            // Try to find the tmux process for this session
-            system.refresh_processes();
-            let mut total_memory = 0.0;
-            let mut total_cpu = 0.0;
-            let mut process_count = 0;
+/// `list-sessions` records, split on `LIST_RECORD_END` when the output uses
+/// it and on lines otherwise (older callers and hand-written test input).
+fn list_records(output: &str) -> Vec<&str> {
+    if !output.contains(LIST_RECORD_END) {
+        return output.lines().collect();
+    }
+    output
+        .split(LIST_RECORD_END)
+        .map(|record| record.strip_prefix('\n').unwrap_or(record))
+        .filter(|record| !record.is_empty())
+        .collect()
+}
 
-            for (pid, process) in system.processes() {
-                let cmd = process.cmd();
-                if cmd
-                    .iter()
-                    .any(|arg| arg.contains("tmux") || arg.contains(&session.name))
-                {
-                    total_memory += process.memory() as f64 / 1024.0 / 1024.0; // Convert to MB
-                    total_cpu += process.cpu_usage();
-                    process_count += 1;
+/// `list-sessions -F` string for a custom `list_format`, or
+/// `None` to use the built-in format.
+fn list_format_string(fields: &[String]) -> Result<Option<String>> {
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    if let Some(unknown) = fields.iter().find(|field| {
+        !CORE_LIST_FIELDS.contains(&field.as_str()) && !EXTRA_LIST_FIELDS.contains(&field.as_str())
+    }) {
+        return Err(anyhow::anyhow!(
+            "Unknown list_format field '{}' (known: {}, {})",
+            unknown,
+            CORE_LIST_FIELDS.join(", "),
+            EXTRA_LIST_FIELDS.join(", ")
+        ));
+    }
+    if !fields.iter().any(|field| field == "session_name") {
+        return Err(anyhow::anyhow!("list_format must include session_name"));
+    }
+    let format = fields
+        .iter()
+        .map(|field| format!("#{{{}}}", field))
+        .collect::<Vec<_>>()
+        .join(LIST_FIELD_SEPARATOR);
+    Ok(Some(format + LIST_RECORD_END))
+}
 
-                    if session.process_info.is_none() {
-                        session.process_info = Some(ProcessInfo {
-                            pid: Some(pid.as_u32()),
-                            command: cmd.join(" "),
-                            user: process
-                                .user_id()
-                                .map(|u| u.to_string())
-                                .unwrap_or_else(|| "unknown".to_string()),
+/// Parse `list-sessions` output produced by `list_format_string(fields)`.
+fn parse_tmux_sessions_with_fields(output: &str, fields: &[String]) -> Vec<TmuxSession> {
+    let sessions = list_records(output)
+        .into_iter()
+        .filter_map(|line| {
+            let values: Vec<&str> = line.splitn(fields.len(), LIST_FIELD_SEPARATOR).collect();
+            if values.len() != fields.len() {
+                return None;
+            }
+            let mut session = TmuxSession {
+                name: String::new(),
+                windows: 0,
+                attached: false,
+                attached_clients: 0,
+                attached_users: Vec::new(),
+                created: String::new(),
+                activity: String::new(),
+                process_info: None,
+                resource_info: None,
+                server: None,
+                extra_fields: BTreeMap::new(),
+                window_list: Vec::new(),
+            };
+            for (field, value) in fields.iter().zip(values) {
+                match field.as_str() {
+                    "session_name" => session.name = value.to_string(),
+                    "session_windows" => session.windows = value.parse().unwrap_or(0),
+                    "session_attached" => {
+                        session.attached_clients = value.parse().unwrap_or(0);
+                        session.attached = session.attached_clients > 0;
+                    }
+                    "session_created" => session.created = value.to_string(),
+                    "session_activity" => session.activity = value.to_string(),
+                    _ => {
+                        session
+                            .extra_fields
+                            .insert(field.clone(), value.to_string());
+                    }
+                }
+            }
+            if session.activity.trim().is_empty() {
+                session.activity = session.created.clone();
+            }
+            Some(session)
+        })
+        .collect();
+
+    dedup_sessions_by_name(sessions)
+}
+
+/// Drop rows whose name was already seen, keeping the first occurrence.
+///
+/// tmux shouldn't report the same name twice, but merged sockets or server bugs
+/// can, and index-based selection gets confusing when it does.
+fn dedup_sessions_by_name(sessions: Vec<TmuxSession>) -> Vec<TmuxSession> {
+    let mut seen = HashSet::new();
+    sessions
+        .into_iter()
+        .filter(|session| {
+            let first = seen.insert(session.name.clone());
+            if !first && is_verbose() {
+                eprintln!("warning: duplicate tmux session '{}' ignored", session.name);
+            }
+            first
+        })
+        .collect()
+}
+
+fn capture_pane(target: &str, lines: usize) -> Result<PaneCapture> {
+    capture_pane_with_executor(&DefaultTmuxExecutor, target, lines)
+}
+
+fn capture_pane_with_executor(
+    executor: &dyn TmuxExecutor,
+    target: &str,
+    lines: usize,
+) -> Result<PaneCapture> {
+    let start = format!("-{}", lines);
+    let output = executor.execute_command(&["capture-pane", "-p", "-t", target, "-S", &start])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!(
+            "Failed to capture session '{}': {}",
+            target,
+            stderr.trim()
+        ));
+    }
+    Ok(parse_capture_output(
+        &String::from_utf8_lossy(&output.stdout),
+        lines,
+    ))
+}
+
+/// Keep the last `lines` non-blank-trailing lines of a `capture-pane` dump.
+///
+/// `capture-pane -S -N` returns up to N lines of history plus the visible pane,
+/// so anything beyond N lines means there was more content above what we show.
+fn parse_capture_output(output: &str, lines: usize) -> PaneCapture {
+    let mut captured: Vec<String> = output.lines().map(|line| line.to_string()).collect();
+    while captured.last().is_some_and(|line| line.trim().is_empty()) {
+        captured.pop();
+    }
+
+    let truncated = captured.len() > lines;
+    if truncated {
+        captured.drain(..captured.len() - lines);
+    }
+
+    PaneCapture {
+        lines: captured,
+        truncated,
+    }
+}
+
+/// Fill in process and resource figures for `session`. The fallbacks are
+/// applied even when tmux can't answer for it (e.g. it was killed mid-listing),
+/// and that failure is returned so callers can report it.
+fn enrich_session_info(
+    session: &mut TmuxSession,
+    executor: &dyn TmuxExecutor,
+    system: &mut System,
+    options: EnrichOptions,
+) -> Result<()> {
+    let mut outcome = Ok(());
+
+    // Get tmux server PID
+    match executor.execute_command(&["list-sessions", "-t", &session.name, "-F", "#{session_id}"]) {
+        Ok(output) if output.status.success() => {
+            let _session_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+            // Try to find the tmux process for this session
+            system.refresh_processes();
+            let mut total_memory = 0.0;
+            let mut total_cpu = 0.0;
+            let mut total_swap: Option<u64> = None;
+            let mut total_fds: Option<usize> = None;
+            let mut process_count = 0;
+
+            for (pid, process) in system.processes() {
+                let cmd = process.cmd();
+                if cmd
+                    .iter()
+                    .any(|arg| arg.contains("tmux") || arg.contains(&session.name))
+                {
+                    total_memory += process.memory() as f64 / 1024.0 / 1024.0; // Convert to MB
+                    total_cpu += process.cpu_usage();
+                    if options.swap_and_fds {
+                        if let Some(swap) = process_swap_kb(pid.as_u32()) {
+                            *total_swap.get_or_insert(0) += swap;
+                        }
+                        if let Some(fds) = process_open_fds(pid.as_u32()) {
+                            *total_fds.get_or_insert(0) += fds;
+                        }
+                    }
+                    process_count += 1;
+
+                    if session.process_info.is_none() {
+                        session.process_info = Some(ProcessInfo {
+                            pid: Some(pid.as_u32()),
+                            command: cmd.join(" "),
+                            user: process
+                                .user_id()
+                                .map(|u| u.to_string())
+                                .unwrap_or_else(|| "unknown".to_string()),
                         });
                     }
                 }
@@ -775,6 +2784,8 @@ fn enrich_session_info(
                 session.resource_info = Some(ResourceInfo {
                     memory_mb: total_memory,
                     cpu_percent: total_cpu,
+                    swap_kb: total_swap,
+                    open_fds: total_fds,
                 });
             }
 
@@ -800,10 +2811,18 @@ fn enrich_session_info(
                 }
             }
         }
+        Ok(output) => {
+            outcome = Err(anyhow::anyhow!(
+                "{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Err(err) => outcome = Err(err),
     }
 
-    // Fallback process info if not found
-    if session.process_info.is_none() {
+    // Fallback process info if not found, unless the user asked for gaps
+    // over a guessed `$USER` (wrong on shared servers)
+    if session.process_info.is_none() && options.fallback_process_info {
         session.process_info = Some(ProcessInfo {
             pid: None,
             command: "tmux".to_string(),
@@ -816,6 +2835,8 @@ fn enrich_session_info(
         session.resource_info = Some(ResourceInfo {
             memory_mb: 0.0,
             cpu_percent: 0.0,
+            swap_kb: None,
+            open_fds: None,
         });
     }
 
@@ -824,58 +2845,465 @@ fn enrich_session_info(
             session.attached_users = vec![process.user.clone()];
         }
     }
+
+    outcome
 }
 
-fn list_sessions() -> Result<()> {
-    let sessions = get_tmux_sessions()?;
+/// Which sessions `list` fetches, and in what order.
+#[derive(Debug, Clone, Copy, Default)]
+struct ListSource {
+    all_servers: bool,
+    global_filter: bool,
+    sort: Option<SortKey>,
+    enrich: EnrichOptions,
+}
 
-    if sessions.is_empty() {
-        println!("No tmux sessions found.");
+impl ListSource {
+    fn fetch(&self) -> Result<Vec<TmuxSession>> {
+        let mut sessions = if self.all_servers {
+            get_all_server_sessions(self.enrich)?
+        } else {
+            get_tmux_sessions_with_options(self.enrich)?
+        };
+        if self.global_filter {
+            let prefix = load_config()?.session_prefix.ok_or_else(|| {
+                anyhow::anyhow!("--global-filter needs session_prefix set in ~/.cmux_config.json")
+            })?;
+            sessions.retain(|session| session.name.starts_with(&prefix));
+        }
+        if let Some(key) = self.sort {
+            sort_sessions(&mut sessions, key);
+        }
+        Ok(sessions)
+    }
+}
+
+/// Seconds for `--interval`/`--watch`; 0 means "print once".
+fn parse_interval_secs(value: &str) -> Result<u64, String> {
+    const MAX_INTERVAL_SECS: u64 = 24 * 60 * 60;
+    let secs: u64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a whole number of seconds", value))?;
+    if secs > MAX_INTERVAL_SECS {
+        return Err(format!(
+            "{} seconds is longer than a day (max {})",
+            secs, MAX_INTERVAL_SECS
+        ));
+    }
+    Ok(secs)
+}
+
+fn list_sessions(
+    null: bool,
+    output: OutputFormat,
+    only: Option<String>,
+    source: ListSource,
+    watch: Option<u64>,
+) -> Result<()> {
+    if only.is_some() && output != OutputFormat::Json {
+        return Err(anyhow::anyhow!("--only requires --json (or --output json)"));
+    }
+    let fields = only.as_deref().map(parse_json_fields).transpose()?;
+    // Swap and fds cost a procfs read per process, so only JSON that shows them pays for it
+    let source = ListSource {
+        enrich: EnrichOptions {
+            swap_and_fds: output == OutputFormat::Json
+                && fields.as_ref().is_none_or(|fields| {
+                    fields.iter().any(|field| field == "swap" || field == "fds")
+                }),
+            ..source.enrich
+        },
+        ..source
+    };
+    let sessions = source.fetch()?;
+
+    if null {
+        let mut stdout = io::stdout().lock();
+        for session in &sessions {
+            stdout.write_all(session.name.as_bytes())?;
+            stdout.write_all(b"\0")?;
+        }
+        stdout.flush()?;
         return Ok(());
     }
 
-    println!("Active tmux sessions:");
-    println!("{:<20} {:<10} {:<10}", "Name", "Windows", "Status");
-    println!("{}", "-".repeat(40));
+    let style = if output == OutputFormat::Table {
+        ListStyle {
+            tags: load_tags()?,
+            current: current_tmux_session().ok(),
+            colorize: io::stdout().is_terminal(),
+        }
+    } else {
+        ListStyle::default()
+    };
 
-    for session in sessions {
-        let status = if session.attached {
-            "attached"
-        } else {
-            "detached"
-        };
-        println!(
-            "{:<20} {:<10} {:<10}",
-            session.name, session.windows, status
+    let Some(secs) = watch.filter(|secs| *secs > 0) else {
+        print!(
+            "{}",
+            render_sessions(&sessions, output, fields.as_deref(), &style)?
+        );
+        return Ok(());
+    };
+
+    // Redraw in place instead of clearing, so the list doesn't flicker
+    let mut stdout = io::stdout();
+    execute!(
+        stdout,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+    )?;
+    let mut sessions = sessions;
+    loop {
+        let header = format!(
+            "Every {}s: cmux list    {}\n\n",
+            secs,
+            chrono::Local::now().format("%H:%M:%S")
         );
+        let body = render_sessions(&sessions, output, fields.as_deref(), &style)?;
+        crossterm::queue!(stdout, crossterm::cursor::MoveTo(0, 0))?;
+        for line in header.lines().chain(body.lines()) {
+            crossterm::queue!(
+                stdout,
+                crossterm::style::Print(line),
+                crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine),
+                crossterm::style::Print("\n")
+            )?;
+        }
+        crossterm::queue!(
+            stdout,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::FromCursorDown)
+        )?;
+        stdout.flush()?;
+
+        std::thread::sleep(Duration::from_secs(secs));
+        sessions = source.fetch()?;
     }
+}
 
-    Ok(())
+/// Output formats for `list --output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Aligned table for people
+    Table,
+    /// Pretty-printed JSON array
+    Json,
+    /// One session name per line
+    Plain,
+    /// Header plus one comma-separated row per session
+    Csv,
+    /// One short `name windows status` line per session
+    Compact,
+}
+
+/// Decorations only the table format uses.
+#[derive(Debug, Default)]
+struct ListStyle {
+    tags: HashMap<String, String>,
+    current: Option<String>,
+    colorize: bool,
+}
+
+/// Render `sessions` in `format`. Every line, including the last, ends in a newline.
+fn render_sessions(
+    sessions: &[TmuxSession],
+    format: OutputFormat,
+    fields: Option<&[String]>,
+    style: &ListStyle,
+) -> Result<String> {
+    let mut out = String::new();
+
+    match format {
+        OutputFormat::Json => {
+            let json = match fields {
+                Some(fields) => serde_json::to_string_pretty(&project_sessions(sessions, fields))?,
+                None => serde_json::to_string_pretty(sessions)?,
+            };
+            out.push_str(&json);
+            out.push('\n');
+        }
+        OutputFormat::Plain => {
+            for session in sessions {
+                out.push_str(&session.name);
+                out.push('\n');
+            }
+        }
+        OutputFormat::Csv => {
+            let with_server = sessions.iter().any(|s| s.server.is_some());
+            out.push_str("name,windows,attached,memory_mb,cpu_percent");
+            out.push_str(if with_server { ",server\n" } else { "\n" });
+            for session in sessions {
+                let (memory, cpu) = match session.resource_info {
+                    Some(ref resource) => (
+                        format!("{:.1}", resource.memory_mb),
+                        format!("{:.1}", resource.cpu_percent),
+                    ),
+                    None => (String::new(), String::new()),
+                };
+                out.push_str(&format!(
+                    "{},{},{},{},{}",
+                    csv_field(&session.name),
+                    session.windows,
+                    session.attached,
+                    memory,
+                    cpu
+                ));
+                if with_server {
+                    out.push_str(&format!(
+                        ",{}",
+                        csv_field(session.server.as_deref().unwrap_or(""))
+                    ));
+                }
+                out.push('\n');
+            }
+        }
+        OutputFormat::Compact => {
+            for session in sessions {
+                let status = if session.attached {
+                    "attached"
+                } else {
+                    "detached"
+                };
+                out.push_str(&format!("{} {}w {}", session.name, session.windows, status));
+                if let Some(ref server) = session.server {
+                    out.push_str(&format!(" @{}", server));
+                }
+                out.push('\n');
+            }
+        }
+        OutputFormat::Table => {
+            if sessions.is_empty() {
+                out.push_str("No tmux sessions found.\n");
+                return Ok(out);
+            }
+
+            let with_server = sessions.iter().any(|s| s.server.is_some());
+            out.push_str("Active tmux sessions:\n");
+            if with_server {
+                out.push_str(&format!(
+                    "{:<20} {:<10} {:<10} {:<12}\n",
+                    "Name", "Windows", "Status", "Server"
+                ));
+                out.push_str(&format!("{}\n", "-".repeat(53)));
+            } else {
+                out.push_str(&format!(
+                    "{:<20} {:<10} {:<10}\n",
+                    "Name", "Windows", "Status"
+                ));
+                out.push_str(&format!("{}\n", "-".repeat(40)));
+            }
+
+            for session in sessions {
+                let status = if session.attached {
+                    "attached"
+                } else {
+                    "detached"
+                };
+                let name = pad_display(&truncate_display(&session.name, 20), 20);
+                let name = match style
+                    .tags
+                    .get(&session.name)
+                    .and_then(|tag| tag_ansi_code(tag))
+                {
+                    Some(code) if style.colorize => format!("\x1b[{}m{}\x1b[0m", code, name),
+                    _ => name,
+                };
+                let mut line = format!("{} {:<10} {:<10}", name, session.windows, status);
+                if with_server {
+                    line.push(' ');
+                    line.push_str(&pad_display(
+                        &truncate_display(session.server.as_deref().unwrap_or(""), 12),
+                        12,
+                    ));
+                }
+                if style.current.as_deref() == Some(session.name.as_str()) {
+                    out.push_str(&format!("{} (current)\n", line));
+                } else {
+                    out.push_str(&format!("{}\n", line));
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Quote a CSV field when it contains a comma, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Field names accepted by `list --json --only`.
+const JSON_FIELDS: &[&str] = &[
+    "name", "windows", "attached", "clients", "users", "created", "activity", "pid", "command",
+    "user", "memory", "cpu", "swap", "fds", "server",
+];
+
+fn parse_json_fields(spec: &str) -> Result<Vec<String>> {
+    let fields: Vec<String> = spec
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect();
+
+    if fields.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No fields given. Valid fields: {}",
+            JSON_FIELDS.join(", ")
+        ));
+    }
+    if let Some(unknown) = fields.iter().find(|f| !JSON_FIELDS.contains(&f.as_str())) {
+        return Err(anyhow::anyhow!(
+            "Unknown field '{}'. Valid fields: {}",
+            unknown,
+            JSON_FIELDS.join(", ")
+        ));
+    }
+    Ok(fields)
+}
+
+fn session_json_field(session: &TmuxSession, field: &str) -> serde_json::Value {
+    use serde_json::Value;
+
+    let process = session.process_info.as_ref();
+    let resource = session.resource_info.as_ref();
+    match field {
+        "name" => Value::from(session.name.clone()),
+        "windows" => Value::from(session.windows),
+        "attached" => Value::from(session.attached),
+        "clients" => Value::from(session.attached_clients),
+        "users" => Value::from(session.attached_users.clone()),
+        "created" => Value::from(session.created.clone()),
+        "activity" => Value::from(session.activity.clone()),
+        "pid" => process.and_then(|p| p.pid).map_or(Value::Null, Value::from),
+        "command" => process.map_or(Value::Null, |p| Value::from(p.command.clone())),
+        "user" => process.map_or(Value::Null, |p| Value::from(p.user.clone())),
+        "memory" => resource.map_or(Value::Null, |r| Value::from(r.memory_mb)),
+        "cpu" => resource.map_or(Value::Null, |r| Value::from(r.cpu_percent)),
+        "swap" => resource
+            .and_then(|r| r.swap_kb)
+            .map_or(Value::Null, Value::from),
+        "fds" => resource
+            .and_then(|r| r.open_fds)
+            .map_or(Value::Null, Value::from),
+        "server" => session.server.clone().map_or(Value::Null, Value::from),
+        _ => Value::Null,
+    }
+}
+
+/// Build one JSON object per session containing only the requested fields.
+fn project_sessions(sessions: &[TmuxSession], fields: &[String]) -> Vec<serde_json::Value> {
+    sessions
+        .iter()
+        .map(|session| {
+            let object: serde_json::Map<String, serde_json::Value> = fields
+                .iter()
+                .map(|field| (field.clone(), session_json_field(session, field)))
+                .collect();
+            serde_json::Value::Object(object)
+        })
+        .collect()
+}
+
+/// Tweaks to how `attach_session` invokes `tmux attach-session`.
+#[derive(Debug, Clone, Default)]
+struct AttachOptions {
+    read_only: bool,
+    /// Attach without affecting the session's size
+    no_resize: bool,
+    /// Detach the session's other clients
+    exclusive: bool,
+    /// Print the command for the caller to run instead of attaching
+    print_cmd: bool,
+    /// Raw arguments passed through after `--`
+    extra_args: Vec<String>,
+    /// `WIDTHxHEIGHT` sent on the attaching client once it is attached
+    size: Option<(u16, u16)>,
 }
 
-fn attach_session(session_name: Option<String>) -> Result<()> {
+fn attach_session(session_name: Option<String>, options: &AttachOptions) -> Result<()> {
     let sessions = get_tmux_sessions()?;
+    let config = load_config()?;
+    let attach_size = config
+        .attach_size
+        .as_deref()
+        .map(parse_attach_size)
+        .transpose()?;
+    let options = &AttachOptions {
+        size: attach_size.or(options.size),
+        ..options.clone()
+    };
 
     let target_session = match session_name {
-        Some(name) => name,
+        Some(name) => resolve_attach_name(
+            &name,
+            &sessions,
+            &load_aliases()?,
+            config.session_prefix.as_deref(),
+        ),
         None => {
-            if sessions.is_empty() {
+            if sessions.is_empty() && options.print_cmd {
                 return Err(anyhow::anyhow!("No tmux sessions found"));
             }
+            if sessions.is_empty() {
+                return match config.attach_when_empty {
+                    AttachWhenEmpty::Error => Err(anyhow::anyhow!("No tmux sessions found")),
+                    AttachWhenEmpty::Create => new_session(
+                        Some(default_session_name(&[], config.session_prefix.as_deref())),
+                        None,
+                        false,
+                    ),
+                };
+            }
             sessions[0].name.clone()
         }
     };
 
-    let _ = Command::new("tmux")
+    if options.print_cmd {
+        println!("{}", attach_command_line(&target_session, options));
+        return Ok(());
+    }
+
+    ensure_server_responsive(&target_session)?;
+
+    // With a fixed size the client is sized after attaching, so there is
+    // nothing to warn about
+    if options.size.is_none() && !options.no_resize && !options.exclusive {
+        let other_clients = sessions
+            .iter()
+            .find(|s| s.name == target_session)
+            .map_or(0, |s| s.attached_clients);
+        let session_size = session_size_with_executor(&DefaultTmuxExecutor, &target_session);
+        if let (Some(session_size), Ok(terminal_size)) = (session_size, crossterm::terminal::size())
+        {
+            if let Some(warning) = resize_warning(session_size, terminal_size, other_clients) {
+                eprintln!("{}", warning);
+            }
+        }
+    }
+
+    let _ = tmux_command()
         .args(["set-option", "-g", "detach-on-destroy", "on"])
         .output();
 
-    let status = Command::new("tmux")
-        .args(["attach-session", "-t", &target_session])
-        .status()
-        .context("Failed to execute tmux attach command")?;
+    let mut result = run_attach(&target_session, options)?;
 
-    if !status.success() {
+    // Phone SSH clients often haven't reported a size yet on the first
+    // attempt; retry with the terminal's size sent on the attaching client
+    if let Err(ref stderr) = result {
+        if is_attach_size_error(stderr) {
+            let retry = AttachOptions {
+                size: options.size.or(crossterm::terminal::size().ok()),
+                ..options.clone()
+            };
+            result = run_attach(&target_session, &retry)?;
+        }
+    }
+
+    if let Err(stderr) = result {
+        eprint!("{}", stderr);
         return Err(anyhow::anyhow!(
             "Failed to attach to session '{}'. Session may not exist.",
             target_session
@@ -885,1957 +3313,7931 @@ fn attach_session(session_name: Option<String>) -> Result<()> {
     Ok(())
 }
 
-fn attach_remote_session(host: &HostConfig, session_name: &str) -> Result<()> {
-    let mut cmd = Command::new("ssh");
-    cmd.arg("-t");
-    apply_ssh_args(&mut cmd, host, SSH_ATTACH_TIMEOUT_SECS, false);
-    let remote_cmd = format!(
-        "tmux set-option -g detach-on-destroy on >/dev/null 2>&1; tmux attach-session -t {}",
-        session_name
-    );
-    let status = cmd
-        .arg(remote_cmd)
-        .status()
-        .context("Failed to execute ssh attach command")?;
-
-    if !status.success() {
+/// `cmux switch`: always `switch-client`, never `attach-session`, so it does
+/// the same thing from a key binding as from a shell inside tmux.
+fn switch_session(name: &str) -> Result<()> {
+    if std::env::var_os("TMUX").is_none() {
         return Err(anyhow::anyhow!(
-            "Failed to attach to remote session '{}' on '{}'",
-            session_name,
-            host.name
+            "cmux switch only works inside tmux ($TMUX is not set); use `cmux attach` instead"
         ));
     }
-
-    Ok(())
+    let sessions = list_tmux_sessions_with_executor(&DefaultTmuxExecutor)?;
+    let target = resolve_attach_name(
+        name,
+        &sessions,
+        &load_aliases()?,
+        load_config()?.session_prefix.as_deref(),
+    );
+    if !sessions.iter().any(|session| session.name == target) {
+        return Err(anyhow::anyhow!("Session '{}' not found", target));
+    }
+    switch_client_with_executor(&DefaultTmuxExecutor, &target)
+        .with_context(|| format!("Failed to switch to session '{}'", target))
 }
 
-fn kill_remote_session(host: &HostConfig, session_name: &str) -> Result<()> {
-    let mut cmd = Command::new("ssh");
-    apply_ssh_args(&mut cmd, host, SSH_ACTION_TIMEOUT_SECS, true);
-    let remote_cmd = format!("tmux kill-session -t {}", shell_quote(session_name));
-    let status = cmd
-        .arg(remote_cmd)
-        .status()
-        .context("Failed to execute ssh kill-session command")?;
-
-    if !status.success() {
+/// Fail fast on a wedged server instead of handing it the terminal.
+///
+/// Only a timeout is an error here; a missing session is left for
+/// `attach-session` to report.
+fn ensure_server_responsive(target_session: &str) -> Result<()> {
+    let child = tmux_command()
+        .args(["has-session", "-t", target_session])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute tmux has-session command")?;
+
+    if wait_with_timeout(child, SERVER_RESPONSE_TIMEOUT)?.is_none() {
         return Err(anyhow::anyhow!(
-            "Failed to kill remote session '{}' on '{}'",
-            session_name,
-            host.name
+            "tmux server did not respond within {}s; it may be hung",
+            SERVER_RESPONSE_TIMEOUT.as_secs()
         ));
     }
-
     Ok(())
 }
 
-fn new_session_remote(host: &HostConfig, name: Option<String>) -> Result<()> {
-    let mut cmd = Command::new("ssh");
-    apply_ssh_args(&mut cmd, host, SSH_ATTACH_TIMEOUT_SECS, false);
-
-    let remote_cmd = match name {
-        Some(name) => format!("tmux new-session -d -s {}", shell_quote(&name)),
-        None => "tmux new-session -d".to_string(),
-    };
+/// Wait for `child` up to `timeout`, killing it and returning `None` if it is still running.
+fn wait_with_timeout(
+    mut child: std::process::Child,
+    timeout: Duration,
+) -> Result<Option<std::process::ExitStatus>> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
 
-    let status = cmd
-        .arg(remote_cmd)
-        .status()
-        .context("Failed to execute ssh new-session command")?;
+/// Run `tmux attach-session`, returning tmux's stderr if it fails.
+fn run_attach(
+    target_session: &str,
+    options: &AttachOptions,
+) -> Result<std::result::Result<(), String>> {
+    let output = tmux_command()
+        .args(attach_args(target_session, options))
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to execute tmux attach command")?;
 
-    if !status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to create remote session on '{}'",
-            host.name
-        ));
+    if output.status.success() {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(String::from_utf8_lossy(&output.stderr).to_string()))
     }
-
-    Ok(())
 }
 
-fn new_session(name: Option<String>) -> Result<()> {
-    let mut cmd = Command::new("tmux");
-    cmd.arg("new-session");
+fn attach_args(target_session: &str, options: &AttachOptions) -> Vec<String> {
+    let mut args = vec![
+        "attach-session".to_string(),
+        "-t".to_string(),
+        target_session.to_string(),
+    ];
+    if options.read_only {
+        args.push("-r".to_string());
+    }
+    if options.no_resize {
+        args.extend(["-f".to_string(), "ignore-size".to_string()]);
+    }
+    if options.exclusive {
+        args.push("-d".to_string());
+    }
+    args.extend(options.extra_args.iter().cloned());
+    // Chained so it runs on this client only, after it has attached; other
+    // clients and the windows' own sizes are left alone
+    if let Some((width, height)) = options.size {
+        args.extend([
+            ";".to_string(),
+            "refresh-client".to_string(),
+            "-C".to_string(),
+            format!("{}x{}", width, height),
+        ]);
+    }
+    args
+}
 
-    if let Some(session_name) = name {
-        cmd.args(["-s", &session_name]);
+/// `tmux attach-session ...` as a shell command line, for `attach --print-cmd`.
+fn attach_command_line(target_session: &str, options: &AttachOptions) -> String {
+    std::iter::once("tmux".to_string())
+        .chain(attach_args(target_session, options))
+        .map(|arg| shell_word(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote `value` for a POSIX shell only when it needs it.
+fn shell_word(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "-_./:=@%+,".contains(ch));
+    if plain {
+        value.to_string()
+    } else {
+        shell_quote(value)
     }
+}
 
-    let status = cmd
-        .status()
-        .context("Failed to execute tmux new-session command")?;
+/// Session a name given to `attach` refers to: an exact session name first,
+/// then an alias from `cmux alias`, then the `session_prefix`-ed name.
+fn resolve_attach_name(
+    name: &str,
+    sessions: &[TmuxSession],
+    aliases: &HashMap<String, String>,
+    prefix: Option<&str>,
+) -> String {
+    if sessions.iter().any(|session| session.name == name) {
+        return name.to_string();
+    }
+    let name = aliases.get(name).map_or(name, String::as_str);
+    resolve_prefixed_name(prefix, name, sessions)
+}
 
-    if !status.success() {
+/// Active window name for every session, fetched with one `list-windows -a`.
+fn active_window_names_with_executor(executor: &dyn TmuxExecutor) -> HashMap<String, String> {
+    let format =
+        ["#{window_active}", "#{session_name}", "#{window_name}"].join(LIST_FIELD_SEPARATOR);
+    match executor.execute_command(&["list-windows", "-a", "-F", &format]) {
+        Ok(output) if output.status.success() => {
+            parse_active_windows(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => HashMap::new(),
+    }
+}
+
+fn parse_active_windows(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, LIST_FIELD_SEPARATOR);
+            let active = parts.next()?;
+            let session = parts.next()?;
+            let window = parts.next()?;
+            (active == "1").then(|| (session.to_string(), window.to_string()))
+        })
+        .collect()
+}
+
+/// `attach --here`: attach to the session whose active pane is in the
+/// current directory, or create one there named after the directory.
+fn attach_here(options: &AttachOptions) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    let dirs = session_dirs_with_executor(&DefaultTmuxExecutor);
+    if let Some(name) = session_for_dir(&dirs, &cwd) {
+        return attach_session(Some(name), options);
+    }
+    if options.print_cmd {
+        return Err(anyhow::anyhow!("No session in {}", cwd.display()));
+    }
+
+    let existing: Vec<String> = dirs.into_iter().map(|(name, _, _)| name).collect();
+    let prefix = load_config()?.session_prefix;
+    let name = here_session_name(&cwd, &existing, prefix.as_deref());
+    check_session_capacity(&DefaultTmuxExecutor, false)?;
+    invalidate_session_cache();
+    let dir = cwd.to_string_lossy();
+    let output =
+        DefaultTmuxExecutor.execute_command(&["new-session", "-d", "-s", &name, "-c", &dir])?;
+    if !output.status.success() {
         return Err(anyhow::anyhow!(
-            "Failed to create new tmux session. Session name may already exist."
+            "Failed to create session '{}': {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
         ));
     }
-
-    Ok(())
+    attach_session(Some(name), options)
 }
 
-fn kill_session(session_name: Option<String>) -> Result<()> {
-    let target_session = match session_name {
-        Some(name) => name,
-        None => {
-            // In interactive mode, we'd select, but in CLI mode, refuse to kill without name
-            return Err(anyhow::anyhow!("Please specify a session name to kill"));
+/// `(session, activity, active pane path)` for every session.
+fn session_dirs_with_executor(executor: &dyn TmuxExecutor) -> Vec<(String, u64, String)> {
+    let format = [
+        "#{session_name}",
+        "#{session_activity}",
+        "#{pane_current_path}",
+    ]
+    .join(LIST_FIELD_SEPARATOR);
+    match executor.execute_command(&["list-sessions", "-F", &format]) {
+        Ok(output) if output.status.success() => {
+            parse_session_dirs(&String::from_utf8_lossy(&output.stdout))
         }
+        _ => Vec::new(),
+    }
+}
+
+fn parse_session_dirs(output: &str) -> Vec<(String, u64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, LIST_FIELD_SEPARATOR);
+            let name = parts.next()?;
+            let activity = parts.next()?.parse().unwrap_or(0);
+            let path = parts.next()?;
+            Some((name.to_string(), activity, path.to_string()))
+        })
+        .collect()
+}
+
+/// The most recently active session whose pane is in `dir`, comparing
+/// canonical paths so symlinked directories still match.
+fn session_for_dir(dirs: &[(String, u64, String)], dir: &Path) -> Option<String> {
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = canonical(dir);
+    dirs.iter()
+        .filter(|(_, _, path)| !path.is_empty() && canonical(Path::new(path)) == dir)
+        .max_by_key(|(_, activity, _)| *activity)
+        .map(|(name, _, _)| name.clone())
+}
+
+/// Name for a session created by `attach --here`: the directory's name with
+/// the characters tmux rejects (`.` and `:`) replaced, made unique.
+fn here_session_name(dir: &Path, existing: &[String], prefix: Option<&str>) -> String {
+    let Some(base) = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().replace(['.', ':'], "_"))
+    else {
+        return default_session_name(existing, prefix);
+    };
+    let base = apply_session_prefix(prefix, &base);
+    if existing.contains(&base) {
+        suggest_unique_name(&base, existing)
+    } else {
+        base
+    }
+}
+
+/// Parse the `attach_size` preference, e.g. `120x40`.
+fn parse_attach_size(size: &str) -> Result<(u16, u16)> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid attach_size '{}' in config (expected WIDTHxHEIGHT, e.g. 120x40)",
+            size
+        )
     };
+    let (width, height) = size.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: u16 = width.parse().map_err(|_| invalid())?;
+    let height: u16 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+/// Current size of the session's active window as `(cols, rows)`.
+fn session_size_with_executor(executor: &dyn TmuxExecutor, session: &str) -> Option<(u16, u16)> {
+    let target = format!("={}:", session);
+    let output = executor
+        .execute_command(&[
+            "display-message",
+            "-p",
+            "-t",
+            &target,
+            "#{window_width}x#{window_height}",
+        ])
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let size = String::from_utf8_lossy(&output.stdout);
+    let (cols, rows) = size.trim().split_once('x')?;
+    Some((cols.parse().ok()?, rows.parse().ok()?))
+}
+
+/// tmux sizes a session to its smallest client, so attaching from a smaller
+/// terminal while someone else is attached shrinks their view too.
+fn resize_warning(
+    session_size: (u16, u16),
+    terminal_size: (u16, u16),
+    other_clients: usize,
+) -> Option<String> {
+    if other_clients == 0 {
+        return None;
+    }
+    let cols = session_size.0.min(terminal_size.0);
+    let rows = session_size.1.min(terminal_size.1);
+    if (cols, rows) == session_size {
+        return None;
+    }
+    Some(format!(
+        "Attaching will resize the session to {}x{}; use --read-only or --no-resize to avoid",
+        cols, rows
+    ))
+}
 
-    let status = Command::new("tmux")
-        .args(["kill-session", "-t", &target_session])
+/// Whether `attach-session` failed because the terminal reported no usable size.
+fn is_attach_size_error(stderr: &str) -> bool {
+    const SIZE_MESSAGES: &[&str] = &["size missing", "terminal too small"];
+    let stderr = stderr.to_lowercase();
+    SIZE_MESSAGES.iter().any(|message| stderr.contains(message))
+}
+
+fn attach_remote_session(host: &HostConfig, session_name: &str) -> Result<()> {
+    let mut cmd = Command::new("ssh");
+    cmd.arg("-t");
+    apply_ssh_args(&mut cmd, host, SSH_ATTACH_TIMEOUT_SECS, false);
+    let remote_cmd = format!(
+        "tmux set-option -g detach-on-destroy on >/dev/null 2>&1; tmux attach-session -t {}",
+        session_name
+    );
+    let status = cmd
+        .arg(remote_cmd)
         .status()
-        .context("Failed to execute tmux kill-session command")?;
+        .context("Failed to execute ssh attach command")?;
 
     if !status.success() {
         return Err(anyhow::anyhow!(
-            "Failed to kill session '{}'. Session may not exist.",
-            target_session
+            "Failed to attach to remote session '{}' on '{}'",
+            session_name,
+            host.name
         ));
     }
 
-    println!("Killed session: {}", target_session);
     Ok(())
 }
 
-fn rename_session(old_name: &str, new_name: &str) -> Result<()> {
-    let status = Command::new("tmux")
-        .args(["rename-session", "-t", old_name, new_name])
+fn kill_remote_session(host: &HostConfig, session_name: &str) -> Result<()> {
+    let mut cmd = Command::new("ssh");
+    apply_ssh_args(&mut cmd, host, SSH_ACTION_TIMEOUT_SECS, true);
+    let remote_cmd = format!("tmux kill-session -t {}", shell_quote(session_name));
+    let status = cmd
+        .arg(remote_cmd)
         .status()
-        .context("Failed to execute tmux rename command")?;
+        .context("Failed to execute ssh kill-session command")?;
 
     if !status.success() {
         return Err(anyhow::anyhow!(
-            "Failed to rename session '{}' to '{}'. Session may not exist.",
-            old_name,
-            new_name
+            "Failed to kill remote session '{}' on '{}'",
+            session_name,
+            host.name
         ));
     }
 
-    println!("Renamed session '{}' to '{}'", old_name, new_name);
     Ok(())
 }
 
-fn restore_sessions(file: Option<PathBuf>) -> Result<()> {
-    let snapshot_path = file.unwrap_or_else(|| {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        PathBuf::from(home).join(".cmux_snapshot.json")
-    });
+fn new_session_remote(host: &HostConfig, name: Option<String>) -> Result<()> {
+    let mut cmd = Command::new("ssh");
+    apply_ssh_args(&mut cmd, host, SSH_ATTACH_TIMEOUT_SECS, false);
 
-    let content = fs::read_to_string(&snapshot_path).context("Failed to read snapshot file")?;
+    let remote_cmd = match name {
+        Some(name) => format!("tmux new-session -d -s {}", shell_quote(&name)),
+        None => "tmux new-session -d".to_string(),
+    };
 
-    let snapshot: SessionSnapshot =
-        serde_json::from_str(&content).context("Failed to parse snapshot file")?;
+    let status = cmd
+        .arg(remote_cmd)
+        .status()
+        .context("Failed to execute ssh new-session command")?;
 
-    println!(
-        "Restoring {} sessions from snapshot...",
-        snapshot.sessions.len()
-    );
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to create remote session on '{}'",
+            host.name
+        ));
+    }
 
-    for session in snapshot.sessions {
-        if get_tmux_sessions()?.iter().any(|s| s.name == session.name) {
-            println!("Session '{}' already exists, skipping...", session.name);
-            continue;
-        }
+    Ok(())
+}
 
-        Command::new("tmux")
-            .args(["new-session", "-d", "-s", &session.name])
-            .status()
-            .context("Failed to create session")?;
+/// Refuse to create another local session once `max_sessions` exist. Every
+/// local creation path calls this first; `force` is `cmux new --force`.
+fn check_session_capacity(executor: &dyn TmuxExecutor, force: bool) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+    let config = load_config()?;
+    if config.max_sessions.is_none() {
+        return Ok(());
+    }
+    config.check_session_limit(list_tmux_sessions_with_executor(executor)?.len())
+}
 
-        println!("Restored session: {}", session.name);
+fn new_session(name: Option<String>, shell: Option<&Path>, force: bool) -> Result<()> {
+    if let Some(shell) = shell {
+        validate_shell(shell)?;
     }
+    check_session_capacity(&DefaultTmuxExecutor, force)?;
 
-    Ok(())
-}
+    invalidate_session_cache();
+    let mut cmd = tmux_command();
+    cmd.arg("new-session");
 
-fn save_snapshot() -> Result<PathBuf> {
-    let sessions = get_tmux_sessions()?;
-    let snapshot = SessionSnapshot {
-        sessions,
-        timestamp: chrono::Local::now().to_rfc3339(),
-    };
+    if let Some(session_name) = name {
+        cmd.args(["-s", &session_name]);
+    }
+    if let Some(shell) = shell {
+        cmd.arg(shell);
+    }
 
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let snapshot_path = PathBuf::from(home).join(".cmux_snapshot.json");
+    let status = cmd
+        .status()
+        .context("Failed to execute tmux new-session command")?;
 
-    let json = serde_json::to_string_pretty(&snapshot)?;
-    fs::write(&snapshot_path, json)?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to create new tmux session. Session name may already exist."
+        ));
+    }
 
-    Ok(snapshot_path)
+    Ok(())
 }
 
-fn load_aliases() -> Result<HashMap<String, String>> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let alias_path = PathBuf::from(home).join(".cmux_aliases.json");
+/// Create a session whose environment omits `vars`, then attach to it.
+///
+/// The session starts detached so the variables can be marked for removal
+/// (`set-environment -r`) and the first window respawned without them.
+fn new_clean_env_session(
+    name: Option<String>,
+    shell: Option<&Path>,
+    vars: &[String],
+    force: bool,
+) -> Result<()> {
+    if let Some(shell) = shell {
+        validate_shell(shell)?;
+    }
+    check_session_capacity(&DefaultTmuxExecutor, force)?;
 
-    if !alias_path.exists() {
-        return Ok(HashMap::new());
+    invalidate_session_cache();
+    let mut cmd = tmux_command();
+    cmd.args(["new-session", "-d", "-P", "-F", "#{session_name}"]);
+    if let Some(ref session_name) = name {
+        cmd.args(["-s", session_name]);
+    }
+    if let Some(shell) = shell {
+        cmd.arg(shell);
     }
 
-    let content = fs::read_to_string(&alias_path)?;
-    let aliases: HashMap<String, String> = serde_json::from_str(&content)?;
-    Ok(aliases)
-}
+    let output = cmd
+        .output()
+        .context("Failed to execute tmux new-session command")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to create new tmux session: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let created = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-fn save_aliases(aliases: &HashMap<String, String>) -> Result<()> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let alias_path = PathBuf::from(home).join(".cmux_aliases.json");
+    scrub_session_env_with_executor(&DefaultTmuxExecutor, &created, vars)?;
 
-    let json = serde_json::to_string_pretty(aliases)?;
-    fs::write(&alias_path, json)?;
+    let status = tmux_command()
+        .args(["attach-session", "-t", &created])
+        .status()
+        .context("Failed to execute tmux attach command")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Created session '{}' but failed to attach to it",
+            created
+        ));
+    }
     Ok(())
 }
 
-fn hosts_config_path() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home).join(".cmux_hosts.toml")
-}
-
-fn load_hosts() -> Result<Vec<HostConfig>> {
-    let path = hosts_config_path();
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
-    let content = fs::read_to_string(&path)?;
-    if content.trim().is_empty() {
-        return Ok(Vec::new());
+fn scrub_session_env_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    vars: &[String],
+) -> Result<()> {
+    for var in vars {
+        let output = executor.execute_command(&["set-environment", "-t", session, "-r", var])?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to remove {} from session '{}': {}",
+                var,
+                session,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
     }
-    let config: HostsConfig = toml::from_str(&content).context("Failed to parse hosts config")?;
-    Ok(config.hosts)
-}
 
-fn save_hosts(hosts: &[HostConfig]) -> Result<()> {
-    let config = HostsConfig {
-        hosts: hosts.to_vec(),
-    };
-    let path = hosts_config_path();
-    let content = toml::to_string_pretty(&config).context("Failed to serialize hosts config")?;
-    fs::write(&path, content)?;
+    // The first window was spawned before the removals, so restart it
+    let target = format!("{}:", session);
+    let output = executor.execute_command(&["respawn-window", "-k", "-t", &target])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to respawn window in session '{}': {}",
+            session,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
     Ok(())
 }
 
-fn add_host_config(host: HostConfig) -> Result<()> {
-    let mut hosts = load_hosts()?;
-    if hosts.iter().any(|h| h.name == host.name) {
-        return Err(anyhow::anyhow!("Host '{}' already exists", host.name));
+fn validate_shell(shell: &Path) -> Result<()> {
+    if !shell.is_file() {
+        return Err(anyhow::anyhow!(
+            "Shell '{}' does not exist or is not a file",
+            shell.display()
+        ));
     }
-    hosts.push(host);
-    save_hosts(&hosts)?;
     Ok(())
 }
 
-fn remove_host_config(name: &str) -> Result<()> {
-    let mut hosts = load_hosts()?;
-    let original_len = hosts.len();
-    hosts.retain(|h| h.name != name);
-    if hosts.len() == original_len {
-        return Err(anyhow::anyhow!("Host '{}' not found", name));
+/// Name used when the user doesn't pick one, e.g. `session-142501`, with a
+/// `-2`, `-3`, ... suffix when another session was created in the same second.
+fn default_session_name(existing: &[String], prefix: Option<&str>) -> String {
+    let base = apply_session_prefix(
+        prefix,
+        &format!("session-{}", chrono::Local::now().format("%H%M%S")),
+    );
+    if existing.contains(&base) {
+        suggest_unique_name(&base, existing)
+    } else {
+        base
     }
-    save_hosts(&hosts)?;
-    Ok(())
 }
 
-fn list_hosts() -> Result<()> {
-    let hosts = load_hosts()?;
-    if hosts.is_empty() {
-        println!("No remote hosts configured.");
-        return Ok(());
+/// Prepend the configured `session_prefix` unless `name` already carries it.
+fn apply_session_prefix(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) if !name.starts_with(prefix) => format!("{}{}", prefix, name),
+        _ => name.to_string(),
     }
+}
 
-    println!("Remote hosts:");
-    println!("{:<16} {:<24} Key", "Name", "Host");
-    println!("{}", "-".repeat(60));
-    for host in hosts {
-        let key = host.key.unwrap_or_else(|| "default".to_string());
-        println!("{:<16} {:<24} {}", host.name, host.host, key);
+/// Let users type session names without the `session_prefix`: an exact match
+/// wins, then the prefixed name, otherwise `name` is passed through unchanged.
+fn resolve_prefixed_name(prefix: Option<&str>, name: &str, sessions: &[TmuxSession]) -> String {
+    let exists = |candidate: &str| sessions.iter().any(|s| s.name == candidate);
+    if exists(name) {
+        return name.to_string();
+    }
+    let prefixed = apply_session_prefix(prefix, name);
+    if exists(&prefixed) {
+        prefixed
+    } else {
+        name.to_string()
     }
-    Ok(())
 }
 
-fn manage_hosts(command: HostCommands) -> Result<()> {
-    match command {
-        HostCommands::Add { name, host, key } => {
-            add_host_config(HostConfig { name, host, key })?;
-            println!("Added host.");
-        }
-        HostCommands::Remove { name } => {
-            remove_host_config(&name)?;
-            println!("Removed host '{}'.", name);
-        }
-        HostCommands::List => list_hosts()?,
+/// Window names for `new --windows N --window-names a,b`, one entry per window
+/// (`None` leaves tmux's automatic name).
+fn window_plan(count: Option<u16>, names: &[String]) -> Result<Vec<Option<String>>> {
+    let names: Vec<Option<String>> = names
+        .iter()
+        .map(|name| Some(name.trim().to_string()).filter(|name| !name.is_empty()))
+        .collect();
+    match count {
+        None => Ok(names),
+        Some(count) if names.is_empty() => Ok(vec![None; usize::from(count)]),
+        Some(count) if names.len() == usize::from(count) => Ok(names),
+        Some(count) => Err(anyhow::anyhow!(
+            "--window-names has {} name(s) but --windows is {}",
+            names.len(),
+            count
+        )),
     }
-    Ok(())
 }
 
-fn manage_alias(name: Option<String>, session: Option<String>) -> Result<()> {
-    let mut aliases = load_aliases()?;
+/// Create a detached session whose windows follow `plan`.
+fn new_session_with_windows_with_executor(
+    executor: &dyn TmuxExecutor,
+    name: &str,
+    plan: &[Option<String>],
+    force: bool,
+) -> Result<()> {
+    check_session_capacity(executor, force)?;
+    invalidate_session_cache();
+    let mut args = vec!["new-session", "-d", "-s", name];
+    if let Some(Some(first)) = plan.first() {
+        args.extend(["-n", first.as_str()]);
+    }
+    let output = executor.execute_command(&args)?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to create session '{}': {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
 
-    match (name, session) {
-        (Some(alias_name), Some(session_name)) => {
-            aliases.insert(alias_name.clone(), session_name.clone());
-            save_aliases(&aliases)?;
-            println!(
-                "Created alias '{}' for session '{}'",
-                alias_name, session_name
-            );
-        }
-        (Some(alias_name), None) => {
-            if let Some(session_name) = aliases.get(&alias_name) {
-                println!("{} -> {}", alias_name, session_name);
-            } else {
-                println!("Alias '{}' not found", alias_name);
-            }
-        }
-        (None, None) => {
-            if aliases.is_empty() {
-                println!("No aliases defined");
-            } else {
-                println!("Current aliases:");
-                for (alias, session) in aliases {
-                    println!("  {} -> {}", alias, session);
-                }
-            }
+    let target = format!("={}:", name);
+    for (index, window) in plan.iter().enumerate().skip(1) {
+        let mut args = vec!["new-window", "-d", "-t", target.as_str()];
+        if let Some(window) = window {
+            args.extend(["-n", window.as_str()]);
         }
-        _ => {
-            return Err(anyhow::anyhow!("Invalid alias command"));
+        let output = executor.execute_command(&args)?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Created session '{}' but failed to add window {}: {}",
+                name,
+                index + 1,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
         }
     }
+    Ok(())
+}
+
+/// Create a session in the background without attaching, so the TUI keeps the terminal.
+fn new_detached_session(name: &str, force: bool) -> Result<()> {
+    check_session_capacity(&DefaultTmuxExecutor, force)?;
+    invalidate_session_cache();
+    let output = tmux_command()
+        .args(["new-session", "-d", "-s", name])
+        .output()
+        .context("Failed to execute tmux new-session command")?;
 
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
     Ok(())
 }
 
-fn show_session_info(session_name: Option<String>) -> Result<()> {
-    let sessions = get_tmux_sessions()?;
+/// Create a detached session that shares windows with `group` (`tmux new-session -t`)
+/// and return its name.
+fn new_grouped_session(name: Option<String>, group: &str, force: bool) -> Result<String> {
+    check_session_capacity(&DefaultTmuxExecutor, force)?;
+    invalidate_session_cache();
+    let mut cmd = tmux_command();
+    cmd.args(["new-session", "-d", "-P", "-F", "#{session_name}"]);
+    if let Some(ref session_name) = name {
+        cmd.args(["-s", session_name]);
+    }
+    cmd.args(["-t", group]);
+
+    let output = cmd
+        .output()
+        .context("Failed to execute tmux new-session command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!(
+            "Failed to create session grouped with '{}': {}",
+            group,
+            stderr.trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
+fn kill_session(session_name: Option<String>, confirm: bool) -> Result<()> {
     let target_session = match session_name {
-        Some(name) => sessions
-            .into_iter()
-            .find(|s| s.name == name)
-            .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", name))?,
-        None => {
-            if sessions.is_empty() {
-                return Err(anyhow::anyhow!("No tmux sessions found"));
+        Some(name) => {
+            let prefix = load_config()?.session_prefix;
+            match prefix {
+                Some(prefix) => resolve_prefixed_name(Some(&prefix), &name, &get_tmux_sessions()?),
+                None => name,
             }
-            sessions.into_iter().next().unwrap()
+        }
+        None => {
+            // In interactive mode, we'd select, but in CLI mode, refuse to kill without name
+            return Err(anyhow::anyhow!("Please specify a session name to kill"));
         }
     };
 
-    println!("Session Information:");
-    println!("  Name: {}", target_session.name);
-    println!("  Windows: {}", target_session.windows);
-    println!(
-        "  Status: {}",
-        if target_session.attached {
-            "attached"
-        } else {
-            "detached"
+    let is_current = current_tmux_session().is_ok_and(|current| current == target_session);
+    if is_current && io::stdin().is_terminal() {
+        if !read_confirmation(&own_session_kill_question(&target_session))? {
+            println!("Cancelled.");
+            return Ok(());
         }
-    );
-    println!("  Created: {}", target_session.created);
-    println!("  Last Activity: {}", target_session.activity);
+    } else if confirm {
+        ensure_interactive_confirm()?;
+        if !read_confirmation(&format!("Kill session '{}'?", target_session))? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
 
-    // Get window details
-    let output = Command::new("tmux")
-        .args([
-            "list-windows",
-            "-t",
-            &target_session.name,
-            "-F",
-            "#{window_index}: #{window_name} (#{window_panes} panes)",
-        ])
-        .output()?;
+    kill_local_session(&target_session)?;
+    println!("Killed session: {}", target_session);
+    Ok(())
+}
 
-    if output.status.success() {
-        println!("\nWindows:");
-        let windows = String::from_utf8_lossy(&output.stdout);
-        for window in windows.lines() {
-            println!("  {}", window);
-        }
+/// Kill a local session without printing, so the TUI can report the result itself.
+fn kill_local_session(session_name: &str) -> Result<()> {
+    invalidate_session_cache();
+    let output = tmux_command()
+        .args(["kill-session", "-t", session_name])
+        .output()
+        .context("Failed to execute tmux kill-session command")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to kill session '{}'. Session may not exist.",
+            session_name
+        ));
     }
 
     Ok(())
 }
 
-fn kill_all_sessions() -> Result<()> {
-    let sessions = get_tmux_sessions()?;
+fn rename_session(old_name: &str, new_name: &str) -> Result<()> {
+    invalidate_session_cache();
+    let status = tmux_command()
+        .args(["rename-session", "-t", old_name, new_name])
+        .status()
+        .context("Failed to execute tmux rename command")?;
 
-    if sessions.is_empty() {
-        println!("No tmux sessions to kill.");
-        return Ok(());
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to rename session '{}' to '{}'. Session may not exist.",
+            old_name,
+            new_name
+        ));
     }
 
-    println!("This will kill {} sessions:", sessions.len());
-    for session in &sessions {
-        println!("  - {}", session.name);
+    println!("Renamed session '{}' to '{}'", old_name, new_name);
+    Ok(())
+}
+
+/// How `rename --prefix`/`--replace` derive new names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BulkRename {
+    Prefix(String),
+    Replace(String, String),
+}
+
+impl BulkRename {
+    fn apply(&self, name: &str) -> String {
+        match self {
+            BulkRename::Prefix(prefix) => apply_session_prefix(Some(prefix), name),
+            BulkRename::Replace(from, to) => name.replace(from.as_str(), to),
+        }
     }
+}
 
-    print!("\nAre you sure? (y/N): ");
-    io::stdout().flush()?;
+/// `(old, new)` pairs for a bulk rename of `names`, ordered so each rename's
+/// target is free by the time it runs. Fails before anything is renamed if
+/// two sessions would end up with the same name.
+fn plan_bulk_rename(
+    names: &[String],
+    rule: &BulkRename,
+    only_matching: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    let mut pending: Vec<(String, String)> = names
+        .iter()
+        .filter(|name| only_matching.is_none_or(|text| name.contains(text)))
+        .map(|name| (name.clone(), rule.apply(name)))
+        .filter(|(old, new)| old != new)
+        .collect();
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+    let mut final_names: HashMap<&str, &str> = HashMap::new();
+    for name in names {
+        let new = pending
+            .iter()
+            .find(|(old, _)| old == name)
+            .map_or(name.as_str(), |(_, new)| new.as_str());
+        if new.is_empty() {
+            return Err(anyhow::anyhow!(
+                "'{}' would be renamed to an empty name",
+                name
+            ));
+        }
+        if let Some(other) = final_names.insert(new, name) {
+            return Err(anyhow::anyhow!(
+                "'{}' and '{}' would both be named '{}'; nothing was renamed",
+                other,
+                name,
+                new
+            ));
+        }
+    }
 
-    if input.trim().to_lowercase() != "y" {
-        println!("Cancelled.");
+    // Rename into names that nobody holds any more first, so chains like
+    // a -> aa, aa -> aaaa run back to front
+    let mut ordered = Vec::new();
+    let mut taken: HashSet<String> = names.iter().cloned().collect();
+    while !pending.is_empty() {
+        let Some(index) = pending.iter().position(|(_, new)| !taken.contains(new)) else {
+            return Err(anyhow::anyhow!("Renames form a cycle; nothing was renamed"));
+        };
+        let (old, new) = pending.remove(index);
+        taken.remove(&old);
+        taken.insert(new.clone());
+        ordered.push((old, new));
+    }
+    Ok(ordered)
+}
+
+fn bulk_rename_sessions(rule: &BulkRename, only_matching: Option<&str>, yes: bool) -> Result<()> {
+    let names: Vec<String> = get_tmux_sessions()?.into_iter().map(|s| s.name).collect();
+    let plan = plan_bulk_rename(&names, rule, only_matching)?;
+    if plan.is_empty() {
+        println!("No sessions to rename");
         return Ok(());
     }
 
-    for session in sessions {
-        Command::new("tmux")
-            .args(["kill-session", "-t", &session.name])
-            .status()?;
-        println!("Killed: {}", session.name);
+    for (old, new) in &plan {
+        println!("  {} → {}", old, new);
+    }
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err(anyhow::anyhow!(
+                "Pass --yes to rename without a terminal to confirm on"
+            ));
+        }
+        let question = format!(
+            "Rename {} session{}?",
+            plan.len(),
+            if plan.len() == 1 { "" } else { "s" }
+        );
+        if !read_confirmation(&question)? {
+            println!("Rename cancelled");
+            return Ok(());
+        }
     }
 
-    println!("All sessions killed.");
+    invalidate_session_cache();
+    for (old, new) in &plan {
+        let target = format!("={}", old);
+        let output =
+            DefaultTmuxExecutor.execute_command(&["rename-session", "-t", &target, new])?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to rename '{}' to '{}': {}",
+                old,
+                new,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    println!(
+        "Renamed {} session{}",
+        plan.len(),
+        if plan.len() == 1 { "" } else { "s" }
+    );
     Ok(())
 }
 
-fn run_top_mode() -> Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Whether a session called exactly `name` exists. No server counts as "no".
+fn session_exists_with_executor(executor: &dyn TmuxExecutor, name: &str) -> Result<bool> {
+    // `=` disables tmux's prefix matching, so `dev` doesn't match `dev2`
+    let target = format!("={}", name);
+    let output = executor.execute_command(&["has-session", "-t", &target])?;
+    Ok(output.status.success())
+}
 
-    let mut app = App::new()?;
-    let mut last_refresh = std::time::Instant::now();
+/// The session's `history-limit`, falling back to the global value when the
+/// session doesn't override it.
+fn history_limit_with_executor(executor: &dyn TmuxExecutor, session: &str) -> Result<Option<u64>> {
+    let target = format!("={}:", session);
+    for args in [
+        vec!["show-options", "-v", "-t", target.as_str(), "history-limit"],
+        vec!["show-options", "-gv", "history-limit"],
+    ] {
+        let output = executor.execute_command(&args)?;
+        if !output.status.success() {
+            continue;
+        }
+        if let Ok(limit) = String::from_utf8_lossy(&output.stdout).trim().parse() {
+            return Ok(Some(limit));
+        }
+    }
+    Ok(None)
+}
 
-    loop {
-        // Auto-refresh periodically so new sessions appear without input
-        if last_refresh.elapsed() >= AUTO_REFRESH_INTERVAL {
-            app.refresh()?;
-            last_refresh = std::time::Instant::now();
+fn set_history_limit_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    lines: u64,
+) -> Result<()> {
+    let target = format!("={}:", session);
+    let lines = lines.to_string();
+    let output =
+        executor.execute_command(&["set-option", "-t", &target, "history-limit", &lines])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to set history-limit for '{}': {}",
+            session,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// The `remain-on-exit` window option of the session's current window,
+/// falling back to the global value.
+fn remain_on_exit_with_executor(executor: &dyn TmuxExecutor, session: &str) -> Result<bool> {
+    let target = format!("={}:", session);
+    for args in [
+        vec![
+            "show-options",
+            "-wv",
+            "-t",
+            target.as_str(),
+            "remain-on-exit",
+        ],
+        vec!["show-options", "-gwv", "remain-on-exit"],
+    ] {
+        let output = executor.execute_command(&args)?;
+        if !output.status.success() {
+            continue;
+        }
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "on" | "failed" => return Ok(true),
+            "off" => return Ok(false),
+            _ => continue,
+        }
+    }
+    Ok(false)
+}
+
+/// Ways the session's options differ from `expect`, one line each.
+fn session_option_findings(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    expect: &SessionExpectations,
+) -> Result<Vec<String>> {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let mut findings = Vec::new();
+    if let Some(want) = expect.mouse {
+        let mouse = mouse_mode_with_executor(executor, session)?;
+        if mouse != want {
+            findings.push(format!(
+                "mouse is {} (expected {})",
+                on_off(mouse),
+                on_off(want)
+            ));
+        }
+    }
+    if let Some(limit) = history_limit_with_executor(executor, session)? {
+        if limit < expect.min_history_limit {
+            findings.push(format!(
+                "history-limit is {} (expected at least {})",
+                limit, expect.min_history_limit
+            ));
         }
+    }
+    let remain = remain_on_exit_with_executor(executor, session)?;
+    if remain != expect.remain_on_exit {
+        findings.push(format!(
+            "remain-on-exit is {} (expected {})",
+            on_off(remain),
+            on_off(expect.remain_on_exit)
+        ));
+    }
+    Ok(findings)
+}
 
-        terminal.draw(|f| draw_top_ui(f, &app))?;
+fn run_doctor(audit_sessions: bool) -> Result<()> {
+    let tmux = tmux_command()
+        .arg("-V")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    match tmux {
+        Some(version) => println!("ok    {}", version),
+        None => return Err(anyhow::anyhow!("tmux was not found on PATH")),
+    }
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => break,
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
-                    KeyCode::Char('r') => {
-                        app.refresh()?;
-                        last_refresh = std::time::Instant::now();
-                    }
-                    _ => {}
-                }
+    let config = match load_config() {
+        Ok(config) => {
+            if config_path().exists() {
+                println!("ok    config {}", config_path().display());
+            } else {
+                println!("ok    no config file, using defaults");
             }
+            config
         }
-    }
+        Err(err) => {
+            println!("error {:#}", err);
+            Config::default()
+        }
+    };
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    let sessions = get_tmux_sessions()?;
+    if sessions.is_empty() {
+        println!("warn  no tmux server running");
+        return Ok(());
+    }
+    println!("ok    server running ({} sessions)", sessions.len());
+    if !audit_sessions {
+        return Ok(());
+    }
 
+    let mut flagged = 0;
+    for session in &sessions {
+        let findings = session_option_findings(
+            &DefaultTmuxExecutor,
+            &session.name,
+            &config.session_expectations,
+        )?;
+        if findings.is_empty() {
+            continue;
+        }
+        flagged += 1;
+        for finding in findings {
+            println!("warn  {}: {}", session.name, finding);
+        }
+    }
+    if flagged == 0 {
+        println!("ok    all sessions match session_expectations");
+    }
     Ok(())
 }
 
-fn draw_top_ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Min(5),
-            Constraint::Length(3),
-        ])
-        .split(f.size());
+/// `on`/`off` argument for option-toggling commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Toggle {
+    On,
+    Off,
+}
 
-    // Header with system info
-    let total_sessions = app.sessions.len();
-    let active_sessions = app.sessions.iter().filter(|s| s.attached).count();
-    let header_text = format!(
-        "crabmux - Live Overview | {} total, {} active | {}",
-        total_sessions,
-        active_sessions,
-        chrono::Local::now().format("%H:%M:%S")
-    );
-    let header = Paragraph::new(header_text)
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(header, chunks[0]);
-
-    // Session list with detailed info
-    let sessions: Vec<ListItem> = app
-        .sessions
-        .iter()
-        .map(|s| {
-            let status = if s.attached { "●" } else { "○" };
-            let user = format_attached_users(s);
-            let (memory_info, cpu_info) = if let Some(ref resource) = s.resource_info {
-                (
-                    format!("{:.1}MB", resource.memory_mb),
-                    format!("{:.1}%", resource.cpu_percent),
-                )
-            } else {
-                ("N/A".to_string(), "N/A".to_string())
-            };
-
-            let content = Line::from(vec![
-                Span::styled(
-                    "▶ ",
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(
-                    status,
-                    Style::default().fg(if s.attached { Color::Green } else { Color::Red }),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{:<12}", s.name),
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{}W", s.windows),
-                    Style::default().fg(Color::Yellow),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{:<8}", memory_info),
-                    Style::default().fg(Color::Cyan),
-                ),
-                Span::raw(" "),
-                Span::styled(
-                    format!("{:<6}", cpu_info),
-                    Style::default().fg(Color::Magenta),
-                ),
-                Span::raw(" "),
-                Span::styled(format!("{:<8}", user), Style::default().fg(Color::Gray)),
-            ]);
-            ListItem::new(content)
-        })
-        .collect();
+/// The session's `mouse` option, falling back to the global value.
+fn mouse_mode_with_executor(executor: &dyn TmuxExecutor, session: &str) -> Result<bool> {
+    let target = format!("={}:", session);
+    for args in [
+        vec!["show-options", "-v", "-t", target.as_str(), "mouse"],
+        vec!["show-options", "-gv", "mouse"],
+    ] {
+        let output = executor.execute_command(&args)?;
+        if !output.status.success() {
+            continue;
+        }
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "on" => return Ok(true),
+            "off" => return Ok(false),
+            _ => continue,
+        }
+    }
+    Ok(false)
+}
 
-    let title = " │ Name             │Win │  Memory │   CPU │ Clients ";
-    // Helper function to get terminal-appropriate styles
-    fn get_top_ui_highlight_style() -> Style {
-        let term = std::env::var("TERM").unwrap_or_else(|_| "unknown".to_string());
-        let term_program = std::env::var("TERM_PROGRAM").unwrap_or_else(|_| "unknown".to_string());
-        let colorterm = std::env::var("COLORTERM").unwrap_or_else(|_| "unknown".to_string());
+/// Set (or flip, when `state` is `None`) the session's mouse mode and return
+/// the new value.
+fn set_mouse_mode_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    state: Option<Toggle>,
+) -> Result<bool> {
+    if !session_exists_with_executor(executor, session)? {
+        return Err(anyhow::anyhow!("Session '{}' not found", session));
+    }
+    let on = match state {
+        Some(state) => state == Toggle::On,
+        None => !mouse_mode_with_executor(executor, session)?,
+    };
+    let target = format!("={}:", session);
+    let value = if on { "on" } else { "off" };
+    let output = executor.execute_command(&["set-option", "-t", &target, "mouse", value])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to set mouse mode for '{}': {}",
+            session,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(on)
+}
 
-        // For Warp terminal and other terminals that may have issues with background colors
-        if term_program.contains("WarpTerminal") || term_program.contains("Warp") {
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED)
-        } else if term.contains("screen") || term.contains("tmux") {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::REVERSED)
-        } else if colorterm.contains("truecolor") || term.contains("256color") {
-            Style::default()
-                .bg(Color::Rgb(0, 100, 200))
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::REVERSED)
-        }
+/// Working directory of the session's active pane (`#{pane_current_path}`).
+fn session_current_path_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+) -> Result<Option<String>> {
+    let target = format!("={}:", session);
+    let output = executor.execute_command(&[
+        "display-message",
+        "-p",
+        "-t",
+        &target,
+        "#{pane_current_path}",
+    ])?;
+    if !output.status.success() {
+        return Ok(None);
     }
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!path.is_empty()).then_some(path))
+}
 
-    fn get_top_ui_selection_symbol() -> &'static str {
-        let term_program = std::env::var("TERM_PROGRAM").unwrap_or_else(|_| "unknown".to_string());
-        let term = std::env::var("TERM").unwrap_or_else(|_| "unknown".to_string());
+/// Hand `path` to the desktop's opener without waiting for it.
+fn open_in_file_manager(path: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if is_windows_path(path) {
+        // A Windows path under WSL; xdg-open can't resolve it
+        "explorer.exe"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("'{}' is not available", opener))?;
+    Ok(())
+}
 
-        if term_program.contains("WarpTerminal") || term_program.contains("Warp") {
-            "===> "
-        } else if term_program.contains("iTerm") {
-            "▶ "
-        } else if term.contains("screen") || term.contains("tmux") {
-            "-> "
-        } else {
-            "► "
-        }
+/// Name of the session the caller is running in, detected via `$TMUX`.
+fn current_tmux_session() -> Result<String> {
+    if std::env::var_os("TMUX").is_none() {
+        return Err(anyhow::anyhow!(
+            "Not inside a tmux session ($TMUX is not set)"
+        ));
     }
+    let pane = std::env::var("TMUX_PANE").ok();
+    current_tmux_session_with_executor(&DefaultTmuxExecutor, pane.as_deref())
+}
 
-    let sessions_list = List::new(sessions)
-        .block(Block::default().borders(Borders::ALL).title(title))
-        .highlight_style(get_top_ui_highlight_style())
-        .highlight_symbol(get_top_ui_selection_symbol());
+fn current_tmux_session_with_executor(
+    executor: &dyn TmuxExecutor,
+    pane: Option<&str>,
+) -> Result<String> {
+    let mut args = vec!["display-message", "-p"];
+    if let Some(pane) = pane {
+        args.extend(["-t", pane]);
+    }
+    args.push("#{session_name}");
 
-    f.render_widget(sessions_list, chunks[1]);
+    let output = executor.execute_command(&args)?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to detect current tmux session: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
 
-    // Help
-    let help_text = "Press 'q' to quit, 'r' to refresh, Ctrl+C to exit";
-    let help = Paragraph::new(help_text)
-        .style(Style::default().fg(Color::Gray))
-        .alignment(Alignment::Left)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(help, chunks[2]);
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Failed to detect current tmux session"));
+    }
+    Ok(name)
 }
 
-fn run_tui() -> Result<()> {
-    // Check if we're in a proper terminal
-    if !std::io::stdout().is_terminal() {
-        return Err(anyhow::anyhow!("cmux requires an interactive terminal. Try running a specific command like 'cmux ls' or 'cmux --help'"));
-    }
+/// Describe a snapshot JSON error with its position and the expected shape,
+/// since snapshots are often edited by hand.
+fn snapshot_parse_error(path: &Path, err: &serde_json::Error) -> anyhow::Error {
+    let location = format!(" at line {} column {}", err.line(), err.column());
+    let message = err.to_string();
+    let message = message.strip_suffix(&location).unwrap_or(&message);
+
+    anyhow::anyhow!(
+        "Failed to parse snapshot file {} (line {}, column {}): {}\n\
+         Expected {{\"sessions\": [{{\"name\", \"windows\", \"attached\", \"created\", \"activity\"}}, ...], \"timestamp\": \"...\"}}",
+        path.display(),
+        err.line(),
+        err.column(),
+        message
+    )
+}
 
-    enable_raw_mode()
-        .context("Failed to enable raw mode. Make sure you're running in a supported terminal.")?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// How `restore_sessions` treats sessions that already exist.
+#[derive(Debug, Default)]
+struct RestoreOptions {
+    dry_run: bool,
+    /// Kill and recreate sessions that already exist
+    force: bool,
+    /// Skip the confirmation `force` asks for
+    yes: bool,
+}
 
-    let mut app = App::new()?;
-    let mut list_state = ListState::default();
-    list_state.select(Some(0));
-    let mut last_refresh = Instant::now();
+/// Sessions are always created in the order they appear in the snapshot. A
+/// saved manual order is merged into the local one so the TUI shows the
+/// restored sessions in that order too.
+fn restore_sessions(file: Option<PathBuf>, options: &RestoreOptions) -> Result<()> {
+    let dry_run = options.dry_run;
+    let snapshot_path = file.unwrap_or_else(|| {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".cmux_snapshot.json")
+    });
 
-    loop {
-        terminal.draw(|f| draw_ui(f, &mut app, &mut list_state))?;
+    let snapshot = load_snapshot(&snapshot_path)?;
+    let mut existing: HashSet<String> = get_tmux_sessions()?.into_iter().map(|s| s.name).collect();
+
+    // Sessions `--force` may replace; each is replaced at most once even if
+    // the snapshot lists it twice
+    let mut replaceable: HashSet<String> = HashSet::new();
+    if options.force {
+        let current = window_layouts_with_executor(&DefaultTmuxExecutor).unwrap_or_default();
+        let colorize = io::stdout().is_terminal();
+        for session in &snapshot.sessions {
+            if !existing.contains(&session.name) || !replaceable.insert(session.name.clone()) {
+                continue;
+            }
+            let saved_windows = snapshot_window_layouts(session);
+            let diff = restore_diff(
+                current.get(&session.name).map_or(&[], Vec::as_slice),
+                snapshot
+                    .layouts
+                    .get(&session.name)
+                    .or(saved_windows.as_ref())
+                    .map(Vec::as_slice),
+                colorize,
+            );
+            println!(
+                "{}: {}",
+                session.name,
+                diff.unwrap_or_else(|| "no window changes".to_string())
+            );
+        }
+        if !replaceable.is_empty() && !dry_run && !options.yes {
+            if !io::stdin().is_terminal() {
+                return Err(anyhow::anyhow!(
+                    "restore --force replaces existing sessions; pass --yes to confirm without a terminal"
+                ));
+            }
+            let count = replaceable.len();
+            let question = format!(
+                "Replace {} existing session{}?",
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+            if !read_confirmation(&question)? {
+                println!("Restore cancelled");
+                return Ok(());
+            }
+        }
+    }
 
-        let timeout = AUTO_REFRESH_INTERVAL
-            .checked_sub(last_refresh.elapsed())
-            .unwrap_or(Duration::from_secs(0));
+    if dry_run {
+        println!(
+            "Dry run: checking {} sessions from snapshot...",
+            snapshot.sessions.len()
+        );
+    } else {
+        println!(
+            "Restoring {} sessions from snapshot...",
+            snapshot.sessions.len()
+        );
+    }
 
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match handle_input(&mut app, key)? {
-                    InputResult::Continue => {}
-                    InputResult::Quit => break,
-                    InputResult::AttachSession(target) => {
-                        // Clean up terminal before attaching
-                        disable_raw_mode()?;
-                        execute!(
-                            terminal.backend_mut(),
-                            LeaveAlternateScreen,
-                            DisableMouseCapture
-                        )?;
-                        terminal.show_cursor()?;
+    let total = snapshot.sessions.len();
+    let (mut restored, mut replaced, mut skipped, mut failed) = (0, 0, 0, 0);
+
+    for (index, session) in snapshot.sessions.into_iter().enumerate() {
+        let progress = format!("[{}/{}]", index + 1, total);
+        if replaceable.remove(&session.name) {
+            replaced += 1;
+            if dry_run {
+                println!("{} Would replace session: {}", progress, session.name);
+                restored += 1;
+                continue;
+            }
+            invalidate_session_cache();
+            let target = format!("={}", session.name);
+            let status = tmux_command()
+                .args(["kill-session", "-t", &target])
+                .status()
+                .context("Failed to execute tmux kill-session command")?;
+            if !status.success() {
+                println!("{} Failed to replace session: {}", progress, session.name);
+                failed += 1;
+                continue;
+            }
+        } else if existing.contains(&session.name) {
+            if dry_run {
+                println!(
+                    "{} Would skip '{}' (already exists)",
+                    progress, session.name
+                );
+            } else {
+                println!(
+                    "{} Session '{}' already exists, skipping...",
+                    progress, session.name
+                );
+            }
+            skipped += 1;
+            continue;
+        }
 
-                        // Attach to session
-                        match target {
-                            AttachTarget::Local(name) => {
-                                attach_session(Some(name))?;
-                            }
-                            AttachTarget::Remote(host, name) => {
-                                attach_remote_session(&host, &name)?;
-                            }
-                        }
+        if dry_run {
+            println!("{} Would restore session: {}", progress, session.name);
+            existing.insert(session.name);
+            restored += 1;
+            continue;
+        }
 
-                        // Re-enter TUI mode after detaching
-                        let mut new_stdout = io::stdout();
-                        hard_reset_terminal(&mut new_stdout)?;
-                        enable_raw_mode()?;
-                        execute!(new_stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        invalidate_session_cache();
+        let status = tmux_command()
+            .args(restore_session_args(&session))
+            .status()
+            .context("Failed to create session")?;
 
-                        // Clear the screen and refresh the terminal
-                        execute!(
-                            new_stdout,
-                            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
-                            crossterm::terminal::Clear(crossterm::terminal::ClearType::Purge),
-                            crossterm::cursor::MoveTo(0, 0)
-                        )?;
-                        let backend = CrosstermBackend::new(new_stdout);
-                        terminal = Terminal::new(backend)?;
-                        terminal.hide_cursor()?;
-                        terminal.clear()?;
-                        app.refresh()?;
-                        terminal.draw(|f| draw_ui(f, &mut app, &mut list_state))?;
-                        last_refresh = Instant::now();
-                    }
-                    InputResult::Refreshed => {
-                        last_refresh = Instant::now();
-                    }
-                }
+        if status.success() {
+            println!("{} Restored session: {}", progress, session.name);
+            // A saved layout already names every window and its splits
+            let warnings = match snapshot.layouts.get(&session.name) {
+                Some(windows) => restore_window_layouts_with_executor(
+                    &DefaultTmuxExecutor,
+                    &session.name,
+                    windows,
+                ),
+                None => restore_windows_with_executor(
+                    &DefaultTmuxExecutor,
+                    &session.name,
+                    session.window_list.get(1..).unwrap_or_default(),
+                ),
+            };
+            for warning in warnings {
+                println!("    warning: {}", warning);
             }
+            existing.insert(session.name);
+            restored += 1;
+        } else {
+            println!("{} Failed to restore session: {}", progress, session.name);
+            failed += 1;
         }
+    }
 
-        if last_refresh.elapsed() >= AUTO_REFRESH_INTERVAL {
-            app.refresh()?;
-            last_refresh = Instant::now();
+    if !dry_run && !snapshot.order.is_empty() {
+        let current = load_session_order()?;
+        let merged = merge_session_order(&current, &snapshot.order);
+        if merged != current {
+            save_session_order(&merged)?;
         }
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    let replaced = if options.force {
+        format!(" ({} replaced)", replaced)
+    } else {
+        String::new()
+    };
+    if dry_run {
+        println!("Would restore {}{}, skip {}", restored, replaced, skipped);
+    } else if failed > 0 {
+        println!(
+            "Restored {}{}, skipped {}, failed {}",
+            restored, replaced, skipped, failed
+        );
+    } else {
+        println!("Restored {}{}, skipped {}", restored, replaced, skipped);
+    }
 
     Ok(())
 }
 
-fn hard_reset_terminal(stdout: &mut impl Write) -> Result<()> {
-    stdout.write_all(b"\x1bc")?;
-    stdout.flush()?;
-    Ok(())
+/// `tmux new-session` arguments recreating `session` detached, with its first
+/// saved window's name and directory.
+fn restore_session_args(session: &TmuxSession) -> Vec<String> {
+    let mut args = vec![
+        "new-session".to_string(),
+        "-d".to_string(),
+        "-s".to_string(),
+        session.name.clone(),
+    ];
+    if let Some(first) = session.window_list.first() {
+        args.extend(["-n".to_string(), first.name.clone()]);
+        if let Some(path) = &first.path {
+            args.extend(["-c".to_string(), path.clone()]);
+        }
+    }
+    args
 }
 
-enum InputResult {
-    Continue,
-    Quit,
-    AttachSession(AttachTarget),
-    Refreshed,
+/// Add `windows` after the first one of a freshly created `session`.
+///
+/// Returns warnings instead of failing, like `restore_window_layouts_with_executor`.
+fn restore_windows_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    windows: &[SnapshotWindow],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let session_target = format!("={}:", session);
+    for window in windows {
+        let mut args = vec![
+            "new-window",
+            "-d",
+            "-t",
+            session_target.as_str(),
+            "-n",
+            window.name.as_str(),
+        ];
+        if let Some(path) = &window.path {
+            args.extend(["-c", path.as_str()]);
+        }
+        match executor.execute_command(&args) {
+            Ok(output) if output.status.success() => {}
+            _ => warnings.push(format!("could not create window '{}'", window.name)),
+        }
+    }
+    warnings
 }
 
-#[derive(Debug, Clone)]
-enum AttachTarget {
-    Local(String),
-    Remote(HostConfig, String),
+/// A session's saved windows in the shape `restore_diff` compares, or `None`
+/// for snapshots from before windows were saved.
+fn snapshot_window_layouts(session: &TmuxSession) -> Option<Vec<WindowLayout>> {
+    if session.window_list.is_empty() {
+        return None;
+    }
+    Some(
+        session
+            .window_list
+            .iter()
+            .map(|window| WindowLayout {
+                name: window.name.clone(),
+                panes: 1,
+                layout: String::new(),
+            })
+            .collect(),
+    )
 }
 
-#[derive(Debug, Clone)]
-enum NewSessionTarget {
-    Local,
-    Remote(HostConfig),
+/// What replacing a session with its snapshot changes, e.g.
+/// `2 windows → 4 windows, +logs, -scratch`, or `None` if nothing visible
+/// does. Without saved windows the session comes back with one window.
+fn restore_diff(
+    current: &[WindowLayout],
+    restored: Option<&[WindowLayout]>,
+    colorize: bool,
+) -> Option<String> {
+    let paint = |text: String, code: &str| {
+        if colorize {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text
+        }
+    };
+    let windows = |count: usize| format!("{} window{}", count, if count == 1 { "" } else { "s" });
+
+    let mut changes = Vec::new();
+    let restored_count = restored.map_or(1, <[WindowLayout]>::len);
+    if current.len() != restored_count {
+        changes.push(format!(
+            "{} → {}",
+            paint(windows(current.len()), "31"),
+            paint(windows(restored_count), "32")
+        ));
+    }
+    if let Some(restored) = restored {
+        let has = |windows: &[WindowLayout], name: &str| windows.iter().any(|w| w.name == name);
+        for window in restored.iter().filter(|w| !has(current, &w.name)) {
+            changes.push(paint(format!("+{}", window.name), "32"));
+        }
+        for window in current.iter().filter(|w| !has(restored, &w.name)) {
+            changes.push(paint(format!("-{}", window.name), "31"));
+        }
+    }
+    (!changes.is_empty()).then(|| changes.join(", "))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum HostField {
-    Name,
-    Host,
+fn load_snapshot(path: &Path) -> Result<SessionSnapshot> {
+    check_snapshot_path(path, false)?;
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read snapshot file {}", path.display()))?;
+    serde_json::from_str(&content).map_err(|err| snapshot_parse_error(path, &err))
 }
 
-#[derive(Debug, Clone)]
-struct KillTarget {
-    origin: SessionOrigin,
-    session_name: String,
-    attached_clients: usize,
+/// Which input wins in `snapshot-merge` when both have a session of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SnapshotSide {
+    A,
+    B,
 }
 
-fn handle_input(app: &mut App, key: KeyEvent) -> Result<InputResult> {
-    // Handle Ctrl+C for exit
-    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-        return Ok(InputResult::Quit);
-    }
-
-    if app.show_new_host_popup {
-        match key.code {
-            KeyCode::Enter => {
-                match build_host_config(&app.new_host_name_input, &app.new_host_host_input) {
-                    Ok(host) => {
-                        add_host_config(host)?;
-                        app.hide_new_host_popup();
-                        app.refresh()?;
-                        return Ok(InputResult::Refreshed);
-                    }
-                    Err(err) => {
-                        app.new_host_error = Some(err.to_string());
-                    }
-                }
-            }
-            KeyCode::Esc => {
-                app.hide_new_host_popup();
-            }
-            KeyCode::Tab | KeyCode::Down => {
-                app.new_host_active_field = match app.new_host_active_field {
-                    HostField::Name => HostField::Host,
-                    HostField::Host => HostField::Name,
-                };
-            }
-            KeyCode::BackTab | KeyCode::Up => {
-                app.new_host_active_field = match app.new_host_active_field {
-                    HostField::Name => HostField::Host,
-                    HostField::Host => HostField::Name,
-                };
-            }
-            KeyCode::Left => {
-                move_cursor_left(
-                    &app.new_host_active_field,
-                    &mut app.new_host_name_cursor,
-                    &mut app.new_host_host_cursor,
-                );
-            }
-            KeyCode::Right => {
-                move_cursor_right(
-                    &app.new_host_active_field,
-                    &app.new_host_name_input,
-                    &app.new_host_host_input,
-                    &mut app.new_host_name_cursor,
-                    &mut app.new_host_host_cursor,
-                );
-            }
-            KeyCode::Home => {
-                set_cursor_start(
-                    &app.new_host_active_field,
-                    &mut app.new_host_name_cursor,
-                    &mut app.new_host_host_cursor,
-                );
-            }
-            KeyCode::End => {
-                set_cursor_end(
-                    &app.new_host_active_field,
-                    &app.new_host_name_input,
-                    &app.new_host_host_input,
-                    &mut app.new_host_name_cursor,
-                    &mut app.new_host_host_cursor,
-                );
-            }
-            KeyCode::Delete => {
-                app.new_host_error = None;
-                match app.new_host_active_field {
-                    HostField::Name => {
-                        remove_char_at(&mut app.new_host_name_input, &mut app.new_host_name_cursor);
-                    }
-                    HostField::Host => {
-                        remove_char_at(&mut app.new_host_host_input, &mut app.new_host_host_cursor);
-                    }
+/// Sessions from `a` keep their order, followed by sessions only found in `b`.
+/// The merged timestamp is the later of the two.
+fn merge_snapshots(
+    a: SessionSnapshot,
+    b: SessionSnapshot,
+    prefer: SnapshotSide,
+) -> SessionSnapshot {
+    let timestamp = later_timestamp(a.timestamp, b.timestamp);
+    let order = merge_session_order(&a.order, &b.order);
+    let mut sessions = a.sessions;
+    let mut layouts = a.layouts;
+    let mut b_layouts = b.layouts;
+    for session in b.sessions {
+        let taken = match sessions.iter_mut().find(|s| s.name == session.name) {
+            Some(existing) => {
+                if prefer == SnapshotSide::B {
+                    *existing = session.clone();
+                    true
+                } else {
+                    false
                 }
             }
-            KeyCode::Backspace => {
-                app.new_host_error = None;
-                app.backspace_new_host_input();
-            }
-            KeyCode::Char(c) => {
-                app.new_host_error = None;
-                app.handle_new_host_input(c);
+            None => {
+                sessions.push(session.clone());
+                true
             }
-            _ => {}
+        };
+        // Layouts follow whichever copy of the session was kept
+        if taken {
+            match b_layouts.remove(&session.name) {
+                Some(windows) => layouts.insert(session.name, windows),
+                None => layouts.remove(&session.name),
+            };
         }
-        return Ok(InputResult::Continue);
     }
+    SessionSnapshot {
+        sessions,
+        timestamp,
+        layouts,
+        order,
+    }
+}
 
-    if app.show_kill_confirm {
-        match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
-                if let Some(target) = app.kill_confirm_target.clone() {
-                    match target.origin {
-                        SessionOrigin::Local => {
-                            kill_session(Some(target.session_name.clone()))?;
-                            app.set_status_message("Session killed.");
-                            app.refresh()?;
-                            app.hide_kill_confirm();
-                            return Ok(InputResult::Refreshed);
-                        }
-                        SessionOrigin::Remote(host) => {
-                            match kill_remote_session(&host, &target.session_name) {
-                                Ok(()) => {
-                                    app.set_status_message("Remote session killed.");
-                                    app.refresh()?;
-                                    app.hide_kill_confirm();
-                                    return Ok(InputResult::Refreshed);
-                                }
-                                Err(err) => {
-                                    app.set_status_message(format!("Kill failed: {}", err));
-                                }
-                            }
-                        }
-                    }
-                }
-                app.hide_kill_confirm();
-            }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                app.hide_kill_confirm();
+fn later_timestamp(a: String, b: String) -> String {
+    match (
+        chrono::DateTime::parse_from_rfc3339(&a),
+        chrono::DateTime::parse_from_rfc3339(&b),
+    ) {
+        (Ok(ta), Ok(tb)) => {
+            if tb > ta {
+                b
+            } else {
+                a
             }
-            _ => {}
         }
-        return Ok(InputResult::Continue);
+        (Ok(_), Err(_)) => a,
+        (Err(_), Ok(_)) => b,
+        (Err(_), Err(_)) => a.max(b),
     }
+}
 
-    // Handle popup input if showing new session popup
-    if app.show_new_session_popup {
-        match key.code {
-            KeyCode::Enter => {
-                let session_name = if app.new_session_input.trim().is_empty() {
-                    format!("session-{}", chrono::Local::now().format("%H%M%S"))
-                } else {
-                    app.new_session_input.clone()
-                };
-                match app.new_session_target.clone() {
-                    NewSessionTarget::Local => {
-                        new_session(Some(session_name))?;
-                    }
-                    NewSessionTarget::Remote(host) => {
-                        new_session_remote(&host, Some(session_name))?;
-                    }
-                }
-                app.hide_new_session_popup();
-                app.refresh()?;
-                return Ok(InputResult::Refreshed);
-            }
-            KeyCode::Esc => {
-                app.hide_new_session_popup();
-            }
-            KeyCode::Left => {
-                if app.new_session_cursor > 0 {
-                    app.new_session_cursor -= 1;
-                }
-            }
-            KeyCode::Right => {
-                let len = app.new_session_input.chars().count();
-                if app.new_session_cursor < len {
-                    app.new_session_cursor += 1;
-                }
-            }
-            KeyCode::Home => {
-                app.new_session_cursor = 0;
-            }
-            KeyCode::End => {
-                app.new_session_cursor = app.new_session_input.chars().count();
-            }
-            KeyCode::Delete => {
-                remove_char_at(&mut app.new_session_input, &mut app.new_session_cursor);
-            }
-            KeyCode::Backspace => {
-                app.backspace_new_session_input();
+fn snapshot_merge(a: &Path, b: &Path, output: Option<&Path>, prefer: SnapshotSide) -> Result<()> {
+    let merged = merge_snapshots(load_snapshot(a)?, load_snapshot(b)?, prefer);
+    let json = serde_json::to_string_pretty(&merged)?;
+    match output {
+        Some(path) => {
+            check_snapshot_path(path, true)?;
+            fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Merged {} sessions into {}",
+                merged.sessions.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+fn save_snapshot() -> Result<PathBuf> {
+    write_snapshot(None, false)
+}
+
+fn write_snapshot(file: Option<PathBuf>, with_layout: bool) -> Result<PathBuf> {
+    let snapshot = build_snapshot(with_layout)?;
+    let snapshot_path = file.unwrap_or_else(|| {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".cmux_snapshot.json")
+    });
+    write_snapshot_file(&snapshot_path, &snapshot)?;
+    Ok(snapshot_path)
+}
+
+/// Where `auto_snapshot_secs` saves go, kept apart from `~/.cmux_snapshot.json`
+/// so a timer never replaces a snapshot (or layouts) saved by hand.
+fn auto_snapshot_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cmux_auto_snapshot.json")
+}
+
+/// Save an auto-snapshot, or return `None` without writing when there are no
+/// sessions: after a reboot the last good snapshot is the one worth keeping.
+fn write_auto_snapshot() -> Result<Option<PathBuf>> {
+    let snapshot = build_snapshot(false)?;
+    if snapshot.sessions.is_empty() {
+        return Ok(None);
+    }
+    let path = auto_snapshot_path();
+    write_snapshot_file(&path, &snapshot)?;
+    Ok(Some(path))
+}
+
+/// Write via a temporary file and a rename, so an interrupted save leaves the
+/// previous snapshot intact instead of a truncated one.
+fn write_snapshot_file(path: &Path, snapshot: &SessionSnapshot) -> Result<()> {
+    check_snapshot_path(path, true)?;
+    let json = serde_json::to_string_pretty(snapshot)?;
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, json).with_context(|| format!("Failed to write {}", temp.display()))?;
+    fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn build_snapshot(with_layout: bool) -> Result<SessionSnapshot> {
+    let mut sessions = get_tmux_sessions()?;
+    for session in &mut sessions {
+        // A session killed since listing is saved without its windows
+        session.window_list =
+            snapshot_windows_with_executor(&DefaultTmuxExecutor, &session.name).unwrap_or_default();
+    }
+    let layouts = if with_layout {
+        window_layouts_with_executor(&DefaultTmuxExecutor)?
+    } else {
+        BTreeMap::new()
+    };
+    // Store sessions in the order the TUI shows them, since restore creates them in file order
+    let manual_order = load_session_order()?;
+    apply_session_order(&mut sessions, &manual_order);
+    let order = if manual_order.is_empty() {
+        Vec::new()
+    } else {
+        sessions
+            .iter()
+            .map(|session| session.name.clone())
+            .collect()
+    };
+    Ok(SessionSnapshot {
+        sessions,
+        timestamp: chrono::Local::now().to_rfc3339(),
+        layouts,
+        order,
+    })
+}
+
+/// Catch the common path mistakes up front; the raw IO errors for these are
+/// "Is a directory (os error 21)" and "No such file or directory". A missing
+/// parent only matters when `writing`; reads report the missing file instead.
+fn check_snapshot_path(path: &Path, writing: bool) -> Result<()> {
+    if path.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Snapshot path is a directory: {} (pass a file path, e.g. {}/snapshot.json)",
+            path.display(),
+            path.display()
+        ));
+    }
+    if !writing {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Parent directory does not exist: {} (create it first)",
+                parent.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `session`'s windows with their active pane's directory, for snapshots.
+fn snapshot_windows_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+) -> Result<Vec<SnapshotWindow>> {
+    let target = format!("={}:", session);
+    // The name goes last so a separator inside it can't shift the path
+    let format = ["#{pane_current_path}", "#{window_name}"].join(LIST_FIELD_SEPARATOR);
+    let output = executor.execute_command(&["list-windows", "-t", &target, "-F", &format])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list windows of '{}': {}",
+            session,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_snapshot_windows(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_snapshot_windows(output: &str) -> Vec<SnapshotWindow> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (path, name) = line.split_once(LIST_FIELD_SEPARATOR)?;
+            Some(SnapshotWindow {
+                name: name.to_string(),
+                path: (!path.is_empty()).then(|| path.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Every window's layout, grouped by session, from one `list-windows -a`.
+fn window_layouts_with_executor(
+    executor: &dyn TmuxExecutor,
+) -> Result<BTreeMap<String, Vec<WindowLayout>>> {
+    // The name goes last so a separator inside it can't shift the other fields
+    let format = [
+        "#{session_name}",
+        "#{window_panes}",
+        "#{window_layout}",
+        "#{window_name}",
+    ]
+    .join(LIST_FIELD_SEPARATOR);
+    let output = executor.execute_command(&["list-windows", "-a", "-F", &format])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list window layouts: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_window_layouts(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_window_layouts(output: &str) -> BTreeMap<String, Vec<WindowLayout>> {
+    let mut layouts: BTreeMap<String, Vec<WindowLayout>> = BTreeMap::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.splitn(4, LIST_FIELD_SEPARATOR).collect();
+        if let [session, panes, layout, name] = parts[..] {
+            layouts
+                .entry(session.to_string())
+                .or_default()
+                .push(WindowLayout {
+                    name: name.to_string(),
+                    panes: panes.parse().unwrap_or(1),
+                    layout: layout.to_string(),
+                });
+        }
+    }
+    layouts
+}
+
+/// Rebuild `windows` inside a freshly created single-window `session`.
+///
+/// Returns warnings instead of failing: a window whose splits or layout can't
+/// be applied is left as a single pane so the rest of the restore continues.
+fn restore_window_layouts_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    windows: &[WindowLayout],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let session_target = format!("={}:", session);
+    for (index, window) in windows.iter().enumerate() {
+        let ids = if index == 0 {
+            executor.execute_command(&[
+                "display-message",
+                "-p",
+                "-t",
+                &session_target,
+                "#{window_id} #{pane_id}",
+            ])
+        } else {
+            executor.execute_command(&[
+                "new-window",
+                "-d",
+                "-P",
+                "-F",
+                "#{window_id} #{pane_id}",
+                "-t",
+                &session_target,
+                "-n",
+                &window.name,
+            ])
+        };
+        let ids = match ids {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).trim().to_string()
             }
-            KeyCode::Char(c) => {
-                app.handle_new_session_input(c);
+            _ => {
+                warnings.push(format!("could not create window '{}'", window.name));
+                continue;
             }
-            _ => {}
-        }
-        return Ok(InputResult::Continue);
+        };
+        let Some((window_id, pane_id)) = ids.split_once(' ') else {
+            warnings.push(format!("could not create window '{}'", window.name));
+            continue;
+        };
+        if index == 0 {
+            let _ = executor.execute_command(&["rename-window", "-t", window_id, &window.name]);
+        }
+
+        if let Err(err) = apply_window_layout(executor, window_id, window) {
+            warnings.push(format!(
+                "window '{}': {}; left as a single pane",
+                window.name, err
+            ));
+            let _ = executor.execute_command(&["kill-pane", "-a", "-t", pane_id]);
+        }
+    }
+    warnings
+}
+
+fn apply_window_layout(
+    executor: &dyn TmuxExecutor,
+    window_id: &str,
+    window: &WindowLayout,
+) -> Result<()> {
+    let check = |output: Output, what: &str| -> Result<()> {
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "{} failed: {}",
+                what,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    };
+    for _ in 1..window.panes {
+        check(
+            executor.execute_command(&["split-window", "-d", "-t", window_id])?,
+            "split-window",
+        )?;
+    }
+    check(
+        executor.execute_command(&["select-layout", "-t", window_id, &window.layout])?,
+        "select-layout",
+    )
+}
+
+/// User preferences stored in `~/.cmux_config.json`. Missing keys use defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    /// Behave as if `--confirm` were always passed
+    confirm_all: bool,
+    /// Optional columns in the TUI session lists
+    columns: ColumnConfig,
+    /// Variables `new --clean-env` removes from the new session's environment
+    clean_env_vars: Vec<String>,
+    /// What `cmux attach` with no name does when there are no sessions
+    attach_when_empty: AttachWhenEmpty,
+    /// Fixed `WIDTHxHEIGHT` for the attaching client only (`refresh-client -C`)
+    attach_size: Option<String>,
+    /// Milliseconds allowed between the two presses of `dd` in the TUI
+    double_press_ms: u64,
+    /// tmux session variables to fetch instead of the built-in five
+    list_format: Vec<String>,
+    /// Prepended to the names of sessions cmux creates
+    session_prefix: Option<String>,
+    /// Which keys quit the TUI from the session list (Ctrl+C always does)
+    quit_key: QuitKey,
+    /// Ask before quitting the TUI with the quit key
+    quit_confirm: bool,
+    /// Option values `doctor --sessions` expects every session to have
+    session_expectations: SessionExpectations,
+    /// Measure every session when the TUI starts; `false` behaves like `--fast`
+    enrich_in_tui: bool,
+    /// Seconds between automatic refreshes of the TUI and `top`
+    refresh_secs: u64,
+    /// Sort the TUI starts with, and `list` uses when `--sort` is left out
+    default_sort: Option<SortKey>,
+    /// Have the TUI `K` key ask before killing even detached sessions
+    confirm_kill: bool,
+    /// Credit sessions whose process wasn't found to `$USER` running `tmux`
+    fallback_process_info: bool,
+    /// Have `top` save a snapshot this often; unset or 0 never does
+    auto_snapshot_secs: Option<u64>,
+    /// Refuse to create sessions once this many exist; unset means no limit
+    max_sessions: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum QuitKey {
+    /// `q`, or `Esc` when there is no view to go back from
+    #[default]
+    QOrEsc,
+    /// Only `q`; `Esc` just goes back
+    Q,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AttachWhenEmpty {
+    /// Fail with "No tmux sessions found"
+    #[default]
+    Error,
+    /// Create a default-named session and attach to it
+    Create,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            confirm_all: false,
+            columns: ColumnConfig::default(),
+            clean_env_vars: DEFAULT_CLEAN_ENV_VARS
+                .iter()
+                .map(|var| var.to_string())
+                .collect(),
+            attach_when_empty: AttachWhenEmpty::default(),
+            attach_size: None,
+            double_press_ms: DEFAULT_DOUBLE_PRESS_MS,
+            list_format: Vec::new(),
+            session_prefix: None,
+            quit_key: QuitKey::default(),
+            quit_confirm: false,
+            session_expectations: SessionExpectations::default(),
+            enrich_in_tui: true,
+            refresh_secs: DEFAULT_REFRESH_SECS,
+            default_sort: None,
+            confirm_kill: false,
+            fallback_process_info: true,
+            auto_snapshot_secs: None,
+            max_sessions: None,
+        }
+    }
+}
+
+impl Config {
+    /// `refresh_secs` as a duration; 0 is treated as 1 so nothing spins.
+    fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_secs.max(1))
+    }
+
+    /// `auto_snapshot_secs` as a duration, `None` when auto-saving is off.
+    fn auto_snapshot_interval(&self) -> Option<Duration> {
+        self.auto_snapshot_secs
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Fail when `count` running sessions already reach `max_sessions`.
+    fn check_session_limit(&self, count: usize) -> Result<()> {
+        match self.max_sessions {
+            Some(max) if count >= max => Err(anyhow::anyhow!(
+                "Refusing to create a session: {} already running and max_sessions is {} \
+                 (pass --force to create it anyway)",
+                count,
+                max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+const DEFAULT_DOUBLE_PRESS_MS: u64 = 500;
+
+/// Per-login variables that leak the creating terminal into a session.
+const DEFAULT_CLEAN_ENV_VARS: &[&str] = &[
+    "SSH_AUTH_SOCK",
+    "SSH_AGENT_PID",
+    "SSH_CLIENT",
+    "SSH_CONNECTION",
+    "SSH_TTY",
+    "DISPLAY",
+    "XAUTHORITY",
+    "WINDOWID",
+    "VIRTUAL_ENV",
+    "CONDA_DEFAULT_ENV",
+    "CONDA_PREFIX",
+];
+
+/// Optional TUI columns, set under `columns` in the config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ColumnConfig {
+    /// Gauge next to the CPU percentage
+    cpu_bar: bool,
+    /// Name of each session's active window
+    active_window: bool,
+    /// Swap used by the session's processes
+    swap: bool,
+    /// Open file descriptors held by the session's processes
+    fds: bool,
+}
+
+impl Default for ColumnConfig {
+    fn default() -> Self {
+        Self {
+            cpu_bar: true,
+            active_window: false,
+            swap: false,
+            fds: false,
+        }
+    }
+}
+
+/// What `doctor --sessions` considers normal, set under
+/// `session_expectations` in the config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct SessionExpectations {
+    /// Required `mouse` value; unset means either is fine
+    mouse: Option<bool>,
+    /// Smallest `history-limit` that isn't flagged
+    min_history_limit: u64,
+    /// Expected `remain-on-exit` value
+    remain_on_exit: bool,
+}
+
+impl Default for SessionExpectations {
+    fn default() -> Self {
+        Self {
+            mouse: None,
+            min_history_limit: 1000,
+            remain_on_exit: false,
+        }
+    }
+}
+
+fn config_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cmux_config.json")
+}
+
+fn load_config() -> Result<Config> {
+    let config_path = config_path();
+
+    if !config_path.exists() {
+        return Ok(Config::default());
+    }
+
+    let content = fs::read_to_string(&config_path)?;
+    let config: Config = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", config_path.display()))?;
+    Ok(config)
+}
+
+fn save_config(config: &Config) -> Result<()> {
+    let json = serde_json::to_string_pretty(config)?;
+    fs::write(config_path(), json)?;
+    Ok(())
+}
+
+/// Everything `config-export` bundles from the `~/.cmux_*.json` sidecar files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigBackup {
+    aliases: HashMap<String, String>,
+    tags: HashMap<String, String>,
+    session_order: Vec<String>,
+    config: Option<Config>,
+}
+
+fn export_config() -> Result<()> {
+    let backup = ConfigBackup {
+        aliases: load_aliases()?,
+        tags: load_tags()?,
+        session_order: load_session_order()?,
+        config: config_path().exists().then(load_config).transpose()?,
+    };
+
+    // Going through `Value` sorts map keys so backups diff cleanly
+    let json = serde_json::to_string_pretty(&serde_json::to_value(&backup)?)?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn import_config(file: Option<PathBuf>) -> Result<()> {
+    let content = match file {
+        Some(ref path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read backup file {}", path.display()))?,
+        None => io::read_to_string(io::stdin()).context("Failed to read backup from stdin")?,
+    };
+    let backup: ConfigBackup =
+        serde_json::from_str(&content).context("Failed to parse backup file")?;
+
+    if let Some((session, color)) = backup
+        .tags
+        .iter()
+        .find(|(_, color)| tag_ansi_code(color).is_none())
+    {
+        return Err(anyhow::anyhow!(
+            "Backup tags session '{}' with unknown color '{}'",
+            session,
+            color
+        ));
+    }
+
+    let mut aliases = load_aliases()?;
+    let (added, overwritten) = merge_entries(&mut aliases, backup.aliases);
+    save_aliases(&aliases)?;
+    println!("Aliases: {} added, {} overwritten", added, overwritten);
+
+    let mut tags = load_tags()?;
+    let (added, overwritten) = merge_entries(&mut tags, backup.tags);
+    save_tags(&tags)?;
+    println!("Tags: {} added, {} overwritten", added, overwritten);
+
+    if !backup.session_order.is_empty() {
+        let replaced = !load_session_order()?.is_empty();
+        save_session_order(&backup.session_order)?;
+        println!(
+            "Session order: {}",
+            if replaced { "overwritten" } else { "added" }
+        );
+    }
+
+    if let Some(config) = backup.config {
+        let replaced = config_path().exists();
+        save_config(&config)?;
+        println!(
+            "Preferences: {}",
+            if replaced { "overwritten" } else { "added" }
+        );
+    }
+
+    Ok(())
+}
+
+/// Insert `incoming` into `target`, returning how many keys were (added, overwritten).
+/// Keys whose value is unchanged count as neither.
+fn merge_entries(
+    target: &mut HashMap<String, String>,
+    incoming: HashMap<String, String>,
+) -> (usize, usize) {
+    let mut added = 0;
+    let mut overwritten = 0;
+    for (key, value) in incoming {
+        match target.insert(key, value.clone()) {
+            None => added += 1,
+            Some(previous) if previous != value => overwritten += 1,
+            Some(_) => {}
+        }
+    }
+    (added, overwritten)
+}
+
+fn load_aliases() -> Result<HashMap<String, String>> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let alias_path = PathBuf::from(home).join(".cmux_aliases.json");
+
+    if !alias_path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&alias_path)?;
+    let aliases: HashMap<String, String> = serde_json::from_str(&content)?;
+    Ok(aliases)
+}
+
+fn save_aliases(aliases: &HashMap<String, String>) -> Result<()> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let alias_path = PathBuf::from(home).join(".cmux_aliases.json");
+
+    let json = serde_json::to_string_pretty(aliases)?;
+    fs::write(&alias_path, json)?;
+    Ok(())
+}
+
+fn tags_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cmux_tags.json")
+}
+
+fn load_tags() -> Result<HashMap<String, String>> {
+    let tags_path = tags_path();
+
+    if !tags_path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&tags_path)?;
+    let tags: HashMap<String, String> = serde_json::from_str(&content)?;
+    Ok(tags)
+}
+
+fn save_tags(tags: &HashMap<String, String>) -> Result<()> {
+    let json = serde_json::to_string_pretty(tags)?;
+    fs::write(tags_path(), json)?;
+    Ok(())
+}
+
+fn tag_color(tag: Option<&String>) -> Option<Color> {
+    let tag = tag?;
+    TAG_COLORS
+        .iter()
+        .find(|(name, _, _)| name == tag)
+        .map(|(_, color, _)| *color)
+}
+
+fn tag_ansi_code(tag: &str) -> Option<u8> {
+    TAG_COLORS
+        .iter()
+        .find(|(name, _, _)| *name == tag)
+        .map(|(_, _, code)| *code)
+}
+
+/// Glyph and color for a session's attached/detached dot.
+fn status_marker(attached: bool) -> (&'static str, Color) {
+    if attached {
+        ("●", Color::Green)
+    } else {
+        ("○", Color::Red)
+    }
+}
+
+/// Key for the glyphs and colors in the session list, built from the same
+/// helpers the rows use so it can't drift from what is drawn.
+fn legend_line(app: &App) -> Line<'static> {
+    let (attached, attached_color) = status_marker(true);
+    let (detached, detached_color) = status_marker(false);
+    let mut spans = vec![
+        Span::styled(attached, Style::default().fg(attached_color)),
+        Span::raw(" attached  "),
+        Span::styled(detached, Style::default().fg(detached_color)),
+        Span::raw(" detached  "),
+    ];
+    for (name, color, _) in TAG_COLORS {
+        spans.push(Span::styled(TAG_MARKER, Style::default().fg(*color)));
+        spans.push(Span::raw(format!("{} ", name)));
+    }
+    spans.push(Span::raw("tag  "));
+    if app.columns.cpu_bar {
+        spans.push(Span::styled(
+            cpu_bar(50.0, 2),
+            Style::default().fg(Color::Magenta),
+        ));
+        spans.push(Span::raw(" CPU  "));
+    }
+    spans.push(Span::styled(
+        CURRENT_MARKER.trim_start(),
+        Style::default().fg(Color::Green),
+    ));
+    spans.push(Span::raw(" this session"));
+    Line::from(spans)
+}
+
+/// One-column block shown before a session name, colored by its tag.
+fn tag_marker_span(tag: Option<&String>) -> Span<'static> {
+    match tag_color(tag) {
+        Some(color) => Span::styled(TAG_MARKER, Style::default().fg(color)),
+        None => Span::raw(" "),
+    }
+}
+
+fn manage_tags(session: Option<String>, color: Option<String>) -> Result<()> {
+    let mut tags = load_tags()?;
+
+    match (session, color) {
+        (Some(session_name), Some(color)) => {
+            let color = color.to_lowercase();
+            if color == "none" {
+                if tags.remove(&session_name).is_some() {
+                    save_tags(&tags)?;
+                    println!("Removed tag from session '{}'", session_name);
+                } else {
+                    println!("Session '{}' has no tag", session_name);
+                }
+            } else if tag_ansi_code(&color).is_some() {
+                tags.insert(session_name.clone(), color.clone());
+                save_tags(&tags)?;
+                println!("Tagged session '{}' as {}", session_name, color);
+            } else {
+                let valid: Vec<&str> = TAG_COLORS.iter().map(|(name, _, _)| *name).collect();
+                return Err(anyhow::anyhow!(
+                    "Unknown tag color '{}'. Valid colors: {}, none",
+                    color,
+                    valid.join(", ")
+                ));
+            }
+        }
+        (Some(session_name), None) => match tags.get(&session_name) {
+            Some(color) => println!("{} -> {}", session_name, color),
+            None => println!("Session '{}' has no tag", session_name),
+        },
+        (None, _) => {
+            if tags.is_empty() {
+                println!("No tags defined");
+            } else {
+                println!("Current tags:");
+                let mut entries: Vec<_> = tags.into_iter().collect();
+                entries.sort();
+                for (session, color) in entries {
+                    println!("  {} -> {}", session, color);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn session_order_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cmux_order.json")
+}
+
+fn load_session_order() -> Result<Vec<String>> {
+    let order_path = session_order_path();
+
+    if !order_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&order_path)?;
+    let order: Vec<String> = serde_json::from_str(&content)?;
+    Ok(order)
+}
+
+fn save_session_order(order: &[String]) -> Result<()> {
+    let json = serde_json::to_string_pretty(order)?;
+    fs::write(session_order_path(), json)?;
+    Ok(())
+}
+
+/// Add names from `incoming` that `current` doesn't mention yet, keeping
+/// `incoming`'s relative order. Names already placed by the user stay put.
+fn merge_session_order(current: &[String], incoming: &[String]) -> Vec<String> {
+    let mut merged = current.to_vec();
+    for name in incoming {
+        if !merged.contains(name) {
+            merged.push(name.clone());
+        }
+    }
+    merged
+}
+
+/// Sort sessions by their position in the saved manual order.
+///
+/// tmux doesn't remember an order, so sessions missing from `order` keep
+/// their tmux order after all the ordered ones.
+fn apply_session_order(sessions: &mut [TmuxSession], order: &[String]) {
+    if order.is_empty() {
+        return;
+    }
+    sessions.sort_by_key(|session| {
+        order
+            .iter()
+            .position(|name| name == &session.name)
+            .unwrap_or(usize::MAX)
+    });
+}
+
+fn hosts_config_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cmux_hosts.toml")
+}
+
+fn load_hosts() -> Result<Vec<HostConfig>> {
+    let path = hosts_config_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let config: HostsConfig = toml::from_str(&content).context("Failed to parse hosts config")?;
+    Ok(config.hosts)
+}
+
+fn save_hosts(hosts: &[HostConfig]) -> Result<()> {
+    let config = HostsConfig {
+        hosts: hosts.to_vec(),
+    };
+    let path = hosts_config_path();
+    let content = toml::to_string_pretty(&config).context("Failed to serialize hosts config")?;
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+fn add_host_config(host: HostConfig) -> Result<()> {
+    let mut hosts = load_hosts()?;
+    if hosts.iter().any(|h| h.name == host.name) {
+        return Err(anyhow::anyhow!("Host '{}' already exists", host.name));
+    }
+    hosts.push(host);
+    save_hosts(&hosts)?;
+    Ok(())
+}
+
+fn remove_host_config(name: &str) -> Result<()> {
+    let mut hosts = load_hosts()?;
+    let original_len = hosts.len();
+    hosts.retain(|h| h.name != name);
+    if hosts.len() == original_len {
+        return Err(anyhow::anyhow!("Host '{}' not found", name));
+    }
+    save_hosts(&hosts)?;
+    Ok(())
+}
+
+fn list_hosts() -> Result<()> {
+    let hosts = load_hosts()?;
+    if hosts.is_empty() {
+        println!("No remote hosts configured.");
+        return Ok(());
+    }
+
+    println!("Remote hosts:");
+    println!("{:<16} {:<24} Key", "Name", "Host");
+    println!("{}", "-".repeat(60));
+    for host in hosts {
+        let key = host.key.unwrap_or_else(|| "default".to_string());
+        println!(
+            "{} {} {}",
+            pad_display(&host.name, 16),
+            pad_display(&host.host, 24),
+            key
+        );
+    }
+    Ok(())
+}
+
+fn manage_hosts(command: HostCommands) -> Result<()> {
+    match command {
+        HostCommands::Add { name, host, key } => {
+            add_host_config(HostConfig { name, host, key })?;
+            println!("Added host.");
+        }
+        HostCommands::Remove { name } => {
+            remove_host_config(&name)?;
+            println!("Removed host '{}'.", name);
+        }
+        HostCommands::List => list_hosts()?,
+    }
+    Ok(())
+}
+
+/// What `cmux alias` was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AliasAction {
+    List,
+    Query(String),
+    Create(String, String),
+    Delete(String),
+    Rename(String, String),
+}
+
+fn manage_alias(action: AliasAction) -> Result<()> {
+    let mut aliases = load_aliases()?;
+
+    match action {
+        AliasAction::Create(alias_name, session_name) => {
+            aliases.insert(alias_name.clone(), session_name.clone());
+            save_aliases(&aliases)?;
+            println!(
+                "Created alias '{}' for session '{}'",
+                alias_name, session_name
+            );
+        }
+        AliasAction::Query(alias_name) => {
+            if let Some(session_name) = aliases.get(&alias_name) {
+                println!("{} -> {}", alias_name, session_name);
+            } else {
+                println!("Alias '{}' not found", alias_name);
+            }
+        }
+        AliasAction::List => {
+            if aliases.is_empty() {
+                println!("No aliases defined");
+            } else {
+                println!("Current aliases:");
+                for (alias, session) in aliases {
+                    println!("  {} -> {}", alias, session);
+                }
+            }
+        }
+        AliasAction::Delete(alias_name) => {
+            if aliases.remove(&alias_name).is_some() {
+                save_aliases(&aliases)?;
+                println!("Deleted alias '{}'", alias_name);
+            } else {
+                println!("Alias '{}' not found", alias_name);
+            }
+        }
+        AliasAction::Rename(old_name, new_name) => {
+            if aliases.contains_key(&new_name) {
+                return Err(anyhow::anyhow!("Alias '{}' already exists", new_name));
+            }
+            if let Some(session_name) = aliases.remove(&old_name) {
+                aliases.insert(new_name.clone(), session_name);
+                save_aliases(&aliases)?;
+                println!("Renamed alias '{}' to '{}'", old_name, new_name);
+            } else {
+                println!("Alias '{}' not found", old_name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn show_session_info(session_name: Option<String>, history: bool, porcelain: bool) -> Result<()> {
+    let sessions = get_tmux_sessions()?;
+
+    let target_session = match session_name {
+        Some(name) => sessions
+            .into_iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", name))?,
+        None => {
+            if sessions.is_empty() {
+                return Err(anyhow::anyhow!("No tmux sessions found"));
+            }
+            sessions.into_iter().next().unwrap()
+        }
+    };
+
+    if porcelain {
+        // Name last, so a separator inside it can't shift the other fields
+        let format =
+            ["#{window_index}", "#{window_panes}", "#{window_name}"].join(LIST_FIELD_SEPARATOR);
+        let output = tmux_command()
+            .args(["list-windows", "-t", &target_session.name, "-F", &format])
+            .output()?;
+        let windows = if output.status.success() {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        } else {
+            String::new()
+        };
+        print!("{}", render_info_porcelain(&target_session, &windows));
+        return Ok(());
+    }
+
+    println!("Session Information:");
+    println!("  Name: {}", target_session.name);
+    println!("  Windows: {}", target_session.windows);
+    println!(
+        "  Status: {}",
+        if target_session.attached {
+            "attached"
+        } else {
+            "detached"
+        }
+    );
+    println!("  Created: {}", format_timestamp(&target_session.created));
+    println!(
+        "  Last Activity: {}",
+        format_timestamp(&target_session.activity)
+    );
+    if history {
+        match history_limit_with_executor(&DefaultTmuxExecutor, &target_session.name)? {
+            Some(limit) => println!("  History Limit: {} lines", limit),
+            None => println!("  History Limit: unknown"),
+        }
+    }
+
+    let group_output = tmux_command()
+        .args([
+            "list-sessions",
+            "-F",
+            "#{session_grouped}:|:#{session_group}:|:#{session_name}",
+        ])
+        .output()?;
+    if group_output.status.success() {
+        let members = session_group_members(
+            &String::from_utf8_lossy(&group_output.stdout),
+            &target_session.name,
+        );
+        if !members.is_empty() {
+            println!("  Grouped with: {}", members.join(", "));
+        }
+    }
+
+    // Get window details
+    let output = tmux_command()
+        .args([
+            "list-windows",
+            "-t",
+            &target_session.name,
+            "-F",
+            "#{window_index}: #{window_name} (#{window_panes} panes)",
+        ])
+        .output()?;
+
+    if output.status.success() {
+        println!("\nWindows:");
+        let windows = String::from_utf8_lossy(&output.stdout);
+        for window in windows.lines() {
+            println!("  {}", window);
+        }
+    }
+
+    Ok(())
+}
+
+/// `info --porcelain` output. This format is a stable interface: keys, their
+/// order and value encodings must not change between versions.
+///
+/// ```text
+/// name<TAB>dev
+/// windows<TAB>2
+/// attached<TAB>1            (1 or 0)
+/// created<TAB>1640995200    (unix seconds, as reported by tmux)
+/// activity<TAB>1640995260
+/// window<TAB>0<TAB>editor<TAB>1   (index, name, pane count; one line per window)
+/// ```
+///
+/// Tabs and newlines inside names are replaced with spaces so every record stays
+/// on one line.
+fn render_info_porcelain(session: &TmuxSession, list_windows_output: &str) -> String {
+    let clean = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+    let mut out = String::new();
+    out.push_str(&format!("name\t{}\n", clean(&session.name)));
+    out.push_str(&format!("windows\t{}\n", session.windows));
+    out.push_str(&format!("attached\t{}\n", u8::from(session.attached)));
+    out.push_str(&format!("created\t{}\n", clean(&session.created)));
+    out.push_str(&format!("activity\t{}\n", clean(&session.activity)));
+    for line in list_windows_output.lines() {
+        let mut parts = line.splitn(3, LIST_FIELD_SEPARATOR);
+        let (Some(index), Some(panes), Some(name)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        out.push_str(&format!("window\t{}\t{}\t{}\n", index, clean(name), panes));
+    }
+    out
+}
+
+fn list_windows(session_name: Option<String>, output: OutputFormat) -> Result<()> {
+    let session = match session_name {
+        Some(name) => name,
+        None => list_tmux_sessions_with_executor(&DefaultTmuxExecutor)?
+            .into_iter()
+            .next()
+            .map(|s| s.name)
+            .ok_or_else(|| anyhow::anyhow!("No tmux sessions found"))?,
+    };
+    let windows = session_windows_with_executor(&DefaultTmuxExecutor, &session)?;
+    print!("{}", render_windows(&session, &windows, output)?);
+    Ok(())
+}
+
+/// Every window in `session`, in index order.
+fn session_windows_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+) -> Result<Vec<TmuxWindow>> {
+    let target = format!("={}:", session);
+    // The name goes last so a separator inside it can't shift the other fields
+    let format = [
+        "#{window_index}",
+        "#{window_panes}",
+        "#{window_active}",
+        "#{window_name}",
+    ]
+    .join(LIST_FIELD_SEPARATOR);
+    let output = executor.execute_command(&["list-windows", "-t", &target, "-F", &format])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list windows of '{}': {}",
+            session,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_session_windows(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_session_windows(output: &str) -> Vec<TmuxWindow> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, LIST_FIELD_SEPARATOR).collect();
+            let [index, panes, active, name] = parts[..] else {
+                return None;
+            };
+            Some(TmuxWindow {
+                index: index.parse().ok()?,
+                name: name.to_string(),
+                panes: panes.parse().unwrap_or(0),
+                active: active == "1",
+            })
+        })
+        .collect()
+}
+
+/// Render `windows` in `format`, mirroring `render_sessions`.
+fn render_windows(session: &str, windows: &[TmuxWindow], format: OutputFormat) -> Result<String> {
+    let mut out = String::new();
+    match format {
+        OutputFormat::Json => {
+            out.push_str(&serde_json::to_string_pretty(windows)?);
+            out.push('\n');
+        }
+        OutputFormat::Plain => {
+            for window in windows {
+                out.push_str(&window.name);
+                out.push('\n');
+            }
+        }
+        OutputFormat::Csv => {
+            out.push_str("index,name,panes,active\n");
+            for window in windows {
+                out.push_str(&format!(
+                    "{},{},{},{}\n",
+                    window.index,
+                    csv_field(&window.name),
+                    window.panes,
+                    window.active
+                ));
+            }
+        }
+        OutputFormat::Compact => {
+            for window in windows {
+                out.push_str(&format!(
+                    "{}:{} {}p{}\n",
+                    window.index,
+                    window.name,
+                    window.panes,
+                    if window.active { " *" } else { "" }
+                ));
+            }
+        }
+        OutputFormat::Table => {
+            out.push_str(&format!("Windows in '{}':\n", session));
+            out.push_str(&format!("{:<6} {:<20} {:<6}\n", "Index", "Name", "Panes"));
+            out.push_str(&format!("{}\n", "-".repeat(34)));
+            for window in windows {
+                let line = format!(
+                    "{:<6} {} {:<6}",
+                    window.index,
+                    pad_display(&truncate_display(&window.name, 20), 20),
+                    window.panes
+                );
+                if window.active {
+                    out.push_str(&format!("{} (active)\n", line));
+                } else {
+                    out.push_str(&format!("{}\n", line));
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn capture_session(session_name: Option<String>, lines: usize, no_indicator: bool) -> Result<()> {
+    let target = match session_name {
+        Some(name) => name,
+        None => get_tmux_sessions()?
+            .into_iter()
+            .next()
+            .map(|s| s.name)
+            .ok_or_else(|| anyhow::anyhow!("No tmux sessions found"))?,
+    };
+
+    let capture = capture_pane(&target, lines)?;
+    if capture.truncated && !no_indicator {
+        println!("{}", TRUNCATION_INDICATOR);
+    }
+    for line in capture.lines {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+/// Parse `grouped:|:group:|:name` rows and return the other sessions sharing `name`'s group.
+fn session_group_members(output: &str, name: &str) -> Vec<String> {
+    let rows: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, LIST_FIELD_SEPARATOR);
+            let grouped = parts.next()?;
+            let group = parts.next()?;
+            let session = parts.next()?;
+            (grouped == "1" && !group.is_empty()).then_some((session, group))
+        })
+        .collect();
+
+    let Some(group) = rows
+        .iter()
+        .find(|(session, _)| *session == name)
+        .map(|(_, group)| *group)
+    else {
+        return Vec::new();
+    };
+
+    rows.iter()
+        .filter(|(session, g)| *g == group && *session != name)
+        .map(|(session, _)| session.to_string())
+        .collect()
+}
+
+fn kill_all_sessions(confirm: bool) -> Result<()> {
+    if confirm {
+        ensure_interactive_confirm()?;
+    }
+
+    let sessions = get_tmux_sessions()?;
+
+    if sessions.is_empty() {
+        println!("No tmux sessions to kill.");
+        return Ok(());
+    }
+
+    println!("This will kill {} sessions:", sessions.len());
+    for session in &sessions {
+        println!("  - {}", session.name);
+    }
+
+    println!();
+    if !read_confirmation("Are you sure?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    invalidate_session_cache();
+    for session in sessions {
+        tmux_command()
+            .args(["kill-session", "-t", &session.name])
+            .status()?;
+        println!("Killed: {}", session.name);
+    }
+
+    println!("All sessions killed.");
+    Ok(())
+}
+
+/// Extra `version --full` details; values not known at build time print as "unknown".
+fn print_build_info() {
+    let tmux = tmux_command()
+        .arg("-V")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "not found".to_string());
+    let commit = env!("CMUX_GIT_COMMIT");
+
+    println!();
+    println!("  tmux:      {}", tmux);
+    println!("  target:    {}", env!("CMUX_TARGET"));
+    println!(
+        "  commit:    {}",
+        if commit.is_empty() { "unknown" } else { commit }
+    );
+    println!("  ratatui:   {}", env!("CMUX_RATATUI_VERSION"));
+    println!("  crossterm: {}", env!("CMUX_CROSSTERM_VERSION"));
+}
+
+fn broadcast_command(command: &str, yes: bool) -> Result<()> {
+    let sessions = get_tmux_sessions()?;
+    if sessions.is_empty() {
+        println!("No tmux sessions to broadcast to.");
+        return Ok(());
+    }
+
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err(anyhow::anyhow!(
+                "Refusing to broadcast without confirmation; pass --yes when stdin is not a terminal"
+            ));
+        }
+        println!(
+            "This will run `{}` in {} sessions:",
+            command,
+            sessions.len()
+        );
+        for session in &sessions {
+            println!("  - {}", session.name);
+        }
+        println!();
+        if !read_confirmation("Send it?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let mut failed = 0;
+    for session in &sessions {
+        match send_keys_with_executor(&DefaultTmuxExecutor, &session.name, command) {
+            Ok(()) => println!("Sent to {}", session.name),
+            Err(err) => {
+                println!("Failed for {}: {}", session.name, err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "Broadcast failed for {} of {} sessions",
+            failed,
+            sessions.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Type `command` into the session's active pane and press Enter.
+fn send_keys_with_executor(
+    executor: &dyn TmuxExecutor,
+    session: &str,
+    command: &str,
+) -> Result<()> {
+    let target = format!("={}:", session);
+    // -l sends the text literally, so words like "Enter" or "C-c" aren't taken as keys
+    let output = executor.execute_command(&["send-keys", "-t", &target, "-l", command])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let output = executor.execute_command(&["send-keys", "-t", &target, "Enter"])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Numbered-list fallback for terminals where the full-screen TUI can't run.
+fn run_plain_picker() -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!("cmux requires an interactive terminal. Try running a specific command like 'cmux ls' or 'cmux --help'"));
+    }
+
+    let sessions = get_tmux_sessions()?;
+    if sessions.is_empty() {
+        println!("No tmux sessions found. Create one with 'cmux new'.");
+        return Ok(());
+    }
+
+    print!("{}", render_plain_picker(&sessions));
+    print!("Attach to [1-{}, q to quit]: ", sessions.len());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    match parse_picker_choice(&input, sessions.len())? {
+        Some(index) => attach_session(
+            Some(sessions[index].name.clone()),
+            &AttachOptions::default(),
+        ),
+        None => Ok(()),
+    }
+}
+
+fn render_plain_picker(sessions: &[TmuxSession]) -> String {
+    let width = sessions.len().to_string().len();
+    sessions
+        .iter()
+        .enumerate()
+        .map(|(index, session)| {
+            format!(
+                "{:>width$}) {} ({} windows{})\n",
+                index + 1,
+                session.name,
+                session.windows,
+                if session.attached { ", attached" } else { "" },
+                width = width
+            )
+        })
+        .collect()
+}
+
+/// Zero-based index for a 1-based menu answer; blank or `q` means cancel.
+fn parse_picker_choice(input: &str, count: usize) -> Result<Option<usize>> {
+    let input = input.trim();
+    if input.is_empty() || input.eq_ignore_ascii_case("q") {
+        return Ok(None);
+    }
+    match input.parse::<usize>() {
+        Ok(choice) if (1..=count).contains(&choice) => Ok(Some(choice - 1)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid choice '{}' (expected 1-{})",
+            input,
+            count
+        )),
+    }
+}
+
+/// Print `question` with a `(y/N)` suffix and return whether the user typed `y`.
+fn read_confirmation(question: &str) -> Result<bool> {
+    print!("{} (y/N): ", question);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "y")
+}
+
+/// Forced confirmations must come from a person, so refuse rather than wait on a pipe.
+fn ensure_interactive_confirm() -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Confirmation required (--confirm / confirm_all) but stdin is not a terminal"
+        ));
+    }
+    Ok(())
+}
+
+/// How `top` sections its session list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    /// One flat list
+    #[default]
+    None,
+    /// One section per session owner (`process_info.user`)
+    User,
+}
+
+/// Aggregates shown in the top header and per-group subtotals.
+#[derive(Debug, Default, PartialEq)]
+struct SessionTotals {
+    sessions: usize,
+    active: usize,
+    memory_mb: f64,
+    cpu_percent: f32,
+}
+
+impl SessionTotals {
+    fn summary(&self) -> String {
+        format!(
+            "{} total, {} active, {:.1}MB, {:.1}% CPU",
+            self.sessions, self.active, self.memory_mb, self.cpu_percent
+        )
+    }
+}
+
+fn session_totals<'a>(sessions: impl IntoIterator<Item = &'a TmuxSession>) -> SessionTotals {
+    let mut totals = SessionTotals::default();
+    for session in sessions {
+        totals.sessions += 1;
+        if session.attached {
+            totals.active += 1;
+        }
+        if let Some(ref resource) = session.resource_info {
+            totals.memory_mb += resource.memory_mb;
+            totals.cpu_percent += resource.cpu_percent;
+        }
+    }
+    totals
+}
+
+/// Section title for sessions whose owning process wasn't found.
+const UNRESOLVED_OWNER: &str = "—";
+
+/// Split `sessions` into titled sections. Users are ordered by memory use, heaviest first.
+fn group_sessions(
+    sessions: &[TmuxSession],
+    group_by: GroupBy,
+) -> Vec<(Option<String>, Vec<&TmuxSession>)> {
+    match group_by {
+        GroupBy::None => vec![(None, sessions.iter().collect())],
+        GroupBy::User => {
+            let mut groups: Vec<(String, Vec<&TmuxSession>)> = Vec::new();
+            for session in sessions {
+                let user = session
+                    .process_info
+                    .as_ref()
+                    .map_or(UNRESOLVED_OWNER, |info| info.user.as_str());
+                match groups.iter_mut().find(|(name, _)| name == user) {
+                    Some((_, members)) => members.push(session),
+                    None => groups.push((user.to_string(), vec![session])),
+                }
+            }
+            groups.sort_by(|(a_name, a), (b_name, b)| {
+                let a_memory = session_totals(a.iter().copied()).memory_mb;
+                let b_memory = session_totals(b.iter().copied()).memory_mb;
+                b_memory
+                    .total_cmp(&a_memory)
+                    .then_with(|| a_name.cmp(b_name))
+            });
+            groups
+                .into_iter()
+                .map(|(user, members)| (Some(user), members))
+                .collect()
+        }
+    }
+}
+
+/// Non-interactive `top --interval 0`: print the overview once as plain text.
+fn print_top_once(group_by: GroupBy, summary: bool) -> Result<()> {
+    let sessions = get_tmux_sessions()?;
+
+    println!(
+        "crabmux - Overview | {} | {}",
+        session_totals(&sessions).summary(),
+        chrono::Local::now().format("%H:%M:%S")
+    );
+    println!(
+        "{:<20} {:>4} {:>10} {:>7} Clients",
+        "Name", "Win", "Memory", "CPU"
+    );
+    for (title, members) in group_sessions(&sessions, group_by) {
+        if let Some(title) = title {
+            println!(
+                "[{}] {}",
+                title,
+                session_totals(members.iter().copied()).summary()
+            );
+        }
+        for session in members {
+            let (memory_info, cpu_info) = match session.resource_info {
+                Some(ref resource) => (
+                    format!("{:.1}MB", resource.memory_mb),
+                    format!("{:.1}%", resource.cpu_percent),
+                ),
+                None => ("N/A".to_string(), "N/A".to_string()),
+            };
+            println!(
+                "{} {:>4} {:>10} {:>7} {}",
+                pad_display(&truncate_display(&session.name, 20), 20),
+                session.windows,
+                memory_info,
+                cpu_info,
+                format_attached_users(session)
+            );
+        }
+    }
+    if summary {
+        println!("{}", top_summary_line(&sessions));
+    }
+
+    Ok(())
+}
+
+/// Closing totals line for one-shot `top` text dumps, matching the header.
+fn top_summary_line(sessions: &[TmuxSession]) -> String {
+    format!("Summary: {}", session_totals(sessions).summary())
+}
+
+/// `top --once --output FORMAT`: one sample in the `list` formats, with a
+/// timestamp column in CSV so repeated runs can be appended to one file
+/// (all but the first with `header` off). The text formats end with the
+/// totals line unless `summary` is off.
+fn print_top_snapshot(output: OutputFormat, header: bool, summary: bool) -> Result<()> {
+    let sessions = get_tmux_sessions_with_options(EnrichOptions {
+        swap_and_fds: output == OutputFormat::Json,
+        ..EnrichOptions::configured()
+    })?;
+    let rendered = render_sessions(&sessions, output, None, &ListStyle::default())?;
+    match output {
+        OutputFormat::Csv => {
+            let timestamp = chrono::Local::now().to_rfc3339();
+            let stamped = prepend_csv_column(&rendered, "timestamp", &timestamp);
+            if header {
+                print!("{}", stamped);
+            } else {
+                print!("{}", without_first_line(&stamped));
+            }
+        }
+        OutputFormat::Table | OutputFormat::Compact if summary => {
+            print!("{}", rendered);
+            println!("{}", top_summary_line(&sessions));
+        }
+        _ => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Add a leading column to CSV text: `header` on the first line, `value` on the rest.
+fn prepend_csv_column(csv: &str, header: &str, value: &str) -> String {
+    let mut out = String::new();
+    for (index, line) in csv.lines().enumerate() {
+        let cell = if index == 0 { header } else { value };
+        out.push_str(&format!("{},{}\n", csv_field(cell), line));
+    }
+    out
+}
+
+/// `text` minus its first line, e.g. a CSV without its header row.
+fn without_first_line(text: &str) -> &str {
+    text.split_once('\n').map_or("", |(_, rest)| rest)
+}
+
+/// Outcome of the last `auto_snapshot_secs` save, shown in the `top` footer.
+#[derive(Debug, Clone, PartialEq)]
+enum AutoSnapshotStatus {
+    Saved(chrono::DateTime<chrono::Local>),
+    /// There were no sessions, so the previous snapshot was kept
+    Skipped(chrono::DateTime<chrono::Local>),
+    Failed(String),
+}
+
+impl AutoSnapshotStatus {
+    fn label(&self) -> String {
+        match self {
+            AutoSnapshotStatus::Saved(at) => format!("last saved {}", at.format("%H:%M:%S")),
+            AutoSnapshotStatus::Skipped(at) => {
+                format!(
+                    "no sessions at {}, kept last snapshot",
+                    at.format("%H:%M:%S")
+                )
+            }
+            AutoSnapshotStatus::Failed(err) => format!("snapshot failed: {}", err),
+        }
+    }
+}
+
+fn run_top_mode(
+    idle_timeout: Option<Duration>,
+    refresh_interval: Duration,
+    group_by: GroupBy,
+    on_change: bool,
+    auto_snapshot: Option<Duration>,
+) -> Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut app = App::new()?;
+    let mut last_refresh = std::time::Instant::now();
+    let mut last_snapshot = Instant::now();
+    let mut snapshot_status: Option<AutoSnapshotStatus> = None;
+    let mut last_input = Instant::now();
+    // With --on-change, the sessions as last drawn and whether a redraw is owed
+    let mut drawn = app.sessions.clone();
+    let mut needs_draw = true;
+
+    loop {
+        // Don't keep polling tmux forever from a forgotten dashboard
+        if idle_timeout.is_some_and(|timeout| last_input.elapsed() >= timeout) {
+            break;
+        }
+
+        // Auto-refresh periodically so new sessions appear without input
+        if app.run_pending_refresh()? {
+            last_refresh = std::time::Instant::now();
+        } else if last_refresh.elapsed() >= refresh_interval {
+            app.refresh()?;
+            last_refresh = std::time::Instant::now();
+        }
+        if sessions_changed(&drawn, &app.sessions) {
+            drawn = app.sessions.clone();
+            needs_draw = true;
+        }
+
+        // A failed save is reported in the footer; the dashboard keeps running
+        if auto_snapshot.is_some_and(|every| last_snapshot.elapsed() >= every) {
+            snapshot_status = Some(match write_auto_snapshot() {
+                Ok(Some(_)) => AutoSnapshotStatus::Saved(chrono::Local::now()),
+                Ok(None) => AutoSnapshotStatus::Skipped(chrono::Local::now()),
+                Err(err) => AutoSnapshotStatus::Failed(err.to_string()),
+            });
+            last_snapshot = Instant::now();
+            needs_draw = true;
+        }
+
+        if needs_draw || !on_change {
+            terminal.draw(|f| draw_top_ui(f, &app, group_by, snapshot_status.as_ref()))?;
+            needs_draw = false;
+        }
+
+        if event::poll(Duration::from_millis(100))? {
+            let event = event::read()?;
+            if matches!(event, Event::Resize(..)) {
+                needs_draw = true;
+            }
+            if let Event::Key(key) = event {
+                last_input = Instant::now();
+                needs_draw = true;
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Char('r') => {
+                        let refreshed = app.request_refresh()?;
+                        if refreshed {
+                            last_refresh = std::time::Instant::now();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    Ok(())
+}
+
+/// Whether `top --on-change` should redraw: a session came or went, or its
+/// window count, attached state or activity moved. Memory and CPU are left
+/// out on purpose, they drift on every poll.
+fn sessions_changed(old: &[TmuxSession], new: &[TmuxSession]) -> bool {
+    old.len() != new.len()
+        || old.iter().zip(new).any(|(old, new)| {
+            old.name != new.name
+                || old.windows != new.windows
+                || old.attached != new.attached
+                || old.attached_clients != new.attached_clients
+                || old.activity != new.activity
+        })
+}
+
+fn top_session_item(app: &App, s: &TmuxSession) -> ListItem<'static> {
+    let (status, status_color) = status_marker(s.attached);
+    let user = format_attached_users(s);
+    let (memory_info, cpu_info) = if let Some(ref resource) = s.resource_info {
+        (
+            format!("{:.1}MB", resource.memory_mb),
+            format!("{:.1}%", resource.cpu_percent),
+        )
+    } else {
+        ("N/A".to_string(), "N/A".to_string())
+    };
+
+    let content = Line::from(vec![
+        Span::styled(
+            "▶ ",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(status, Style::default().fg(status_color)),
+        tag_marker_span(app.tags.get(&s.name)),
+        Span::styled(
+            pad_display(&truncate_display(&s.name, 12), 12),
+            Style::default()
+                .fg(tag_color(app.tags.get(&s.name)).unwrap_or(Color::White))
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            format!("{}W", s.windows),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            format!("{:<8}", memory_info),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            format!("{:<6}", cpu_info),
+            Style::default().fg(Color::Magenta),
+        ),
+        cpu_bar_span(app, s, Style::default().fg(Color::Magenta)),
+        resource_extras_span(app, s, Style::default().fg(Color::Magenta)),
+        Span::raw(" "),
+        Span::styled(
+            pad_display(&truncate_display(&user, 8), 8),
+            Style::default().fg(Color::Gray),
+        ),
+    ]);
+    ListItem::new(content)
+}
+
+/// Smallest terminals the layouts fit in, including the 1-cell margin.
+const MIN_TUI_SIZE: (u16, u16) = (30, 16);
+const MIN_TOP_SIZE: (u16, u16) = (30, 13);
+
+/// Draw a notice instead of the layout when `area` can't hold it; returns
+/// whether it did. Checked every frame, so growing the terminal recovers.
+fn draw_too_small(f: &mut Frame, min: (u16, u16)) -> bool {
+    let area = f.size();
+    if area.width >= min.0 && area.height >= min.1 {
+        return false;
+    }
+    let message = Paragraph::new(format!(
+        "Terminal too small (need at least {}x{})",
+        min.0, min.1
+    ))
+    .style(Style::default().fg(Color::Yellow))
+    .wrap(Wrap { trim: true });
+    f.render_widget(message, area);
+    true
+}
+
+fn draw_top_ui(
+    f: &mut Frame,
+    app: &App,
+    group_by: GroupBy,
+    snapshot_status: Option<&AutoSnapshotStatus>,
+) {
+    if draw_too_small(f, MIN_TOP_SIZE) {
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    // Header with system info
+    let header_text = format!(
+        "crabmux - Live Overview | {} | {}",
+        session_totals(&app.sessions).summary(),
+        chrono::Local::now().format("%H:%M:%S")
+    );
+    let header = Paragraph::new(header_text)
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    // Session list with detailed info
+    let mut sessions: Vec<ListItem> = Vec::new();
+    for (title, members) in group_sessions(&app.sessions, group_by) {
+        if let Some(title) = title {
+            sessions.push(ListItem::new(Line::from(Span::styled(
+                format!(
+                    "{} — {}",
+                    title,
+                    session_totals(members.iter().copied()).summary()
+                ),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+        }
+        sessions.extend(members.into_iter().map(|s| top_session_item(app, s)));
+    }
+
+    let title = " │ Name             │Win │  Memory │   CPU │ Clients ";
+    // Helper function to get terminal-appropriate styles
+    fn get_top_ui_highlight_style() -> Style {
+        let term = std::env::var("TERM").unwrap_or_else(|_| "unknown".to_string());
+        let term_program = std::env::var("TERM_PROGRAM").unwrap_or_else(|_| "unknown".to_string());
+        let colorterm = std::env::var("COLORTERM").unwrap_or_else(|_| "unknown".to_string());
+
+        // For Warp terminal and other terminals that may have issues with background colors
+        if term_program.contains("WarpTerminal") || term_program.contains("Warp") {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED)
+        } else if term.contains("screen") || term.contains("tmux") {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::REVERSED)
+        } else if colorterm.contains("truecolor") || term.contains("256color") {
+            Style::default()
+                .bg(Color::Rgb(0, 100, 200))
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::REVERSED)
+        }
+    }
+
+    fn get_top_ui_selection_symbol() -> &'static str {
+        let term_program = std::env::var("TERM_PROGRAM").unwrap_or_else(|_| "unknown".to_string());
+        let term = std::env::var("TERM").unwrap_or_else(|_| "unknown".to_string());
+
+        if term_program.contains("WarpTerminal") || term_program.contains("Warp") {
+            "===> "
+        } else if term_program.contains("iTerm") {
+            "▶ "
+        } else if term.contains("screen") || term.contains("tmux") {
+            "-> "
+        } else {
+            "► "
+        }
+    }
+
+    let sessions_list = List::new(sessions)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(get_top_ui_highlight_style())
+        .highlight_symbol(get_top_ui_selection_symbol());
+
+    f.render_widget(sessions_list, chunks[1]);
+
+    // Help
+    let mut help_spans = vec![Span::raw(
+        "Press 'q' to quit, 'r' to refresh, Ctrl+C to exit",
+    )];
+    if let Some(status) = snapshot_status {
+        let style = match status {
+            AutoSnapshotStatus::Saved(_) => Style::default(),
+            AutoSnapshotStatus::Skipped(_) => Style::default().add_modifier(Modifier::DIM),
+            AutoSnapshotStatus::Failed(_) => Style::default().fg(Color::Red),
+        };
+        help_spans.push(Span::raw(" │ "));
+        help_spans.push(Span::styled(status.label(), style));
+    }
+    let help = Paragraph::new(Line::from(help_spans))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Left)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[2]);
+}
+
+fn run_tui(fast: bool, switch_client: bool) -> Result<()> {
+    // Check if we're in a proper terminal
+    if !std::io::stdout().is_terminal() {
+        return run_plain_picker();
+    }
+
+    if let Err(err) = enable_raw_mode() {
+        if !io::stdin().is_terminal() {
+            return Err(err).context(
+                "Failed to enable raw mode. Make sure you're running in a supported terminal.",
+            );
+        }
+        eprintln!(
+            "warning: could not start the TUI ({}); showing a plain list instead",
+            err
+        );
+        return run_plain_picker();
+    }
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let config = load_config()?;
+    let refresh_interval = config.refresh_interval();
+    let lazy_enrich = fast || !config.enrich_in_tui;
+    let mut app = App::with_enrichment(lazy_enrich)?;
+    let mut list_state = ListState::default();
+    list_state.select(Some(0));
+    let mut last_refresh = Instant::now();
+
+    loop {
+        terminal.draw(|f| draw_ui(f, &mut app, &mut list_state))?;
+
+        let mut timeout = refresh_interval
+            .checked_sub(last_refresh.elapsed())
+            .unwrap_or(Duration::from_secs(0));
+        if app.refresh_pending {
+            timeout = timeout.min(app.refresh_wait(Instant::now()).unwrap_or_default());
+        }
+
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                match handle_input(&mut app, key)? {
+                    InputResult::Continue => {}
+                    InputResult::Quit => break,
+                    InputResult::AttachSession(AttachTarget::Local(name)) if switch_client => {
+                        match switch_client_with_executor(&DefaultTmuxExecutor, &name) {
+                            Ok(()) => break,
+                            Err(err) => app.set_status_message(format!("Switch failed: {}", err)),
+                        }
+                    }
+                    InputResult::AttachSession(target) => {
+                        // Clean up terminal before attaching
+                        disable_raw_mode()?;
+                        execute!(
+                            terminal.backend_mut(),
+                            LeaveAlternateScreen,
+                            DisableMouseCapture
+                        )?;
+                        terminal.show_cursor()?;
+
+                        // Attach to session
+                        match target {
+                            AttachTarget::Local(name) => {
+                                attach_session(Some(name), &AttachOptions::default())?;
+                            }
+                            AttachTarget::Remote(host, name) => {
+                                attach_remote_session(&host, &name)?;
+                            }
+                        }
+
+                        // Re-enter TUI mode after detaching
+                        let mut new_stdout = io::stdout();
+                        hard_reset_terminal(&mut new_stdout)?;
+                        enable_raw_mode()?;
+                        execute!(new_stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
+                        // Clear the screen and refresh the terminal
+                        execute!(
+                            new_stdout,
+                            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+                            crossterm::terminal::Clear(crossterm::terminal::ClearType::Purge),
+                            crossterm::cursor::MoveTo(0, 0)
+                        )?;
+                        let backend = CrosstermBackend::new(new_stdout);
+                        terminal = Terminal::new(backend)?;
+                        terminal.hide_cursor()?;
+                        terminal.clear()?;
+                        app.refresh()?;
+                        terminal.draw(|f| draw_ui(f, &mut app, &mut list_state))?;
+                        last_refresh = Instant::now();
+                    }
+                    InputResult::Refreshed => {
+                        last_refresh = Instant::now();
+                    }
+                }
+                app.enrich_near_selection();
+            }
+        }
+
+        if app.run_pending_refresh()? {
+            app.set_status_message(format!("Refreshed ({} sessions)", app.sessions.len()));
+            last_refresh = Instant::now();
+        }
+
+        if last_refresh.elapsed() >= refresh_interval {
+            app.refresh()?;
+            last_refresh = Instant::now();
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    Ok(())
+}
+
+/// Oldest tmux with `display-popup`.
+const POPUP_MIN_TMUX: (u32, u32) = (3, 2);
+
+/// `cmux popup`: run the picker in a tmux popup that closes once a session is
+/// picked (or the picker is quit), leaving the client where it was otherwise.
+fn run_popup(width: &str, height: &str, depth: usize) -> Result<()> {
+    if std::env::var_os("TMUX").is_none() {
+        return Err(anyhow::anyhow!(
+            "cmux popup only works inside tmux ($TMUX is not set); run plain `cmux` instead"
+        ));
+    }
+    if let Some(version) = tmux_version_with_executor(&DefaultTmuxExecutor) {
+        if version < POPUP_MIN_TMUX {
+            return Err(anyhow::anyhow!(
+                "cmux popup needs tmux {}.{} or newer for display-popup (found {}.{})",
+                POPUP_MIN_TMUX.0,
+                POPUP_MIN_TMUX.1,
+                version.0,
+                version.1
+            ));
+        }
+    }
+
+    // Run this binary by path so the popup works when cmux isn't on tmux's PATH
+    let exe = std::env::current_exe().context("Failed to locate the cmux executable")?;
+    let status = tmux_command()
+        .args(popup_args(&exe.to_string_lossy(), width, height, depth))
+        .status()
+        .context("Failed to execute tmux command")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("tmux display-popup failed"));
+    }
+    Ok(())
+}
+
+fn popup_args(exe: &str, width: &str, height: &str, depth: usize) -> Vec<String> {
+    vec![
+        "display-popup".to_string(),
+        "-E".to_string(),
+        "-e".to_string(),
+        format!("CMUX_DEPTH={}", depth),
+        "-w".to_string(),
+        width.to_string(),
+        "-h".to_string(),
+        height.to_string(),
+        format!("{} --switch-client", shell_quote(exe)),
+    ]
+}
+
+/// `(major, minor)` of the tmux on PATH; `None` when tmux is missing or its
+/// version string has no number (e.g. `tmux master`).
+fn tmux_version_with_executor(executor: &dyn TmuxExecutor) -> Option<(u32, u32)> {
+    let output = executor.execute_command(&["-V"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_tmux_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `tmux 3.3a`, `tmux next-3.4` and the like.
+fn parse_tmux_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().rsplit([' ', '-']).next()?;
+    let (major, rest) = version.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Point the calling tmux client at `session` (`switch-client`).
+fn switch_client_with_executor(executor: &dyn TmuxExecutor, session: &str) -> Result<()> {
+    let target = format!("={}", session);
+    let output = executor.execute_command(&["switch-client", "-t", &target])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn hard_reset_terminal(stdout: &mut impl Write) -> Result<()> {
+    stdout.write_all(b"\x1bc")?;
+    stdout.flush()?;
+    Ok(())
+}
+
+enum InputResult {
+    Continue,
+    Quit,
+    AttachSession(AttachTarget),
+    Refreshed,
+}
+
+#[derive(Debug, Clone)]
+enum AttachTarget {
+    Local(String),
+    Remote(HostConfig, String),
+}
+
+#[derive(Debug, Clone)]
+enum NewSessionTarget {
+    Local,
+    Remote(HostConfig),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostField {
+    Name,
+    Host,
+}
+
+#[derive(Debug, Clone)]
+struct KillTarget {
+    origin: SessionOrigin,
+    session_name: String,
+    attached_clients: usize,
+    /// The session cmux itself is running in
+    is_current: bool,
+}
+
+impl KillTarget {
+    fn from_entry(app: &App, entry: &SessionEntry) -> Self {
+        KillTarget {
+            origin: entry.origin.clone(),
+            session_name: entry.session.name.clone(),
+            attached_clients: entry.session.attached_clients,
+            is_current: matches!(entry.origin, SessionOrigin::Local)
+                && app.current_session.as_deref() == Some(entry.session.name.as_str()),
+        }
+    }
+
+    fn question(&self) -> String {
+        if self.is_current {
+            own_session_kill_question(&self.session_name)
+        } else {
+            format!(
+                "Kill session '{}' with {} attached client(s)?",
+                self.session_name, self.attached_clients
+            )
+        }
+    }
+}
+
+fn own_session_kill_question(session: &str) -> String {
+    format!(
+        "You are attached to '{}'. Killing it will detach you. Continue?",
+        session
+    )
+}
+
+fn handle_input(app: &mut App, key: KeyEvent) -> Result<InputResult> {
+    // Handle Ctrl+C for exit
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Ok(InputResult::Quit);
+    }
+
+    if app.show_new_host_popup {
+        match key.code {
+            KeyCode::Enter => {
+                match build_host_config(&app.new_host_name_input, &app.new_host_host_input) {
+                    Ok(host) => {
+                        add_host_config(host)?;
+                        app.hide_new_host_popup();
+                        app.refresh()?;
+                        return Ok(InputResult::Refreshed);
+                    }
+                    Err(err) => {
+                        app.new_host_error = Some(err.to_string());
+                    }
+                }
+            }
+            KeyCode::Esc => {
+                app.hide_new_host_popup();
+            }
+            KeyCode::Tab | KeyCode::Down => {
+                app.new_host_active_field = match app.new_host_active_field {
+                    HostField::Name => HostField::Host,
+                    HostField::Host => HostField::Name,
+                };
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                app.new_host_active_field = match app.new_host_active_field {
+                    HostField::Name => HostField::Host,
+                    HostField::Host => HostField::Name,
+                };
+            }
+            KeyCode::Left => {
+                move_cursor_left(
+                    &app.new_host_active_field,
+                    &mut app.new_host_name_cursor,
+                    &mut app.new_host_host_cursor,
+                );
+            }
+            KeyCode::Right => {
+                move_cursor_right(
+                    &app.new_host_active_field,
+                    &app.new_host_name_input,
+                    &app.new_host_host_input,
+                    &mut app.new_host_name_cursor,
+                    &mut app.new_host_host_cursor,
+                );
+            }
+            KeyCode::Home => {
+                set_cursor_start(
+                    &app.new_host_active_field,
+                    &mut app.new_host_name_cursor,
+                    &mut app.new_host_host_cursor,
+                );
+            }
+            KeyCode::End => {
+                set_cursor_end(
+                    &app.new_host_active_field,
+                    &app.new_host_name_input,
+                    &app.new_host_host_input,
+                    &mut app.new_host_name_cursor,
+                    &mut app.new_host_host_cursor,
+                );
+            }
+            KeyCode::Delete => {
+                app.new_host_error = None;
+                match app.new_host_active_field {
+                    HostField::Name => {
+                        remove_char_at(&mut app.new_host_name_input, &mut app.new_host_name_cursor);
+                    }
+                    HostField::Host => {
+                        remove_char_at(&mut app.new_host_host_input, &mut app.new_host_host_cursor);
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                app.new_host_error = None;
+                app.backspace_new_host_input();
+            }
+            KeyCode::Char(c) => {
+                app.new_host_error = None;
+                app.handle_new_host_input(c);
+            }
+            _ => {}
+        }
+        return Ok(InputResult::Continue);
+    }
+
+    if app.show_kill_confirm {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if let Some(target) = app.kill_confirm_target.clone() {
+                    match target.origin {
+                        SessionOrigin::Local => match kill_local_session(&target.session_name) {
+                            Ok(()) => {
+                                app.set_status_message(format!("Killed {}", target.session_name));
+                                app.refresh_after_kill()?;
+                                app.hide_kill_confirm();
+                                return Ok(InputResult::Refreshed);
+                            }
+                            Err(err) => {
+                                app.set_status_message(format!("Kill failed: {}", err));
+                            }
+                        },
+                        SessionOrigin::Remote(host) => {
+                            match kill_remote_session(&host, &target.session_name) {
+                                Ok(()) => {
+                                    app.set_status_message("Remote session killed.");
+                                    app.refresh()?;
+                                    app.hide_kill_confirm();
+                                    return Ok(InputResult::Refreshed);
+                                }
+                                Err(err) => {
+                                    app.set_status_message(format!("Kill failed: {}", err));
+                                }
+                            }
+                        }
+                    }
+                }
+                app.hide_kill_confirm();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                app.hide_kill_confirm();
+            }
+            _ => {}
+        }
+        return Ok(InputResult::Continue);
+    }
+
+    // Handle popup input if showing new session popup
+    if app.show_new_session_popup {
+        match key.code {
+            KeyCode::Enter => {
+                if app.new_session_name_suggestion().is_some() {
+                    return Ok(InputResult::Continue);
+                }
+                let session_name = if app.new_session_input.trim().is_empty() {
+                    default_session_name(&app.new_session_target_names(), app.new_session_prefix())
+                } else {
+                    apply_session_prefix(app.new_session_prefix(), &app.new_session_input)
+                };
+                match app.new_session_target.clone() {
+                    NewSessionTarget::Local => {
+                        if let Err(err) = new_session(Some(session_name), None, false) {
+                            app.set_status_message(format!("Create failed: {}", err));
+                        }
+                    }
+                    NewSessionTarget::Remote(host) => {
+                        new_session_remote(&host, Some(session_name))?;
+                    }
+                }
+                app.hide_new_session_popup();
+                app.refresh()?;
+                return Ok(InputResult::Refreshed);
+            }
+            KeyCode::Esc => {
+                app.hide_new_session_popup();
+            }
+            KeyCode::Tab => {
+                app.accept_new_session_suggestion();
+            }
+            KeyCode::Left => {
+                app.new_session_cursor = app.new_session_cursor.saturating_sub(1);
+            }
+            KeyCode::Right => {
+                let len = app.new_session_input.chars().count();
+                if app.new_session_cursor < len {
+                    app.new_session_cursor += 1;
+                }
+            }
+            KeyCode::Home => {
+                app.new_session_cursor = 0;
+            }
+            KeyCode::End => {
+                app.new_session_cursor = app.new_session_input.chars().count();
+            }
+            KeyCode::Delete => {
+                remove_char_at(&mut app.new_session_input, &mut app.new_session_cursor);
+            }
+            KeyCode::Backspace => {
+                app.backspace_new_session_input();
+            }
+            KeyCode::Char(c) => {
+                app.handle_new_session_input(c);
+            }
+            _ => {}
+        }
+        return Ok(InputResult::Continue);
+    }
+
+    if app.editing_name_filter {
+        match key.code {
+            KeyCode::Esc => app.clear_name_filter(),
+            // Keep the filter applied and hand the keys back to the list
+            KeyCode::Enter => app.editing_name_filter = false,
+            KeyCode::Down => app.next(),
+            KeyCode::Up => app.previous(),
+            KeyCode::Left => {
+                app.name_filter_cursor = app.name_filter_cursor.saturating_sub(1);
+            }
+            KeyCode::Right => {
+                let len = app.name_filter.chars().count();
+                if app.name_filter_cursor < len {
+                    app.name_filter_cursor += 1;
+                }
+            }
+            KeyCode::Home => {
+                app.name_filter_cursor = 0;
+            }
+            KeyCode::End => {
+                app.name_filter_cursor = app.name_filter.chars().count();
+            }
+            KeyCode::Delete => {
+                remove_char_at(&mut app.name_filter, &mut app.name_filter_cursor);
+                app.name_filter_changed();
+            }
+            KeyCode::Backspace => {
+                remove_char_before(&mut app.name_filter, &mut app.name_filter_cursor);
+                app.name_filter_changed();
+            }
+            KeyCode::Char(c) => {
+                insert_char_at(&mut app.name_filter, c, &mut app.name_filter_cursor);
+                app.name_filter_changed();
+            }
+            _ => {}
+        }
+        return Ok(InputResult::Continue);
+    }
+
+    let entries = app.build_entries();
+
+    // Normal input handling
+    if app.quit_prompt {
+        app.quit_prompt = false;
+        app.status_message = None;
+        if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+            return Ok(InputResult::Quit);
+        }
+        return Ok(InputResult::Continue);
+    }
+    if key.code != KeyCode::Char('d') {
+        // Any other key (e.g. moving the selection) cancels a pending `dd`
+        app.pending_delete = None;
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT)
+        && matches!(key.code, KeyCode::Up | KeyCode::Down)
+    {
+        if let Some(key) = app.sort {
+            app.set_status_message(format!(
+                "Sorted by {}; press S back to manual order to reorder",
+                key.label()
+            ));
+            return Ok(InputResult::Continue);
+        }
+        let delta = if key.code == KeyCode::Up { -1 } else { 1 };
+        if let Err(err) = app.move_selected_session(delta) {
+            app.set_status_message(format!("Failed to save order: {}", err));
+        }
+        return Ok(InputResult::Continue);
+    }
+
+    match key.code {
+        KeyCode::Char('q') => return Ok(app.request_quit()),
+        KeyCode::Esc if !app.name_filter.is_empty() => app.clear_name_filter(),
+        KeyCode::Esc => {
+            return Ok(if app.pop_view() || app.quit_key == QuitKey::Q {
+                InputResult::Continue
+            } else {
+                app.request_quit()
+            });
+        }
+        KeyCode::Backspace => {
+            app.pop_view();
+        }
+        KeyCode::Char('?') | KeyCode::Char('h') => app.toggle_help(),
+        KeyCode::Down | KeyCode::Char('j') => app.next(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous(),
+        KeyCode::Enter => {
+            if let Some(ListEntry::Session(entry)) = entries.get(app.selected) {
+                match &entry.origin {
+                    SessionOrigin::Local => {
+                        return Ok(InputResult::AttachSession(AttachTarget::Local(
+                            entry.session.name.clone(),
+                        )));
+                    }
+                    SessionOrigin::Remote(host) => {
+                        return Ok(InputResult::AttachSession(AttachTarget::Remote(
+                            host.clone(),
+                            entry.session.name.clone(),
+                        )));
+                    }
+                }
+            }
+        }
+        KeyCode::Char('n') => {
+            app.new_session_target = match entries.get(app.selected) {
+                Some(ListEntry::Session(entry)) => match &entry.origin {
+                    SessionOrigin::Local => NewSessionTarget::Local,
+                    SessionOrigin::Remote(host) => NewSessionTarget::Remote(host.clone()),
+                },
+                Some(ListEntry::Header {
+                    host: Some(host), ..
+                }) => NewSessionTarget::Remote(host.clone()),
+                _ => NewSessionTarget::Local,
+            };
+            app.show_new_session_popup();
+        }
+        KeyCode::Char('N') => {
+            // Skip the popup and create a detached scratch session right away
+            let existing: Vec<String> = app.sessions.iter().map(|s| s.name.clone()).collect();
+            let session_name = default_session_name(&existing, app.session_prefix.as_deref());
+            match new_detached_session(&session_name, false) {
+                Ok(()) => {
+                    app.refresh()?;
+                    app.select_local_session(&session_name);
+                    app.set_status_message(format!("Created {}", session_name));
+                    return Ok(InputResult::Refreshed);
+                }
+                Err(err) => app.set_status_message(format!("Create failed: {}", err)),
+            }
+        }
+        KeyCode::Char('H') => {
+            app.show_new_host_popup();
+        }
+        KeyCode::Char('K') => {
+            // Kill selected session
+            if let Some(ListEntry::Session(entry)) = entries.get(app.selected) {
+                if app.confirm_kill || entry.session.attached_clients > 0 {
+                    let target = KillTarget::from_entry(app, entry);
+                    app.show_kill_confirm(target);
+                    return Ok(InputResult::Continue);
+                }
+
+                return kill_entry_now(app, &entry.origin, &entry.session.name);
+            }
+        }
+        KeyCode::Char('r') => {
+            // Refresh session list; key repeat is coalesced by the throttle
+            let refreshed = app.request_refresh()?;
+            if refreshed {
+                app.set_status_message(format!("Refreshed ({} sessions)", app.sessions.len()));
+                return Ok(InputResult::Refreshed);
+            }
+        }
+        KeyCode::Char('p') => app.toggle_preview(),
+        KeyCode::Char('f') => app.cycle_view_filter(),
+        KeyCode::Char('/') => app.open_name_filter(),
+        KeyCode::Char('S') => app.cycle_sort(),
+        KeyCode::Char('i') => app.cycle_idle_filter(),
+        KeyCode::Char('o') => {
+            if let Some(session) = entries.get(app.selected).and_then(ListEntry::local_session) {
+                let message =
+                    match session_current_path_with_executor(&DefaultTmuxExecutor, &session.name) {
+                        Ok(Some(path)) => match open_in_file_manager(&path) {
+                            Ok(()) => format!(
+                                "Opened {}",
+                                collapse_home(&path, &std::env::var("HOME").unwrap_or_default())
+                            ),
+                            Err(err) => format!("Open failed: {}", err),
+                        },
+                        Ok(None) | Err(_) => format!("No directory found for {}", session.name),
+                    };
+                app.set_status_message(message);
+            }
+        }
+        KeyCode::Char('s') => {
+            // Save snapshot
+            match save_snapshot() {
+                Ok(path) => app.set_status_message(format!("Snapshot saved to {}", path.display())),
+                Err(err) => app.set_status_message(format!("Snapshot failed: {}", err)),
+            }
+        }
+        KeyCode::Char('d') => {
+            // Vim-style `dd`: the second press inside the window kills without a popup
+            if let Some(ListEntry::Session(entry)) = entries.get(app.selected) {
+                if app.take_pending_delete() {
+                    let target = KillTarget::from_entry(app, entry);
+                    if target.is_current {
+                        // Too easy to detach yourself by accident; always ask
+                        app.show_kill_confirm(target);
+                        return Ok(InputResult::Continue);
+                    }
+                    return kill_entry_now(app, &entry.origin, &entry.session.name);
+                }
+                app.pending_delete = Some(Instant::now());
+                app.set_status_message(format!("Press d again to kill {}", entry.session.name));
+            }
+        }
+        KeyCode::Char('D') => {
+            // Debug terminal info
+            eprintln!("{}", app.get_terminal_info());
+        }
+        // Letters and digits without a binding above jump by first character
+        KeyCode::Char(c)
+            if c.is_alphanumeric()
+                && !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            let found = app.jump_to_initial(c);
+            if !found {
+                app.set_status_message(format!("No session starting with '{}'", c));
+            }
+        }
+        _ => {}
+    }
+    Ok(InputResult::Continue)
+}
+
+/// Kill a session straight away (no popup) and report the result in the status line.
+fn kill_entry_now(app: &mut App, origin: &SessionOrigin, name: &str) -> Result<InputResult> {
+    match origin {
+        SessionOrigin::Local => match kill_local_session(name) {
+            Ok(()) => {
+                app.set_status_message(format!("Killed {}", name));
+                app.refresh_after_kill()?;
+                Ok(InputResult::Refreshed)
+            }
+            Err(err) => {
+                app.set_status_message(format!("Kill failed: {}", err));
+                Ok(InputResult::Continue)
+            }
+        },
+        SessionOrigin::Remote(host) => match kill_remote_session(host, name) {
+            Ok(()) => {
+                app.set_status_message("Remote session killed.");
+                app.refresh()?;
+                Ok(InputResult::Refreshed)
+            }
+            Err(err) => {
+                app.set_status_message(format!("Kill failed: {}", err));
+                Ok(InputResult::Continue)
+            }
+        },
+    }
+}
+
+const CPU_BAR_WIDTH: usize = 5;
+
+/// Horizontal gauge for `percent` (clamped to 0–100) drawn with eighth-block characters.
+fn cpu_bar(percent: f32, width: usize) -> String {
+    const PARTIALS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let eighths = ((percent.clamp(0.0, 100.0) / 100.0) * (width * 8) as f32).round() as usize;
+    let full = eighths / 8;
+    let mut bar = "█".repeat(full);
+    if full < width {
+        let partial = eighths % 8;
+        if partial > 0 {
+            bar.push(PARTIALS[partial]);
+        }
+    }
+    while bar.chars().count() < width {
+        bar.push('░');
+    }
+    bar
+}
+
+/// Columns the active window name is padded or truncated to.
+const ACTIVE_WINDOW_WIDTH: usize = 10;
+
+/// The active window column, or an empty span when `columns.active_window` is off.
+fn active_window_span(app: &App, session: &TmuxSession, is_selected: bool) -> Span<'static> {
+    if !app.columns.active_window {
+        return Span::raw("");
+    }
+    let name = app
+        .active_windows
+        .get(&session.name)
+        .map(String::as_str)
+        .unwrap_or("");
+    Span::styled(
+        format!(
+            " {}",
+            pad_display(
+                &truncate_display(name, ACTIVE_WINDOW_WIDTH),
+                ACTIVE_WINDOW_WIDTH
+            )
+        ),
+        Style::default().fg(if is_selected {
+            Color::Yellow
+        } else {
+            Color::Blue
+        }),
+    )
+}
+
+/// The CPU gauge column, or an empty span when it is turned off in the config.
+fn cpu_bar_span(app: &App, session: &TmuxSession, style: Style) -> Span<'static> {
+    if !app.columns.cpu_bar {
+        return Span::raw("");
+    }
+    let percent = session
+        .resource_info
+        .as_ref()
+        .map_or(0.0, |resource| resource.cpu_percent);
+    Span::styled(format!(" {}", cpu_bar(percent, CPU_BAR_WIDTH)), style)
+}
+
+/// Optional `sw:` / `fd:` columns after the CPU gauge, per `columns.swap` / `columns.fds`.
+fn resource_extras_span(app: &App, session: &TmuxSession, style: Style) -> Span<'static> {
+    let resource = session.resource_info.as_ref();
+    let mut text = String::new();
+    if app.columns.swap {
+        let swap = resource
+            .and_then(|r| r.swap_kb)
+            .map_or_else(|| "N/A".to_string(), format_kb);
+        text.push_str(&format!(" sw:{:<6}", swap));
+    }
+    if app.columns.fds {
+        let fds = resource
+            .and_then(|r| r.open_fds)
+            .map_or_else(|| "N/A".to_string(), |fds| fds.to_string());
+        text.push_str(&format!(" fd:{:<4}", fds));
+    }
+    Span::styled(text, style)
+}
+
+/// `512K`, `12.3M`, `1.2G`.
+fn format_kb(kb: u64) -> String {
+    if kb >= 1024 * 1024 {
+        format!("{:.1}G", kb as f64 / 1024.0 / 1024.0)
+    } else if kb >= 1024 {
+        format!("{:.1}M", kb as f64 / 1024.0)
+    } else {
+        format!("{}K", kb)
+    }
+}
+
+/// Render a tmux epoch-seconds field as local time, or "unknown" if it is empty or garbled.
+fn format_timestamp(epoch: &str) -> String {
+    epoch
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn format_attached_users(session: &TmuxSession) -> String {
+    if session.attached_clients == 0 {
+        return "none".to_string();
+    }
+
+    if !session.attached_users.is_empty() {
+        if session.attached_users.len() == 1 {
+            return session.attached_users[0].clone();
+        }
+        let extra = session.attached_users.len() - 1;
+        return format!("{}+{}", session.attached_users[0], extra);
+    }
+
+    format!(
+        "{} client{}",
+        session.attached_clients,
+        if session.attached_clients == 1 {
+            ""
+        } else {
+            "s"
+        }
+    )
+}
+
+/// Cut `s` to at most `width` terminal columns, ending in `…` when shortened.
+///
+/// Widths come from unicode-width, so CJK and emoji count as two columns and
+/// are never split.
+fn truncate_display(s: &str, width: usize) -> String {
+    if UnicodeWidthStr::width(s) <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for ch in s.chars() {
+        let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+        if used + ch_width > width - 1 {
+            break;
+        }
+        truncated.push(ch);
+        used += ch_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Left-align `s` in `width` terminal columns.
+///
+/// `format!("{:<N}")` pads by char count, which leaves wide glyphs one column
+/// over per character; this pads by display width. Longer strings are
+/// returned unchanged, so truncate first.
+fn pad_display(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(UnicodeWidthStr::width(s));
+    format!("{}{}", s, " ".repeat(pad))
+}
+
+/// Append `-2`, `-3`, ... to `base` until it no longer collides with `existing`.
+fn suggest_unique_name(base: &str, existing: &[String]) -> String {
+    let mut suffix = 2;
+    loop {
+        let candidate = format!("{}-{}", base, suffix);
+        if !existing.iter().any(|name| name == &candidate) {
+            return candidate;
+        }
+        suffix += 1;
+    }
+}
+
+fn build_host_config(name_input: &str, host_input: &str) -> Result<HostConfig> {
+    let host = host_input.trim();
+    if host.is_empty() {
+        return Err(anyhow::anyhow!("Host is required"));
+    }
+
+    let mut name = name_input.trim().to_string();
+    if name.is_empty() {
+        name = default_host_name(host);
+    }
+
+    Ok(HostConfig {
+        name,
+        host: host.to_string(),
+        key: None,
+    })
+}
+
+fn default_host_name(host: &str) -> String {
+    if let Some((_, host_part)) = host.rsplit_once('@') {
+        host_part.to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+fn insert_char_at(text: &mut String, ch: char, cursor: &mut usize) {
+    let mut chars: Vec<char> = text.chars().collect();
+    let pos = (*cursor).min(chars.len());
+    chars.insert(pos, ch);
+    *text = chars.iter().collect();
+    *cursor = pos + 1;
+}
+
+fn remove_char_before(text: &mut String, cursor: &mut usize) {
+    if *cursor == 0 {
+        return;
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    let pos = (*cursor).min(chars.len());
+    if pos == 0 {
+        return;
+    }
+    chars.remove(pos - 1);
+    *text = chars.iter().collect();
+    *cursor = pos - 1;
+}
+
+fn remove_char_at(text: &mut String, cursor: &mut usize) {
+    let mut chars: Vec<char> = text.chars().collect();
+    let pos = (*cursor).min(chars.len());
+    if pos >= chars.len() {
+        return;
+    }
+    chars.remove(pos);
+    *text = chars.iter().collect();
+}
+
+fn move_cursor_left(active: &HostField, name_cursor: &mut usize, host_cursor: &mut usize) {
+    match active {
+        HostField::Name => {
+            if *name_cursor > 0 {
+                *name_cursor -= 1;
+            }
+        }
+        HostField::Host => {
+            if *host_cursor > 0 {
+                *host_cursor -= 1;
+            }
+        }
+    }
+}
+
+fn move_cursor_right(
+    active: &HostField,
+    name_input: &str,
+    host_input: &str,
+    name_cursor: &mut usize,
+    host_cursor: &mut usize,
+) {
+    match active {
+        HostField::Name => {
+            let len = name_input.chars().count();
+            if *name_cursor < len {
+                *name_cursor += 1;
+            }
+        }
+        HostField::Host => {
+            let len = host_input.chars().count();
+            if *host_cursor < len {
+                *host_cursor += 1;
+            }
+        }
+    }
+}
+
+fn set_cursor_start(active: &HostField, name_cursor: &mut usize, host_cursor: &mut usize) {
+    match active {
+        HostField::Name => *name_cursor = 0,
+        HostField::Host => *host_cursor = 0,
+    }
+}
+
+fn set_cursor_end(
+    active: &HostField,
+    name_input: &str,
+    host_input: &str,
+    name_cursor: &mut usize,
+    host_cursor: &mut usize,
+) {
+    match active {
+        HostField::Name => *name_cursor = name_input.chars().count(),
+        HostField::Host => *host_cursor = host_input.chars().count(),
+    }
+}
+
+fn with_cursor(text: &str, cursor: usize, active: bool) -> String {
+    if !active {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut idx = 0;
+    for ch in text.chars() {
+        if idx == cursor {
+            result.push('|');
+        }
+        result.push(ch);
+        idx += 1;
+    }
+    if cursor >= idx {
+        result.push('|');
+    }
+    result
+}
+
+fn draw_ui(f: &mut Frame, app: &mut App, list_state: &mut ListState) {
+    app.clear_expired_status();
+    if draw_too_small(f, MIN_TUI_SIZE) {
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(if app.show_help { 7 } else { 5 }),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    // Header
+    let mut header_text = "crabmux - Mobile-Friendly tmux Manager".to_string();
+    if app.view_filter != ViewFilter::All {
+        header_text.push_str(&format!(" [{}]", app.view_filter.label()));
+    }
+    if app.idle_filter != IdleFilter::Off {
+        header_text.push_str(&format!(" [idle ≥ {}]", app.idle_filter.label()));
+    }
+    let header = Paragraph::new(header_text)
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let (list_area, preview_area) = if app.show_preview() {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        (split[0], Some(split[1]))
+    } else {
+        (chunks[1], None)
+    };
+
+    // Session list
+    let entries = app.build_entries();
+    if entries.is_empty() && !app.name_filter.is_empty() {
+        let empty_msg = Paragraph::new(format!(
+            "No sessions match '{}'.\nPress Esc to clear the filter.",
+            app.name_filter
+        ))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).title("Sessions"));
+        f.render_widget(empty_msg, list_area);
+    } else if entries.is_empty() {
+        let empty_msg =
+            Paragraph::new("No tmux sessions found.\nPress 'n' to create a new session.")
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).title("Sessions"));
+        f.render_widget(empty_msg, list_area);
+    } else {
+        let sessions: Vec<ListItem> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let is_selected = i == app.selected;
+                match entry {
+                    ListEntry::Header { title, .. } => {
+                        let content = Line::from(vec![Span::styled(
+                            title.as_str(),
+                            Style::default()
+                                .fg(Color::Cyan)
+                                .add_modifier(Modifier::BOLD),
+                        )]);
+                        let mut item = ListItem::new(content);
+                        if is_selected {
+                            item = item.style(app.get_highlight_style());
+                        }
+                        item
+                    }
+                    ListEntry::Session(entry) => {
+                        let s = &entry.session;
+                        let tag = match entry.origin {
+                            SessionOrigin::Local => app.tags.get(&s.name),
+                            SessionOrigin::Remote(_) => None,
+                        };
+                        let (status, status_color) = status_marker(s.attached);
+                        let user = format_attached_users(s);
+                        let is_current = matches!(entry.origin, SessionOrigin::Local)
+                            && app.current_session.as_deref() == Some(s.name.as_str());
+
+                        // Get resource info
+                        let (memory_info, cpu_info) = if let Some(ref resource) = s.resource_info {
+                            (
+                                format!("{:.1}MB", resource.memory_mb),
+                                format!("{:.1}%", resource.cpu_percent),
+                            )
+                        } else if app.lazy_enrich {
+                            ("…".to_string(), "…".to_string())
+                        } else {
+                            ("N/A".to_string(), "N/A".to_string())
+                        };
+
+                        // Add selection indicator prefix for better visibility
+                        let selection_prefix = app.get_selection_prefix(is_selected);
+
+                        let content = Line::from(vec![
+                            Span::styled(
+                                format!("{:<1}", selection_prefix),
+                                Style::default()
+                                    .fg(if is_selected {
+                                        Color::Yellow
+                                    } else {
+                                        Color::DarkGray
+                                    })
+                                    .add_modifier(if is_selected {
+                                        Modifier::BOLD
+                                    } else {
+                                        Modifier::empty()
+                                    }),
+                            ),
+                            Span::styled(
+                                format!("{:<1}", status),
+                                Style::default().fg(status_color),
+                            ),
+                            tag_marker_span(tag),
+                            Span::styled(
+                                pad_display(&truncate_display(&s.name, 15), 15),
+                                Style::default()
+                                    .fg(if is_selected {
+                                        Color::Yellow
+                                    } else {
+                                        tag_color(tag).unwrap_or(Color::White)
+                                    })
+                                    .add_modifier(if is_selected {
+                                        Modifier::BOLD | Modifier::UNDERLINED
+                                    } else {
+                                        Modifier::BOLD
+                                    }),
+                            ),
+                            Span::styled(
+                                format!("{:>3}W", s.windows),
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::White
+                                }),
+                            ),
+                            active_window_span(app, s, is_selected),
+                            Span::raw(" "),
+                            Span::styled(
+                                format!("{:>8}", memory_info),
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::Cyan
+                                }),
+                            ),
+                            Span::raw(" "),
+                            Span::styled(
+                                format!("{:>6}", cpu_info),
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::Magenta
+                                }),
+                            ),
+                            cpu_bar_span(
+                                app,
+                                s,
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::Magenta
+                                }),
+                            ),
+                            resource_extras_span(
+                                app,
+                                s,
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::Magenta
+                                }),
+                            ),
+                            Span::raw(" "),
+                            Span::styled(
+                                pad_display(&truncate_display(&user, 8), 8),
+                                Style::default().fg(if is_selected {
+                                    Color::Yellow
+                                } else {
+                                    Color::Gray
+                                }),
+                            ),
+                            if is_current {
+                                Span::styled(
+                                    CURRENT_MARKER,
+                                    Style::default()
+                                        .fg(Color::Green)
+                                        .add_modifier(Modifier::BOLD),
+                                )
+                            } else {
+                                Span::raw("")
+                            },
+                        ]);
+
+                        let mut item = ListItem::new(content);
+                        if is_selected {
+                            // Use terminal-aware highlighting
+                            item = item.style(app.get_highlight_style());
+                        }
+                        item
+                    }
+                }
+            })
+            .collect();
+
+        let mut title = session_list_title(app.sort);
+        if !app.name_filter.is_empty() {
+            title.push_str(&format!("│ /{} ", app.name_filter));
+        }
+        // Stays up after the prompt closes, since an older pattern is what's applied
+        if let Some(ref error) = app.name_filter_error {
+            title.push_str(&format!("│ invalid regex, showing last match: {} ", error));
+        }
+        let sessions_list = List::new(sessions)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(app.get_highlight_style())
+            .highlight_symbol(app.get_selection_symbol());
+
+        list_state.select(Some(app.selected));
+        f.render_stateful_widget(sessions_list, list_area, list_state);
+    }
+
+    if let Some(area) = preview_area {
+        draw_preview(f, app, &entries, area);
+    }
+
+    // Controls/Help
+    let help_text: Vec<Line> = if app.show_help {
+        vec![
+            Line::from(
+                "↑/↓/j/k: Navigate    Enter: Attach    n: New session    N: Quick new  H: Add host",
+            ),
+            Line::from("r: Refresh           s: Save snapshot  p: Preview  f: Filter  /: Search  i: Idle  o: Open dir"),
+            Line::from("K: Kill (asks if attached)   dd: Kill now, no popup   D: Debug terminal"),
+            Line::from("Shift+↑/↓: Reorder   S: Sort  q/Esc/Ctrl+C: Quit  ?: Toggle help"),
+            legend_line(app),
+        ]
+    } else {
+        vec![Line::from("Navigate: ↑/↓  Attach: Enter  New: n  Host: H  Kill: K/dd  Preview: p  Filter: f  Search: /  Debug: D  Quit: q/Ctrl+C  Help: ?")]
+    };
+
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("Controls"));
+    f.render_widget(help, chunks[2]);
+
+    // Transient feedback from the last action; the `/` prompt takes its place while open
+    if app.editing_name_filter {
+        let prompt = format!(
+            "/{}",
+            with_cursor(&app.name_filter, app.name_filter_cursor, true)
+        );
+        let mut spans = vec![Span::styled(
+            prompt,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )];
+        if let Some(ref error) = app.name_filter_error {
+            spans.push(Span::styled(
+                format!("  invalid regex: {}", error),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)), chunks[3]);
+    } else if let Some((ref message, _)) = app.status_message {
+        let status = Paragraph::new(message.as_str()).style(Style::default().fg(Color::Yellow));
+        f.render_widget(status, chunks[3]);
+    }
+
+    // Render popup if showing
+    if app.show_new_session_popup {
+        draw_new_session_popup(f, app);
+    }
+    if app.show_new_host_popup {
+        draw_new_host_popup(f, app);
+    }
+    if app.show_kill_confirm {
+        draw_kill_confirm_popup(f, app);
+    }
+}
+
+/// Column headings of the TUI session list, with `▲`/`▼` after the sorted one.
+/// Activity has no column of its own, so it gets a trailing heading.
+fn session_list_title(sort: Option<SortKey>) -> String {
+    const COLUMNS: [(&str, usize, Option<SortKey>); 5] = [
+        ("Name", 11, Some(SortKey::Name)),
+        ("Win", 3, Some(SortKey::Windows)),
+        ("Memory", 6, Some(SortKey::Memory)),
+        ("CPU", 5, Some(SortKey::Cpu)),
+        ("Clients", 7, None),
+    ];
+    let mut title = "Sessions".to_string();
+    for (label, width, key) in COLUMNS {
+        let heading = match sort {
+            Some(sort) if key == Some(sort) => format!("{} {}", label, sort.arrow()),
+            _ => label.to_string(),
+        };
+        title.push_str(&format!(" │ {}", pad_display(&heading, width)));
+    }
+    if sort == Some(SortKey::Activity) {
+        title.push_str(&format!(" │ Activity {}", SortKey::Activity.arrow()));
+    }
+    title.push(' ');
+    title
+}
+
+fn draw_preview(f: &mut Frame, app: &App, entries: &[ListEntry], area: Rect) {
+    let (title, capture) = match entries.get(app.selected) {
+        Some(ListEntry::Session(entry)) => match entry.origin {
+            SessionOrigin::Local => (format!("Preview: {}", entry.session.name), &app.preview),
+            SessionOrigin::Remote(_) => {
+                ("Preview: remote sessions not supported".to_string(), &None)
+            }
+        },
+        _ => ("Preview".to_string(), &None),
+    };
+
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(capture) = capture {
+        let visible = area.height.saturating_sub(2) as usize;
+        let overflow = capture.lines.len() > visible;
+        let body_len = if capture.truncated || overflow {
+            visible.saturating_sub(1)
+        } else {
+            visible
+        };
+        if (capture.truncated || overflow) && visible > 0 {
+            lines.push(Line::from(Span::styled(
+                TRUNCATION_INDICATOR,
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM),
+            )));
+        }
+        let skip = capture.lines.len().saturating_sub(body_len);
+        lines.extend(capture.lines[skip..].iter().map(|l| Line::from(l.as_str())));
+    }
+
+    let preview = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(preview, area);
+}
+
+fn draw_new_session_popup(f: &mut Frame, app: &App) {
+    let popup_area = centered_rect(50, 20, f.size());
+
+    // Clear the area
+    f.render_widget(Clear, popup_area);
+
+    let popup_block = Block::default()
+        .title("New Session")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let popup_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(popup_area);
+
+    f.render_widget(popup_block, popup_area);
+
+    let input_text = Paragraph::new("Enter session name (or press Enter for default):")
+        .style(Style::default().fg(Color::White));
+    f.render_widget(input_text, popup_chunks[0]);
+
+    let input_display = with_cursor(&app.new_session_input, app.new_session_cursor, true);
+    let input_field = Paragraph::new(input_display)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(input_field, popup_chunks[1]);
+
+    let target_label = match &app.new_session_target {
+        NewSessionTarget::Local => "Target: local".to_string(),
+        NewSessionTarget::Remote(host) => format!("Target: {} ({})", host.name, host.host),
+    };
+    let target_text = Paragraph::new(target_label).style(Style::default().fg(Color::Gray));
+    f.render_widget(target_text, popup_chunks[2]);
+
+    let default_name = format!(
+        "Default: {}",
+        default_session_name(&app.new_session_target_names(), app.new_session_prefix())
+    );
+    let default_text = Paragraph::new(default_name).style(Style::default().fg(Color::Gray));
+    f.render_widget(default_text, popup_chunks[3]);
+
+    let help_text = match app.new_session_name_suggestion() {
+        Some(suggestion) => {
+            Paragraph::new(format!("Name already exists (Tab: use '{}')", suggestion))
+                .style(Style::default().fg(Color::Red))
+        }
+        None => {
+            Paragraph::new("Enter: Create  Esc: Cancel").style(Style::default().fg(Color::Gray))
+        }
+    }
+    .alignment(Alignment::Center);
+    f.render_widget(help_text, popup_chunks[4]);
+}
+
+fn draw_new_host_popup(f: &mut Frame, app: &App) {
+    let popup_area = centered_rect(70, 40, f.size());
+
+    f.render_widget(Clear, popup_area);
+
+    let popup_block = Block::default()
+        .title("Add Remote Host")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let popup_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(popup_area);
+
+    f.render_widget(popup_block, popup_area);
+
+    let input_text = Paragraph::new("Add a remote host (name optional; key via config or CLI)")
+        .style(Style::default().fg(Color::White));
+    f.render_widget(input_text, popup_chunks[0]);
+
+    let name_active = app.new_host_active_field == HostField::Name;
+    let host_active = app.new_host_active_field == HostField::Host;
+
+    let name_label = Paragraph::new("Name (optional)").style(Style::default().fg(Color::Gray));
+    f.render_widget(name_label, popup_chunks[1]);
+
+    let name_display = with_cursor(
+        &app.new_host_name_input,
+        app.new_host_name_cursor,
+        name_active,
+    );
+    let name_field = Paragraph::new(name_display)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(if name_active {
+                    Color::Yellow
+                } else {
+                    Color::DarkGray
+                })),
+        );
+    f.render_widget(name_field, popup_chunks[2]);
+
+    let host_label = Paragraph::new("Host (user@host)").style(Style::default().fg(Color::Gray));
+    f.render_widget(host_label, popup_chunks[3]);
+
+    let host_display = with_cursor(
+        &app.new_host_host_input,
+        app.new_host_host_cursor,
+        host_active,
+    );
+    let host_field = Paragraph::new(host_display)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(if host_active {
+                    Color::Yellow
+                } else {
+                    Color::DarkGray
+                })),
+        );
+    f.render_widget(host_field, popup_chunks[4]);
+
+    let help_text = Paragraph::new("Tab/Shift+Tab: Switch  Enter: Save  Esc: Cancel")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(help_text, popup_chunks[5]);
+
+    if let Some(ref error) = app.new_host_error {
+        let error_text = Paragraph::new(error.as_str())
+            .style(Style::default().fg(Color::Red))
+            .alignment(Alignment::Left);
+        f.render_widget(error_text, popup_chunks[6]);
+    }
+}
+
+fn draw_kill_confirm_popup(f: &mut Frame, app: &App) {
+    let Some(ref target) = app.kill_confirm_target else {
+        return;
+    };
+
+    let popup_area = centered_rect(60, 25, f.size());
+    f.render_widget(Clear, popup_area);
+
+    let popup_block = Block::default()
+        .title("Confirm Kill")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+
+    let popup_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(popup_area);
+
+    f.render_widget(popup_block, popup_area);
+
+    let origin_label = match &target.origin {
+        SessionOrigin::Local => "Local session".to_string(),
+        SessionOrigin::Remote(host) => format!("Remote: {} ({})", host.name, host.host),
+    };
+    let line1 = Paragraph::new(origin_label).style(Style::default().fg(Color::Gray));
+    f.render_widget(line1, popup_chunks[0]);
+
+    let line2 = Paragraph::new(target.question()).style(
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_widget(line2, popup_chunks[1]);
+
+    let help_text = Paragraph::new("Enter/Y: Kill  N/Esc: Cancel")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(help_text, popup_chunks[2]);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    // Mock tmux executor for testing
+    struct MockTmuxExecutor {
+        responses: HashMap<String, Result<Output>>,
+    }
+
+    impl MockTmuxExecutor {
+        fn new() -> Self {
+            MockTmuxExecutor {
+                responses: HashMap::new(),
+            }
+        }
+
+        fn add_response(&mut self, args: Vec<&str>, stdout: &str, stderr: &str, success: bool) {
+            let key = args.join(" ");
+            let output = Output {
+                stdout: stdout.as_bytes().to_vec(),
+                stderr: stderr.as_bytes().to_vec(),
+                status: ExitStatus::from_raw(if success { 0 } else { 1 }),
+            };
+            self.responses.insert(key, Ok(output));
+        }
+
+        #[allow(dead_code)]
+        fn add_error_response(&mut self, args: Vec<&str>) {
+            let key = args.join(" ");
+            self.responses
+                .insert(key, Err(anyhow::anyhow!("Command failed")));
+        }
+    }
+
+    impl TmuxExecutor for MockTmuxExecutor {
+        fn execute_command(&self, args: &[&str]) -> Result<Output> {
+            let key = args.join(" ");
+            match self.responses.get(&key) {
+                Some(Ok(output)) => Ok(output.clone()),
+                Some(Err(e)) => Err(anyhow::anyhow!("{}", e)),
+                None => Err(anyhow::anyhow!("No mock response for: {}", key)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_tmux_sessions() {
+        let output = "main:3:2:1234567890:1234567890\ndev:1:0:1234567891:1234567891\ntest:2:1:1234567892:1234567892";
+        let sessions = parse_tmux_sessions(output);
+
+        assert_eq!(sessions.len(), 3);
+
+        assert_eq!(sessions[0].name, "main");
+        assert_eq!(sessions[0].windows, 3);
+        assert!(sessions[0].attached);
+        assert_eq!(sessions[0].attached_clients, 2);
+
+        assert_eq!(sessions[1].name, "dev");
+        assert_eq!(sessions[1].windows, 1);
+        assert!(!sessions[1].attached);
+        assert_eq!(sessions[1].attached_clients, 0);
+
+        assert_eq!(sessions[2].name, "test");
+        assert_eq!(sessions[2].windows, 2);
+        assert!(sessions[2].attached);
+        assert_eq!(sessions[2].attached_clients, 1);
+    }
+
+    #[test]
+    fn test_parse_tmux_sessions_empty() {
+        let output = "";
+        let sessions = parse_tmux_sessions(output);
+        assert_eq!(sessions.len(), 0);
+    }
+
+    #[test]
+    fn test_parse_tmux_sessions_invalid_format() {
+        let output = "invalid:format\nmain:3:1:1234567890:1234567890\nincomplete:data";
+        let sessions = parse_tmux_sessions(output);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "main");
+    }
+
+    #[test]
+    fn test_get_tmux_sessions_with_mock() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["list-sessions", "-F", "#{session_name}:|:#{session_windows}:|:#{session_attached}:|:#{session_created}:|:#{session_activity}|;|"],
+            "main:3:1:1234567890:1234567890\ndev:1:0:1234567891:1234567891",
+            "",
+            true,
+        );
+        // Add mock response for session info enrichment
+        executor.add_response(
+            vec!["list-sessions", "-t", "main", "-F", "#{session_id}"],
+            "$0",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["list-sessions", "-t", "dev", "-F", "#{session_id}"],
+            "$1",
+            "",
+            true,
+        );
+
+        let sessions = get_tmux_sessions_with_executor(&executor).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "main");
+        assert_eq!(sessions[1].name, "dev");
+        assert!(sessions[0].process_info.is_some());
+        assert!(sessions[0].resource_info.is_some());
+    }
+
+    #[test]
+    fn test_enrich_sessions_survives_one_failing_session() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["list-sessions", "-t", "main", "-F", "#{session_id}"],
+            "$0",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["list-sessions", "-t", "gone", "-F", "#{session_id}"],
+            "",
+            "can't find session: gone",
+            false,
+        );
+        executor.add_error_response(vec!["list-sessions", "-t", "broken", "-F", "#{session_id}"]);
+        executor.add_response(
+            vec!["list-sessions", "-t", "dev", "-F", "#{session_id}"],
+            "$3",
+            "",
+            true,
+        );
+        let mut sessions =
+            parse_tmux_sessions("main:1:0:1:1\ngone:1:0:2:2\nbroken:1:0:3:3\ndev:1:0:4:4");
+        let mut system = System::new();
+
+        let diagnostics = enrich_sessions(
+            &mut sessions,
+            &executor,
+            &mut system,
+            EnrichOptions::default(),
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "gone: can't find session: gone".to_string(),
+                "broken: Command failed".to_string(),
+            ]
+        );
+        assert_eq!(sessions.len(), 4);
+        assert!(sessions.iter().all(|s| s.resource_info.is_some()));
+    }
+
+    #[test]
+    fn test_enrich_without_process_fallback_leaves_gaps() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_error_response(vec!["list-sessions", "-t", "gone", "-F", "#{session_id}"]);
+        let mut sessions = parse_tmux_sessions("gone:1:0:1:1");
+        let mut system = System::new();
+
+        enrich_sessions(
+            &mut sessions,
+            &executor,
+            &mut system,
+            EnrichOptions {
+                fallback_process_info: false,
+                ..EnrichOptions::default()
+            },
+        );
+
+        assert!(sessions[0].process_info.is_none());
+        assert_eq!(
+            session_json_field(&sessions[0], "user"),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            session_json_field(&sessions[0], "command"),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_get_tmux_sessions_no_server() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["list-sessions", "-F", "#{session_name}:|:#{session_windows}:|:#{session_attached}:|:#{session_created}:|:#{session_activity}|;|"],
+            "",
+            "no server running on /tmp/tmux-1000/default",
+            false,
+        );
+
+        let sessions = get_tmux_sessions_with_executor(&executor).unwrap();
+        assert_eq!(sessions.len(), 0);
+    }
+
+    #[test]
+    fn test_tmux_session_struct() {
+        let session = TmuxSession {
+            name: "test".to_string(),
+            windows: 2,
+            attached: true,
+            attached_clients: 1,
+            attached_users: Vec::new(),
+            created: "1234567890".to_string(),
+            activity: "1234567890".to_string(),
+            process_info: None,
+            resource_info: None,
+            server: None,
+            extra_fields: BTreeMap::new(),
+            window_list: Vec::new(),
+        };
+
+        assert_eq!(session.name, "test");
+        assert_eq!(session.windows, 2);
+        assert!(session.attached);
+    }
+
+    fn test_session(name: &str) -> TmuxSession {
+        TmuxSession {
+            name: name.to_string(),
+            windows: 1,
+            attached: false,
+            attached_clients: 0,
+            attached_users: Vec::new(),
+            created: "123".to_string(),
+            activity: "123".to_string(),
+            process_info: None,
+            resource_info: None,
+            server: None,
+            extra_fields: BTreeMap::new(),
+            window_list: Vec::new(),
+        }
+    }
+
+    fn test_app(sessions: Vec<TmuxSession>) -> App {
+        App {
+            sessions,
+            remote_hosts: Vec::new(),
+            selected: 0,
+            show_help: false,
+            aliases: HashMap::new(),
+            tags: HashMap::new(),
+            session_order: Vec::new(),
+            view_filter: ViewFilter::default(),
+            idle_filter: IdleFilter::default(),
+            sort: None,
+            name_filter: String::new(),
+            name_filter_cursor: 0,
+            name_filter_regex: None,
+            name_filter_error: None,
+            editing_name_filter: false,
+            columns: ColumnConfig::default(),
+            fallback_process_info: true,
+            active_windows: HashMap::new(),
+            current_session: None,
+            hosts: Vec::new(),
+            show_new_session_popup: false,
+            new_session_input: String::new(),
+            new_session_cursor: 0,
+            new_session_target: NewSessionTarget::Local,
+            show_new_host_popup: false,
+            new_host_name_input: String::new(),
+            new_host_name_cursor: 0,
+            new_host_host_input: String::new(),
+            new_host_host_cursor: 0,
+            new_host_active_field: HostField::Host,
+            new_host_error: None,
+            show_kill_confirm: false,
+            kill_confirm_target: None,
+            status_message: None,
+            pending_delete: None,
+            quit_key: QuitKey::default(),
+            quit_confirm: false,
+            confirm_kill: false,
+            quit_prompt: false,
+            double_press_window: Duration::from_millis(DEFAULT_DOUBLE_PRESS_MS),
+            session_prefix: None,
+            view_stack: Vec::new(),
+            last_refresh_attempt: None,
+            refresh_pending: false,
+            preview: None,
+            lazy_enrich: false,
+            system: System::new_all(),
+        }
+    }
+
+    #[test]
+    fn test_app_navigation() {
+        let mut app = App {
+            sessions: vec![
+                TmuxSession {
+                    name: "session1".to_string(),
+                    windows: 1,
+                    attached: false,
+                    attached_clients: 0,
+                    attached_users: Vec::new(),
+                    created: "123".to_string(),
+                    activity: "123".to_string(),
+                    process_info: None,
+                    resource_info: None,
+                    server: None,
+                    extra_fields: BTreeMap::new(),
+                    window_list: Vec::new(),
+                },
+                TmuxSession {
+                    name: "session2".to_string(),
+                    windows: 2,
+                    attached: false,
+                    attached_clients: 0,
+                    attached_users: Vec::new(),
+                    created: "124".to_string(),
+                    activity: "124".to_string(),
+                    process_info: None,
+                    resource_info: None,
+                    server: None,
+                    extra_fields: BTreeMap::new(),
+                    window_list: Vec::new(),
+                },
+                TmuxSession {
+                    name: "session3".to_string(),
+                    windows: 3,
+                    attached: false,
+                    attached_clients: 0,
+                    attached_users: Vec::new(),
+                    created: "125".to_string(),
+                    activity: "125".to_string(),
+                    process_info: None,
+                    resource_info: None,
+                    server: None,
+                    extra_fields: BTreeMap::new(),
+                    window_list: Vec::new(),
+                },
+            ],
+            remote_hosts: Vec::new(),
+            selected: 0,
+            show_help: false,
+            aliases: HashMap::new(),
+            tags: HashMap::new(),
+            session_order: Vec::new(),
+            view_filter: ViewFilter::default(),
+            idle_filter: IdleFilter::default(),
+            sort: None,
+            name_filter: String::new(),
+            name_filter_cursor: 0,
+            name_filter_regex: None,
+            name_filter_error: None,
+            editing_name_filter: false,
+            columns: ColumnConfig::default(),
+            fallback_process_info: true,
+            active_windows: HashMap::new(),
+            current_session: None,
+            hosts: Vec::new(),
+            show_new_session_popup: false,
+            new_session_input: String::new(),
+            new_session_cursor: 0,
+            new_session_target: NewSessionTarget::Local,
+            show_new_host_popup: false,
+            new_host_name_input: String::new(),
+            new_host_name_cursor: 0,
+            new_host_host_input: String::new(),
+            new_host_host_cursor: 0,
+            new_host_active_field: HostField::Host,
+            new_host_error: None,
+            show_kill_confirm: false,
+            kill_confirm_target: None,
+            status_message: None,
+            pending_delete: None,
+            quit_key: QuitKey::default(),
+            quit_confirm: false,
+            confirm_kill: false,
+            quit_prompt: false,
+            double_press_window: Duration::from_millis(DEFAULT_DOUBLE_PRESS_MS),
+            session_prefix: None,
+            view_stack: Vec::new(),
+            last_refresh_attempt: None,
+            refresh_pending: false,
+            preview: None,
+            lazy_enrich: false,
+            system: System::new_all(),
+        };
+
+        // Test next navigation
+        assert_eq!(app.selected, 0);
+        app.next();
+        assert_eq!(app.selected, 1);
+        app.next();
+        assert_eq!(app.selected, 2);
+        app.next();
+        assert_eq!(app.selected, 0); // Should wrap around
+
+        // Test previous navigation
+        app.previous();
+        assert_eq!(app.selected, 2); // Should wrap around
+        app.previous();
+        assert_eq!(app.selected, 1);
+        app.previous();
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn test_app_navigation_empty() {
+        let mut app = App {
+            sessions: vec![],
+            remote_hosts: Vec::new(),
+            selected: 0,
+            show_help: false,
+            aliases: HashMap::new(),
+            tags: HashMap::new(),
+            session_order: Vec::new(),
+            view_filter: ViewFilter::default(),
+            idle_filter: IdleFilter::default(),
+            sort: None,
+            name_filter: String::new(),
+            name_filter_cursor: 0,
+            name_filter_regex: None,
+            name_filter_error: None,
+            editing_name_filter: false,
+            columns: ColumnConfig::default(),
+            fallback_process_info: true,
+            active_windows: HashMap::new(),
+            current_session: None,
+            hosts: Vec::new(),
+            show_new_session_popup: false,
+            new_session_input: String::new(),
+            new_session_cursor: 0,
+            new_session_target: NewSessionTarget::Local,
+            show_new_host_popup: false,
+            new_host_name_input: String::new(),
+            new_host_name_cursor: 0,
+            new_host_host_input: String::new(),
+            new_host_host_cursor: 0,
+            new_host_active_field: HostField::Host,
+            new_host_error: None,
+            show_kill_confirm: false,
+            kill_confirm_target: None,
+            status_message: None,
+            pending_delete: None,
+            quit_key: QuitKey::default(),
+            quit_confirm: false,
+            confirm_kill: false,
+            quit_prompt: false,
+            double_press_window: Duration::from_millis(DEFAULT_DOUBLE_PRESS_MS),
+            session_prefix: None,
+            view_stack: Vec::new(),
+            last_refresh_attempt: None,
+            refresh_pending: false,
+            preview: None,
+            lazy_enrich: false,
+            system: System::new_all(),
+        };
+
+        // Navigation should not crash with empty sessions
+        app.next();
+        assert_eq!(app.selected, 0);
+        app.previous();
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn test_toggle_help() {
+        let mut app = App {
+            sessions: vec![],
+            remote_hosts: Vec::new(),
+            selected: 0,
+            show_help: false,
+            aliases: HashMap::new(),
+            tags: HashMap::new(),
+            session_order: Vec::new(),
+            view_filter: ViewFilter::default(),
+            idle_filter: IdleFilter::default(),
+            sort: None,
+            name_filter: String::new(),
+            name_filter_cursor: 0,
+            name_filter_regex: None,
+            name_filter_error: None,
+            editing_name_filter: false,
+            columns: ColumnConfig::default(),
+            fallback_process_info: true,
+            active_windows: HashMap::new(),
+            current_session: None,
+            hosts: Vec::new(),
+            show_new_session_popup: false,
+            new_session_input: String::new(),
+            new_session_cursor: 0,
+            new_session_target: NewSessionTarget::Local,
+            show_new_host_popup: false,
+            new_host_name_input: String::new(),
+            new_host_name_cursor: 0,
+            new_host_host_input: String::new(),
+            new_host_host_cursor: 0,
+            new_host_active_field: HostField::Host,
+            new_host_error: None,
+            show_kill_confirm: false,
+            kill_confirm_target: None,
+            status_message: None,
+            pending_delete: None,
+            quit_key: QuitKey::default(),
+            quit_confirm: false,
+            confirm_kill: false,
+            quit_prompt: false,
+            double_press_window: Duration::from_millis(DEFAULT_DOUBLE_PRESS_MS),
+            session_prefix: None,
+            view_stack: Vec::new(),
+            last_refresh_attempt: None,
+            refresh_pending: false,
+            preview: None,
+            lazy_enrich: false,
+            system: System::new_all(),
+        };
+
+        assert!(!app.show_help);
+        app.toggle_help();
+        assert!(app.show_help);
+        app.toggle_help();
+        assert!(!app.show_help);
+    }
+
+    #[test]
+    fn test_session_snapshot_serialization() {
+        let sessions = vec![TmuxSession {
+            name: "main".to_string(),
+            windows: 3,
+            attached: true,
+            attached_clients: 1,
+            attached_users: Vec::new(),
+            created: "123".to_string(),
+            activity: "456".to_string(),
+            process_info: None,
+            resource_info: None,
+            server: None,
+            extra_fields: BTreeMap::new(),
+            window_list: Vec::new(),
+        }];
+
+        let snapshot = SessionSnapshot {
+            sessions: sessions.clone(),
+            timestamp: "2024-01-01T00:00:00".to_string(),
+            layouts: BTreeMap::new(),
+            order: Vec::new(),
+        };
+
+        // Test serialization
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"name\":\"main\""));
+        assert!(json.contains("\"windows\":3"));
+        assert!(json.contains("\"attached\":true"));
+
+        // Test deserialization
+        let deserialized: SessionSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.sessions.len(), 1);
+        assert_eq!(deserialized.sessions[0].name, "main");
+        assert_eq!(deserialized.timestamp, "2024-01-01T00:00:00");
+    }
+
+    #[test]
+    fn test_input_result_variants() {
+        // Test that InputResult enum variants work correctly
+        let result1 = InputResult::Continue;
+        let result2 = InputResult::Quit;
+        let result3 = InputResult::AttachSession(AttachTarget::Local("test".to_string()));
+        let result4 = InputResult::Refreshed;
+
+        match result1 {
+            InputResult::Continue => {}
+            _ => panic!("Expected Continue"),
+        }
+
+        match result2 {
+            InputResult::Quit => {}
+            _ => panic!("Expected Quit"),
+        }
+
+        match result3 {
+            InputResult::AttachSession(AttachTarget::Local(name)) => assert_eq!(name, "test"),
+            _ => panic!("Expected AttachSession"),
+        }
+
+        match result4 {
+            InputResult::Refreshed => {}
+            _ => panic!("Expected Refreshed"),
+        }
+    }
+
+    #[test]
+    fn test_parse_capture_output_truncated() {
+        let output = "one\ntwo\nthree\nfour\n\n\n";
+        let capture = parse_capture_output(output, 2);
+        assert!(capture.truncated);
+        assert_eq!(capture.lines, vec!["three", "four"]);
+    }
+
+    #[test]
+    fn test_parse_capture_output_within_limit() {
+        let capture = parse_capture_output("one\ntwo\n", 5);
+        assert!(!capture.truncated);
+        assert_eq!(capture.lines, vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_capture_pane_with_mock() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["capture-pane", "-p", "-t", "main", "-S", "-3"],
+            "a\nb\nc\nd\ne\n",
+            "",
+            true,
+        );
+
+        let capture = capture_pane_with_executor(&executor, "main", 3).unwrap();
+        assert!(capture.truncated);
+        assert_eq!(capture.lines, vec!["c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_suggest_unique_name() {
+        let existing = vec!["dev".to_string(), "dev-2".to_string()];
+        assert_eq!(suggest_unique_name("dev", &existing), "dev-3");
+        assert_eq!(suggest_unique_name("api", &existing), "api-2");
+    }
+
+    #[test]
+    fn test_new_session_name_suggestion() {
+        let mut app = test_app(vec![test_session("dev")]);
+        app.new_session_input = "dev".to_string();
+        assert_eq!(app.new_session_name_suggestion(), Some("dev-2".to_string()));
+
+        app.accept_new_session_suggestion();
+        assert_eq!(app.new_session_input, "dev-2");
+        assert_eq!(app.new_session_cursor, 5);
+        assert_eq!(app.new_session_name_suggestion(), None);
+    }
+
+    #[test]
+    fn test_new_session_name_suggestion_applies_prefix() {
+        let mut app = test_app(vec![test_session("w-dev")]);
+        app.session_prefix = Some("w-".to_string());
+        app.new_session_input = "dev".to_string();
+        assert_eq!(
+            app.new_session_name_suggestion(),
+            Some("w-dev-2".to_string())
+        );
+
+        app.new_session_input = "api".to_string();
+        assert_eq!(app.new_session_name_suggestion(), None);
+    }
+
+    #[test]
+    fn test_parse_tmux_sessions_duplicate_names() {
+        let output = "main:3:1:1234567890:1234567890\nmain:1:0:1234567891:1234567891";
+        let sessions = parse_tmux_sessions(output);
+
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "main");
+        assert_eq!(sessions[0].windows, 3);
+    }
+
+    #[test]
+    fn test_status_message_expires() {
+        let mut app = test_app(vec![]);
+        app.set_status_message("Snapshot saved");
+        app.clear_expired_status();
+        assert_eq!(
+            app.status_message.as_ref().map(|(m, _)| m.as_str()),
+            Some("Snapshot saved")
+        );
+
+        app.status_message = Some((
+            "old".to_string(),
+            Instant::now() - STATUS_MESSAGE_TTL - Duration::from_millis(1),
+        ));
+        app.clear_expired_status();
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_session_group_members() {
+        let output = "1:|:main:|:main\n1:|:main:|:main-2\n0:|::|:dev\n1:|:other:|:other\n";
+        assert_eq!(session_group_members(output, "main"), vec!["main-2"]);
+        assert_eq!(session_group_members(output, "main-2"), vec!["main"]);
+        assert!(session_group_members(output, "dev").is_empty());
+        assert!(session_group_members(output, "other").is_empty());
+    }
+
+    #[test]
+    fn test_tag_color_lookup() {
+        assert_eq!(tag_color(Some(&"red".to_string())), Some(Color::Red));
+        assert_eq!(tag_color(Some(&"purple".to_string())), None);
+        assert_eq!(tag_color(None), None);
+        assert_eq!(tag_ansi_code("gray"), Some(90));
+    }
+
+    #[test]
+    fn test_parse_json_fields() {
+        assert_eq!(
+            parse_json_fields("name, cpu").unwrap(),
+            vec!["name".to_string(), "cpu".to_string()]
+        );
+        let err = parse_json_fields("name,bogus").unwrap_err().to_string();
+        assert!(err.contains("Unknown field 'bogus'"));
+        assert!(err.contains("Valid fields: name"));
+        assert!(parse_json_fields(",").is_err());
+    }
+
+    #[test]
+    fn test_project_sessions() {
+        let mut session = test_session("main");
+        session.resource_info = Some(ResourceInfo {
+            memory_mb: 12.5,
+            cpu_percent: 3.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        let fields = vec!["name".to_string(), "cpu".to_string()];
+        let projected = project_sessions(&[session], &fields);
+
+        assert_eq!(projected.len(), 1);
+        let object = projected[0].as_object().unwrap();
+        assert_eq!(object.len(), 2);
+        assert_eq!(object["name"], "main");
+        assert_eq!(object["cpu"], 3.0);
+    }
+
+    #[test]
+    fn test_project_swap_and_fds() {
+        let mut measured = test_session("measured");
+        measured.resource_info = Some(ResourceInfo {
+            memory_mb: 1.0,
+            cpu_percent: 0.0,
+            swap_kb: Some(2048),
+            open_fds: Some(17),
+        });
+        let fields = parse_json_fields("name,swap,fds").unwrap();
+        let projected = project_sessions(&[measured, test_session("skipped")], &fields);
+
+        assert_eq!(projected[0]["swap"], 2048);
+        assert_eq!(projected[0]["fds"], 17);
+        // Without `swap_and_fds` enrichment these are never read
+        assert!(projected[1]["swap"].is_null());
+        assert!(projected[1]["fds"].is_null());
+    }
+
+    #[test]
+    fn test_apply_session_order() {
+        let mut sessions = vec![
+            test_session("a"),
+            test_session("b"),
+            test_session("c"),
+            test_session("new"),
+        ];
+        apply_session_order(&mut sessions, &["c".to_string(), "a".to_string()]);
+
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["c", "a", "b", "new"]);
+    }
+
+    #[test]
+    fn test_refresh_after_killing_last_session() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["list-sessions", "-F", TMUX_LIST_FORMAT],
+            "",
+            "lost server",
+            false,
+        );
+
+        // The server exiting after the last kill reads as "no sessions"
+        let sessions = get_tmux_sessions_with_executor(&executor).unwrap();
+        assert!(sessions.is_empty());
+
+        // Unrecognized errors are also tolerated once the last session is gone
+        let failed = Err(anyhow::anyhow!("tmux command failed: unexpected EOF"));
+        assert!(sessions_after_kill(failed, 1).unwrap().is_empty());
+
+        let failed = Err(anyhow::anyhow!("tmux command failed: unexpected EOF"));
+        assert!(sessions_after_kill(failed, 3).is_err());
+    }
+
+    #[test]
+    fn test_ssh_connection_refused_is_not_no_server() {
+        // An unreachable host must surface as an error, not as zero sessions
+        let stderr = "ssh: connect to host example.com port 22: Connection refused";
+        assert!(!is_no_server_error(stderr));
+        assert!(is_server_shutdown_error(stderr));
+        assert!(is_no_server_error(
+            "no server running on /tmp/tmux-1000/default"
+        ));
+    }
+
+    #[test]
+    fn test_current_tmux_session_from_pane() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["display-message", "-p", "-t", "%3", "#{session_name}"],
+            "work\n",
+            "",
+            true,
+        );
+
+        let name = current_tmux_session_with_executor(&executor, Some("%3")).unwrap();
+        assert_eq!(name, "work");
+    }
+
+    #[test]
+    fn test_truncate_display() {
+        assert_eq!(truncate_display("alice", 8), "alice");
+        assert_eq!(truncate_display("verylongusername", 8), "verylon…");
+        // CJK glyphs are two columns wide and must not be split
+        assert_eq!(truncate_display("测试用户名字", 8), "测试用…");
+        assert_eq!(truncate_display("测试", 4), "测试");
+        assert_eq!(truncate_display("🚀🚀🚀🚀🚀", 6), "🚀🚀…");
+        assert!(UnicodeWidthStr::width(truncate_display("🚀rocket-session", 8).as_str()) <= 8);
+        assert_eq!(truncate_display("abc", 0), "");
+    }
+
+    #[test]
+    fn test_config_defaults_for_missing_keys() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(!config.confirm_all);
+        assert_eq!(config.attach_when_empty, AttachWhenEmpty::Error);
+
+        let config: Config = serde_json::from_str(r#"{"attach_when_empty": "create"}"#).unwrap();
+        assert_eq!(config.attach_when_empty, AttachWhenEmpty::Create);
+
+        let config: Config = serde_json::from_str(r#"{"confirm_all": true}"#).unwrap();
+        assert!(config.confirm_all);
+    }
+
+    #[test]
+    fn test_config_refresh_sort_and_kill_preferences() {
+        let config = Config::default();
+        assert_eq!(
+            config.refresh_interval(),
+            Duration::from_secs(DEFAULT_REFRESH_SECS)
+        );
+        assert_eq!(config.default_sort, None);
+        assert!(!config.confirm_kill);
+
+        let config: Config = serde_json::from_str(
+            r#"{"refresh_secs": 0, "default_sort": "memory", "confirm_kill": true}"#,
+        )
+        .unwrap();
+        assert_eq!(config.refresh_interval(), Duration::from_secs(1));
+        assert_eq!(config.default_sort, Some(SortKey::Memory));
+        assert!(config.confirm_kill);
+        assert!(config.fallback_process_info);
+
+        assert!(serde_json::from_str::<Config>(r#"{"default_sort": "size"}"#).is_err());
     }
 
-    let entries = app.build_entries();
+    #[test]
+    fn test_confirm_kill_prompts_for_detached_sessions() {
+        let mut app = test_app(vec![test_session("dev")]);
+        app.confirm_kill = true;
+        handle_input(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('K'), KeyModifiers::NONE),
+        )
+        .unwrap();
+        assert!(app.show_kill_confirm);
+        assert_eq!(
+            app.kill_confirm_target
+                .as_ref()
+                .map(|t| t.session_name.as_str()),
+            Some("dev")
+        );
+    }
 
-    // Normal input handling
-    match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => return Ok(InputResult::Quit),
-        KeyCode::Char('?') | KeyCode::Char('h') => app.toggle_help(),
-        KeyCode::Down | KeyCode::Char('j') => app.next(),
-        KeyCode::Up | KeyCode::Char('k') => app.previous(),
-        KeyCode::Enter => {
-            if let Some(ListEntry::Session(entry)) = entries.get(app.selected) {
-                match &entry.origin {
-                    SessionOrigin::Local => {
-                        return Ok(InputResult::AttachSession(AttachTarget::Local(
-                            entry.session.name.clone(),
-                        )));
-                    }
-                    SessionOrigin::Remote(host) => {
-                        return Ok(InputResult::AttachSession(AttachTarget::Remote(
-                            host.clone(),
-                            entry.session.name.clone(),
-                        )));
-                    }
-                }
-            }
-        }
-        KeyCode::Char('n') => {
-            app.new_session_target = match entries.get(app.selected) {
-                Some(ListEntry::Session(entry)) => match &entry.origin {
-                    SessionOrigin::Local => NewSessionTarget::Local,
-                    SessionOrigin::Remote(host) => NewSessionTarget::Remote(host.clone()),
-                },
-                Some(ListEntry::Header {
-                    host: Some(host), ..
-                }) => NewSessionTarget::Remote(host.clone()),
-                _ => NewSessionTarget::Local,
-            };
-            app.show_new_session_popup();
-        }
-        KeyCode::Char('H') => {
-            app.show_new_host_popup();
-        }
-        KeyCode::Char('K') => {
-            // Kill selected session
-            if let Some(ListEntry::Session(entry)) = entries.get(app.selected) {
-                if entry.session.attached_clients > 0 {
-                    app.show_kill_confirm(KillTarget {
-                        origin: entry.origin.clone(),
-                        session_name: entry.session.name.clone(),
-                        attached_clients: entry.session.attached_clients,
-                    });
-                    return Ok(InputResult::Continue);
-                }
+    #[test]
+    fn test_view_filter_cycles_and_filters_entries() {
+        let mut attached = test_session("attached");
+        attached.attached = true;
+        let mut app = test_app(vec![attached, test_session("detached")]);
+        assert_eq!(app.build_entries().len(), 2);
+
+        app.cycle_view_filter();
+        assert_eq!(app.view_filter, ViewFilter::Attached);
+        let entries = app.build_entries();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(&entries[0], ListEntry::Session(e) if e.session.name == "attached"));
+
+        app.cycle_view_filter();
+        assert_eq!(app.view_filter, ViewFilter::Detached);
+        let entries = app.build_entries();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(&entries[0], ListEntry::Session(e) if e.session.name == "detached"));
+
+        app.cycle_view_filter();
+        assert_eq!(app.view_filter, ViewFilter::All);
+    }
 
-                match &entry.origin {
-                    SessionOrigin::Local => {
-                        let session_name = entry.session.name.clone();
-                        kill_session(Some(session_name))?;
-                        app.refresh()?;
-                        return Ok(InputResult::Refreshed);
-                    }
-                    SessionOrigin::Remote(host) => {
-                        match kill_remote_session(host, &entry.session.name) {
-                            Ok(()) => {
-                                app.set_status_message("Remote session killed.");
-                                app.refresh()?;
-                                return Ok(InputResult::Refreshed);
-                            }
-                            Err(err) => {
-                                app.set_status_message(format!("Kill failed: {}", err));
-                                return Ok(InputResult::Continue);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        KeyCode::Char('r') => {
-            // Refresh session list
-            app.refresh()?;
-            return Ok(InputResult::Refreshed);
-        }
-        KeyCode::Char('s') => {
-            // Save snapshot
-            let path = save_snapshot()?;
-            println!("Snapshot saved to: {:?}", path);
-        }
-        KeyCode::Char('d') => {
-            // Debug terminal info
-            eprintln!("{}", app.get_terminal_info());
-        }
-        _ => {}
+    #[test]
+    fn test_is_attach_size_error() {
+        assert!(is_attach_size_error("size missing"));
+        assert!(is_attach_size_error("terminal too small"));
+        assert!(!is_attach_size_error("can't find session: nope"));
+        assert!(!is_attach_size_error("invalid option: window-size"));
+        assert!(!is_attach_size_error("bad size argument"));
+        assert!(!is_attach_size_error(
+            "open terminal failed: not a terminal"
+        ));
     }
-    Ok(InputResult::Continue)
-}
 
-fn format_attached_users(session: &TmuxSession) -> String {
-    if session.attached_clients == 0 {
-        return "none".to_string();
+    #[test]
+    fn test_merge_entries_counts() {
+        let mut target = HashMap::from([
+            ("work".to_string(), "red".to_string()),
+            ("play".to_string(), "blue".to_string()),
+        ]);
+        let incoming = HashMap::from([
+            ("work".to_string(), "green".to_string()),
+            ("play".to_string(), "blue".to_string()),
+            ("new".to_string(), "cyan".to_string()),
+        ]);
+
+        assert_eq!(merge_entries(&mut target, incoming), (1, 1));
+        assert_eq!(target["work"], "green");
+        assert_eq!(target["new"], "cyan");
     }
 
-    if !session.attached_users.is_empty() {
-        if session.attached_users.len() == 1 {
-            return session.attached_users[0].clone();
-        }
-        let extra = session.attached_users.len() - 1;
-        return format!("{}+{}", session.attached_users[0], extra);
+    #[test]
+    fn test_select_local_session() {
+        let mut app = test_app(vec![
+            test_session("a"),
+            test_session("b"),
+            test_session("c"),
+        ]);
+        app.select_local_session("c");
+        assert_eq!(app.selected, 2);
+
+        // Unknown names leave the selection alone
+        app.select_local_session("missing");
+        assert_eq!(app.selected, 2);
     }
 
-    format!(
-        "{} client{}",
-        session.attached_clients,
-        if session.attached_clients == 1 {
-            ""
-        } else {
-            "s"
-        }
-    )
-}
+    #[test]
+    fn test_render_sessions_formats() {
+        let mut main = test_session("main");
+        main.attached = true;
+        main.resource_info = Some(ResourceInfo {
+            memory_mb: 12.5,
+            cpu_percent: 3.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        let sessions = vec![main, test_session("dev, \"blue\"")];
+        let style = ListStyle::default();
+
+        let csv = render_sessions(&sessions, OutputFormat::Csv, None, &style).unwrap();
+        assert_eq!(
+            csv,
+            "name,windows,attached,memory_mb,cpu_percent\n\
+             main,1,true,12.5,3.0\n\
+             \"dev, \"\"blue\"\"\",1,false,,\n"
+        );
 
-fn build_host_config(name_input: &str, host_input: &str) -> Result<HostConfig> {
-    let host = host_input.trim();
-    if host.is_empty() {
-        return Err(anyhow::anyhow!("Host is required"));
+        let plain = render_sessions(&sessions, OutputFormat::Plain, None, &style).unwrap();
+        assert_eq!(plain, "main\ndev, \"blue\"\n");
+
+        let compact = render_sessions(&sessions, OutputFormat::Compact, None, &style).unwrap();
+        assert_eq!(compact, "main 1w attached\ndev, \"blue\" 1w detached\n");
+
+        let json = render_sessions(&sessions, OutputFormat::Json, None, &style).unwrap();
+        let parsed: Vec<TmuxSession> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let table = render_sessions(&sessions, OutputFormat::Table, None, &style).unwrap();
+        assert!(table.starts_with("Active tmux sessions:\n"));
+        assert!(table.contains("main"));
     }
 
-    let mut name = name_input.trim().to_string();
-    if name.is_empty() {
-        name = default_host_name(host);
+    #[test]
+    fn test_render_sessions_empty() {
+        let style = ListStyle::default();
+        assert_eq!(
+            render_sessions(&[], OutputFormat::Json, None, &style).unwrap(),
+            "[]\n"
+        );
+        assert_eq!(
+            render_sessions(&[], OutputFormat::Csv, None, &style).unwrap(),
+            "name,windows,attached,memory_mb,cpu_percent\n"
+        );
+        assert_eq!(
+            render_sessions(&[], OutputFormat::Table, None, &style).unwrap(),
+            "No tmux sessions found.\n"
+        );
+        assert!(render_sessions(&[], OutputFormat::Plain, None, &style)
+            .unwrap()
+            .is_empty());
     }
 
-    Ok(HostConfig {
-        name,
-        host: host.to_string(),
-        key: None,
-    })
-}
+    #[test]
+    fn test_next_cmux_depth() {
+        assert_eq!(next_cmux_depth(None).unwrap(), 1);
+        assert_eq!(next_cmux_depth(Some("2")).unwrap(), 3);
+        assert_eq!(next_cmux_depth(Some("garbage")).unwrap(), 1);
+        assert!(next_cmux_depth(Some(&MAX_CMUX_DEPTH.to_string())).is_err());
+    }
 
-fn default_host_name(host: &str) -> String {
-    if let Some((_, host_part)) = host.rsplit_once('@') {
-        host_part.to_string()
-    } else {
-        host.to_string()
+    #[test]
+    fn test_cpu_bar() {
+        assert_eq!(cpu_bar(0.0, 4), "░░░░");
+        assert_eq!(cpu_bar(50.0, 4), "██░░");
+        assert_eq!(cpu_bar(100.0, 4), "████");
+        // Multi-core processes can exceed 100%
+        assert_eq!(cpu_bar(250.0, 4), "████");
+        assert_eq!(cpu_bar(-5.0, 4), "░░░░");
+        assert_eq!(cpu_bar(12.5, 2), "▎░");
     }
-}
 
-fn insert_char_at(text: &mut String, ch: char, cursor: &mut usize) {
-    let mut chars: Vec<char> = text.chars().collect();
-    let pos = (*cursor).min(chars.len());
-    chars.insert(pos, ch);
-    *text = chars.iter().collect();
-    *cursor = pos + 1;
-}
+    #[test]
+    fn test_scrub_session_env() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["set-environment", "-t", "proj", "-r", "SSH_AUTH_SOCK"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(vec!["respawn-window", "-k", "-t", "proj:"], "", "", true);
 
-fn remove_char_before(text: &mut String, cursor: &mut usize) {
-    if *cursor == 0 {
-        return;
+        let vars = vec!["SSH_AUTH_SOCK".to_string()];
+        assert!(scrub_session_env_with_executor(&executor, "proj", &vars).is_ok());
+
+        // An unexpected variable hits the mock's "no response" error
+        let vars = vec!["DISPLAY".to_string()];
+        assert!(scrub_session_env_with_executor(&executor, "proj", &vars).is_err());
+
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(config.clean_env_vars.contains(&"SSH_AUTH_SOCK".to_string()));
     }
-    let mut chars: Vec<char> = text.chars().collect();
-    let pos = (*cursor).min(chars.len());
-    if pos == 0 {
-        return;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_wait_with_timeout() {
+        let child = Command::new("sleep").arg("5").spawn().unwrap();
+        let started = Instant::now();
+        assert!(wait_with_timeout(child, Duration::from_millis(100))
+            .unwrap()
+            .is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let child = Command::new("true").spawn().unwrap();
+        let status = wait_with_timeout(child, Duration::from_secs(2)).unwrap();
+        assert!(status.is_some_and(|status| status.success()));
     }
-    chars.remove(pos - 1);
-    *text = chars.iter().collect();
-    *cursor = pos - 1;
-}
 
-fn remove_char_at(text: &mut String, cursor: &mut usize) {
-    let mut chars: Vec<char> = text.chars().collect();
-    let pos = (*cursor).min(chars.len());
-    if pos >= chars.len() {
-        return;
+    #[test]
+    fn test_session_totals_and_user_groups() {
+        let mut alice_big = test_session("alice-big");
+        alice_big.attached = true;
+        alice_big.process_info = Some(ProcessInfo {
+            pid: None,
+            command: "vim".to_string(),
+            user: "alice".to_string(),
+        });
+        alice_big.resource_info = Some(ResourceInfo {
+            memory_mb: 300.0,
+            cpu_percent: 10.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        let mut bob = test_session("bob");
+        bob.process_info = Some(ProcessInfo {
+            pid: None,
+            command: "zsh".to_string(),
+            user: "bob".to_string(),
+        });
+        bob.resource_info = Some(ResourceInfo {
+            memory_mb: 100.0,
+            cpu_percent: 5.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        let mut alice_small = alice_big.clone();
+        alice_small.name = "alice-small".to_string();
+        alice_small.attached = false;
+        let sessions = vec![bob, alice_big, alice_small, test_session("orphan")];
+
+        let totals = session_totals(&sessions);
+        assert_eq!(totals.sessions, 4);
+        assert_eq!(totals.active, 1);
+        assert_eq!(totals.memory_mb, 700.0);
+
+        let groups = group_sessions(&sessions, GroupBy::User);
+        let titles: Vec<_> = groups
+            .iter()
+            .map(|(title, _)| title.clone().unwrap())
+            .collect();
+        assert_eq!(titles, vec!["alice", "bob", "—"]);
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(session_totals(groups[0].1.iter().copied()).memory_mb, 600.0);
+
+        let flat = group_sessions(&sessions, GroupBy::None);
+        assert_eq!(flat.len(), 1);
+        assert!(flat[0].0.is_none());
+    }
+
+    #[test]
+    fn test_attach_args_passthrough() {
+        assert_eq!(
+            attach_args("dev", &AttachOptions::default()),
+            vec!["attach-session", "-t", "dev"]
+        );
+
+        let options = AttachOptions {
+            extra_args: vec!["-E".to_string(), "-f".to_string(), "read-only".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            attach_args("dev", &options),
+            vec!["attach-session", "-t", "dev", "-E", "-f", "read-only"]
+        );
+
+        let options = AttachOptions {
+            exclusive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            attach_args("dev", &options),
+            vec!["attach-session", "-t", "dev", "-d"]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_activity_falls_back_to_created() {
+        let sessions = parse_tmux_sessions("main:1:0:1640995200:\ndev:1:0::");
+        assert_eq!(sessions[0].activity, "1640995200");
+        assert_eq!(sessions[1].activity, "");
+
+        assert_eq!(format_timestamp(""), "unknown");
+        assert_eq!(format_timestamp("garbage"), "unknown");
+        assert_ne!(format_timestamp("1640995200"), "unknown");
+    }
+
+    #[test]
+    fn test_session_exists() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(vec!["has-session", "-t", "=dev"], "", "", true);
+        executor.add_response(
+            vec!["has-session", "-t", "=nope"],
+            "",
+            "can't find session: nope",
+            false,
+        );
+
+        assert!(session_exists_with_executor(&executor, "dev").unwrap());
+        assert!(!session_exists_with_executor(&executor, "nope").unwrap());
+    }
+
+    #[test]
+    fn test_session_current_path() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec![
+                "display-message",
+                "-p",
+                "-t",
+                "=dev:",
+                "#{pane_current_path}",
+            ],
+            "/home/me/project\n",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec![
+                "display-message",
+                "-p",
+                "-t",
+                "=gone:",
+                "#{pane_current_path}",
+            ],
+            "",
+            "can't find session: gone",
+            false,
+        );
+
+        assert_eq!(
+            session_current_path_with_executor(&executor, "dev").unwrap(),
+            Some("/home/me/project".to_string())
+        );
+        assert_eq!(
+            session_current_path_with_executor(&executor, "gone").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_history_limit_falls_back_to_global() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["show-options", "-v", "-t", "=logs:", "history-limit"],
+            "50000\n",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["show-options", "-v", "-t", "=dev:", "history-limit"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["show-options", "-gv", "history-limit"],
+            "2000\n",
+            "",
+            true,
+        );
+
+        assert_eq!(
+            history_limit_with_executor(&executor, "logs").unwrap(),
+            Some(50000)
+        );
+        assert_eq!(
+            history_limit_with_executor(&executor, "dev").unwrap(),
+            Some(2000)
+        );
+    }
+
+    #[test]
+    fn test_set_history_limit() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["set-option", "-t", "=logs:", "history-limit", "100000"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["set-option", "-t", "=gone:", "history-limit", "10"],
+            "",
+            "can't find session: gone",
+            false,
+        );
+
+        assert!(set_history_limit_with_executor(&executor, "logs", 100000).is_ok());
+        let err = set_history_limit_with_executor(&executor, "gone", 10).unwrap_err();
+        assert!(err.to_string().contains("can't find session"));
     }
-    chars.remove(pos);
-    *text = chars.iter().collect();
-}
 
-fn move_cursor_left(active: &HostField, name_cursor: &mut usize, host_cursor: &mut usize) {
-    match active {
-        HostField::Name => {
-            if *name_cursor > 0 {
-                *name_cursor -= 1;
-            }
-        }
-        HostField::Host => {
-            if *host_cursor > 0 {
-                *host_cursor -= 1;
-            }
-        }
+    #[test]
+    fn test_session_cache_expires_and_is_per_socket() {
+        let fetched_at = Instant::now();
+        let cache = SessionCache {
+            socket: "/tmp/tmux-1000/default".to_string(),
+            options: EnrichOptions::default(),
+            fetched_at,
+            sessions: vec![test_session("dev")],
+        };
+        let ttl = Duration::from_millis(500);
+
+        let hit = cache.get(
+            "/tmp/tmux-1000/default",
+            fetched_at + Duration::from_millis(100),
+            ttl,
+        );
+        assert_eq!(hit.map(|s| s.len()), Some(1));
+        assert!(cache
+            .get(
+                "/tmp/tmux-1000/default",
+                fetched_at + Duration::from_millis(600),
+                ttl
+            )
+            .is_none());
+        assert!(cache
+            .get(
+                "/tmp/tmux-1000/other",
+                fetched_at + Duration::from_millis(100),
+                ttl
+            )
+            .is_none());
     }
-}
 
-fn move_cursor_right(
-    active: &HostField,
-    name_input: &str,
-    host_input: &str,
-    name_cursor: &mut usize,
-    host_cursor: &mut usize,
-) {
-    match active {
-        HostField::Name => {
-            let len = name_input.chars().count();
-            if *name_cursor < len {
-                *name_cursor += 1;
-            }
-        }
-        HostField::Host => {
-            let len = host_input.chars().count();
-            if *host_cursor < len {
-                *host_cursor += 1;
-            }
-        }
+    #[test]
+    fn test_attach_args_size_follows_attach() {
+        let options = AttachOptions {
+            extra_args: vec!["-E".to_string()],
+            size: Some((120, 40)),
+            ..Default::default()
+        };
+        assert_eq!(
+            attach_args("dev", &options),
+            vec![
+                "attach-session",
+                "-t",
+                "dev",
+                "-E",
+                ";",
+                "refresh-client",
+                "-C",
+                "120x40"
+            ]
+        );
+        assert_eq!(
+            attach_command_line("dev", &options),
+            "tmux attach-session -t dev -E ';' refresh-client -C 120x40"
+        );
     }
-}
 
-fn set_cursor_start(active: &HostField, name_cursor: &mut usize, host_cursor: &mut usize) {
-    match active {
-        HostField::Name => *name_cursor = 0,
-        HostField::Host => *host_cursor = 0,
+    #[test]
+    fn test_attach_args_read_only_and_no_resize() {
+        let options = AttachOptions {
+            read_only: true,
+            no_resize: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            attach_args("dev", &options),
+            vec!["attach-session", "-t", "dev", "-r", "-f", "ignore-size"]
+        );
     }
-}
 
-fn set_cursor_end(
-    active: &HostField,
-    name_input: &str,
-    host_input: &str,
-    name_cursor: &mut usize,
-    host_cursor: &mut usize,
-) {
-    match active {
-        HostField::Name => *name_cursor = name_input.chars().count(),
-        HostField::Host => *host_cursor = host_input.chars().count(),
+    #[test]
+    fn test_resize_warning() {
+        assert_eq!(
+            resize_warning((200, 50), (80, 40), 1).as_deref(),
+            Some("Attaching will resize the session to 80x40; use --read-only or --no-resize to avoid")
+        );
+        // Nobody else is looking, or our terminal is at least as big
+        assert!(resize_warning((200, 50), (80, 40), 0).is_none());
+        assert!(resize_warning((80, 24), (120, 40), 2).is_none());
     }
-}
 
-fn with_cursor(text: &str, cursor: usize, active: bool) -> String {
-    if !active {
-        return text.to_string();
+    #[test]
+    fn test_session_size() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec![
+                "display-message",
+                "-p",
+                "-t",
+                "=dev:",
+                "#{window_width}x#{window_height}",
+            ],
+            "212x54\n",
+            "",
+            true,
+        );
+        assert_eq!(
+            session_size_with_executor(&executor, "dev"),
+            Some((212, 54))
+        );
+        assert_eq!(session_size_with_executor(&executor, "other"), None);
     }
 
-    let mut result = String::new();
-    let mut idx = 0;
-    for ch in text.chars() {
-        if idx == cursor {
-            result.push('|');
-        }
-        result.push(ch);
-        idx += 1;
+    #[test]
+    fn test_parse_active_windows() {
+        let output = "0:|:dev:|:logs\n1:|:dev:|:editor\n1:|:ops:|:k9s: prod\n0:|:ops:|:shell\n";
+        let windows = parse_active_windows(output);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows["dev"], "editor");
+        assert_eq!(windows["ops"], "k9s: prod");
     }
-    if cursor >= idx {
-        result.push('|');
+
+    #[test]
+    fn test_draw_ui_too_small() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = test_app(vec![test_session("dev")]);
+        let mut list_state = ListState::default();
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal
+            .draw(|f| draw_ui(f, &mut app, &mut list_state))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("Terminal too small (need at least 30x16)"));
+
+        terminal.backend_mut().resize(60, 20);
+        terminal
+            .draw(|f| draw_ui(f, &mut app, &mut list_state))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(!text.contains("Terminal too small"));
+        assert!(text.contains("dev"));
     }
-    result
-}
 
-fn draw_ui(f: &mut Frame, app: &mut App, list_state: &mut ListState) {
-    app.clear_expired_status();
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Min(5),
-            Constraint::Length(5),
-        ])
-        .split(f.size());
+    #[test]
+    fn test_draw_top_ui_skipped_snapshot_footer() {
+        use ratatui::backend::TestBackend;
+
+        let app = test_app(vec![test_session("dev")]);
+        let status = AutoSnapshotStatus::Skipped(chrono::Local::now());
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal
+            .draw(|f| draw_top_ui(f, &app, GroupBy::None, Some(&status)))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("kept last snapshot"));
+    }
 
-    // Header
-    let header = Paragraph::new("crabmux - Mobile-Friendly tmux Manager")
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(header, chunks[0]);
+    #[test]
+    fn test_parse_attach_size() {
+        assert_eq!(parse_attach_size("120x40").unwrap(), (120, 40));
+        assert_eq!(parse_attach_size(" 80X24 ").unwrap(), (80, 24));
+        for bad in [
+            "120",
+            "x40",
+            "120x",
+            "0x40",
+            "-1x40",
+            "120x40x2",
+            "wide x tall",
+        ] {
+            let err = parse_attach_size(bad).unwrap_err();
+            assert!(err.to_string().contains("expected WIDTHxHEIGHT"), "{}", bad);
+        }
+    }
 
-    // Session list
-    let entries = app.build_entries();
-    if entries.is_empty() {
-        let empty_msg =
-            Paragraph::new("No tmux sessions found.\nPress 'n' to create a new session.")
-                .style(Style::default().fg(Color::Gray))
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL).title("Sessions"));
-        f.render_widget(empty_msg, chunks[1]);
-    } else {
-        let sessions: Vec<ListItem> = entries
-            .iter()
-            .enumerate()
-            .map(|(i, entry)| {
-                let is_selected = i == app.selected;
-                match entry {
-                    ListEntry::Header { title, .. } => {
-                        let content = Line::from(vec![Span::styled(
-                            title.as_str(),
-                            Style::default()
-                                .fg(Color::Cyan)
-                                .add_modifier(Modifier::BOLD),
-                        )]);
-                        let mut item = ListItem::new(content);
-                        if is_selected {
-                            item = item.style(app.get_highlight_style());
-                        }
-                        item
-                    }
-                    ListEntry::Session(entry) => {
-                        let s = &entry.session;
-                        let status = if s.attached { "●" } else { "○" };
-                        let user = format_attached_users(s);
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_tmux_sockets_only_returns_sockets() {
+        use std::os::unix::net::UnixListener;
 
-                        // Get resource info
-                        let (memory_info, cpu_info) = if let Some(ref resource) = s.resource_info {
-                            (
-                                format!("{:.1}MB", resource.memory_mb),
-                                format!("{:.1}%", resource.cpu_percent),
-                            )
-                        } else {
-                            ("N/A".to_string(), "N/A".to_string())
-                        };
+        let dir = tempfile::tempdir().unwrap();
+        let _work = UnixListener::bind(dir.path().join("work")).unwrap();
+        let _default = UnixListener::bind(dir.path().join("default")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a socket").unwrap();
 
-                        // Add selection indicator prefix for better visibility
-                        let selection_prefix = app.get_selection_prefix(is_selected);
+        let names: Vec<String> = discover_tmux_sockets(dir.path())
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["default", "work"]);
+        assert!(discover_tmux_sockets(&dir.path().join("missing")).is_empty());
+    }
 
-                        let content = Line::from(vec![
-                            Span::styled(
-                                format!("{:<1}", selection_prefix),
-                                Style::default()
-                                    .fg(if is_selected {
-                                        Color::Yellow
-                                    } else {
-                                        Color::DarkGray
-                                    })
-                                    .add_modifier(if is_selected {
-                                        Modifier::BOLD
-                                    } else {
-                                        Modifier::empty()
-                                    }),
-                            ),
-                            Span::styled(
-                                format!("{:<1}", status),
-                                Style::default().fg(if s.attached {
-                                    Color::Green
-                                } else {
-                                    Color::Red
-                                }),
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                format!("{:<15}", s.name),
-                                Style::default()
-                                    .fg(if is_selected {
-                                        Color::Yellow
-                                    } else {
-                                        Color::White
-                                    })
-                                    .add_modifier(if is_selected {
-                                        Modifier::BOLD | Modifier::UNDERLINED
-                                    } else {
-                                        Modifier::BOLD
-                                    }),
-                            ),
-                            Span::styled(
-                                format!("{:>3}W", s.windows),
-                                Style::default().fg(if is_selected {
-                                    Color::Yellow
-                                } else {
-                                    Color::White
-                                }),
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                format!("{:>8}", memory_info),
-                                Style::default().fg(if is_selected {
-                                    Color::Yellow
-                                } else {
-                                    Color::Cyan
-                                }),
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                format!("{:>6}", cpu_info),
-                                Style::default().fg(if is_selected {
-                                    Color::Yellow
-                                } else {
-                                    Color::Magenta
-                                }),
-                            ),
-                            Span::raw(" "),
-                            Span::styled(
-                                format!("{:<8}", user),
-                                Style::default().fg(if is_selected {
-                                    Color::Yellow
-                                } else {
-                                    Color::Gray
-                                }),
-                            ),
-                        ]);
+    #[test]
+    fn test_render_sessions_with_server_column() {
+        let mut dev = test_session("dev");
+        dev.server = Some("default".to_string());
+        let mut api = test_session("api");
+        api.server = Some("work".to_string());
+        let sessions = vec![dev, api];
+
+        let csv =
+            render_sessions(&sessions, OutputFormat::Csv, None, &ListStyle::default()).unwrap();
+        assert!(csv.starts_with("name,windows,attached,memory_mb,cpu_percent,server\n"));
+        assert!(csv.contains("api,1,false,,,work\n"));
+
+        let table =
+            render_sessions(&sessions, OutputFormat::Table, None, &ListStyle::default()).unwrap();
+        assert!(table.contains("Server"));
+        assert!(table
+            .lines()
+            .any(|line| line.starts_with("api") && line.contains("work")));
+
+        let plain = render_sessions(
+            &[test_session("solo")],
+            OutputFormat::Csv,
+            None,
+            &ListStyle::default(),
+        )
+        .unwrap();
+        assert!(!plain.contains("server"));
+    }
 
-                        let mut item = ListItem::new(content);
-                        if is_selected {
-                            // Use terminal-aware highlighting
-                            item = item.style(app.get_highlight_style());
-                        }
-                        item
-                    }
-                }
-            })
+    #[test]
+    fn test_info_porcelain_snapshot() {
+        let mut session = test_session("dev");
+        session.windows = 2;
+        session.attached = true;
+        session.created = "1640995200".to_string();
+        session.activity = "1640995260".to_string();
+
+        let windows = "0:|:1:|:editor\n1:|:3:|:logs\tserver\n";
+        assert_eq!(
+            render_info_porcelain(&session, windows),
+            "name\tdev\n\
+             windows\t2\n\
+             attached\t1\n\
+             created\t1640995200\n\
+             activity\t1640995260\n\
+             window\t0\teditor\t1\n\
+             window\t1\tlogs server\t3\n"
+        );
+    }
+
+    #[test]
+    fn test_legend_uses_render_glyphs() {
+        let mut app = test_app(Vec::new());
+        let legend: String = legend_line(&app)
+            .spans
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect();
+        assert!(legend.contains(status_marker(true).0));
+        assert!(legend.contains(status_marker(false).0));
+        assert!(legend.contains(TAG_MARKER));
+        assert!(legend.contains(&cpu_bar(50.0, 2)));
+        assert!(legend.contains(CURRENT_MARKER.trim_start()));
+
+        app.columns.cpu_bar = false;
+        let legend: String = legend_line(&app)
+            .spans
+            .iter()
+            .map(|span| span.content.to_string())
             .collect();
+        assert!(!legend.contains("CPU"));
+    }
 
-        let title = "Sessions │ Name        │ Win │ Memory │ CPU   │ Clients ";
-        let sessions_list = List::new(sessions)
-            .block(Block::default().borders(Borders::ALL).title(title))
-            .highlight_style(app.get_highlight_style())
-            .highlight_symbol(app.get_selection_symbol());
+    #[test]
+    fn test_default_session_name_avoids_existing() {
+        // Cover this second and the next in case the clock ticks mid-test
+        let now = chrono::Local::now();
+        let mut existing = Vec::new();
+        for time in [now, now + chrono::Duration::seconds(1)] {
+            let base = format!("session-{}", time.format("%H%M%S"));
+            existing.push(format!("{}-2", base));
+            existing.push(base);
+        }
 
-        list_state.select(Some(app.selected));
-        f.render_stateful_widget(sessions_list, chunks[1], list_state);
+        let name = default_session_name(&existing, None);
+        assert!(!existing.contains(&name), "{} already exists", name);
+        assert!(name.ends_with("-3"));
+
+        assert!(!default_session_name(&[], None).ends_with("-2"));
+        assert!(default_session_name(&[], Some("team-")).starts_with("team-session-"));
     }
 
-    // Controls/Help
-    let mut help_text: Vec<String> = if app.show_help {
-        vec![
-            "↑/↓/j/k: Navigate    Enter: Attach    n: New session    H: Add host".to_string(),
-            "K: Kill session      r: Refresh       s: Save snapshot".to_string(),
-            "d: Debug terminal    q/Esc/Ctrl+C: Quit  ?: Toggle help".to_string(),
-        ]
-    } else {
-        vec!["Navigate: ↑/↓  Attach: Enter  New: n  Host: H  Kill: K  Debug: d  Quit: q/Ctrl+C  Help: ?".to_string()]
-    };
-    if let Some(ref message) = app.status_message {
-        help_text.push(format!("Status: {}", message));
+    #[test]
+    fn test_send_keys_sends_literal_text_then_enter() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["send-keys", "-t", "=dev:", "-l", "cd /mnt/data"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(vec!["send-keys", "-t", "=dev:", "Enter"], "", "", true);
+        executor.add_response(
+            vec!["send-keys", "-t", "=gone:", "-l", "cd /mnt/data"],
+            "",
+            "can't find session: gone",
+            false,
+        );
+
+        assert!(send_keys_with_executor(&executor, "dev", "cd /mnt/data").is_ok());
+        let err = send_keys_with_executor(&executor, "gone", "cd /mnt/data").unwrap_err();
+        assert_eq!(err.to_string(), "can't find session: gone");
     }
 
-    let help = Paragraph::new(help_text.join("\n"))
-        .style(Style::default().fg(Color::Gray))
-        .alignment(Alignment::Left)
-        .wrap(Wrap { trim: true })
-        .block(Block::default().borders(Borders::ALL).title("Controls"));
-    f.render_widget(help, chunks[2]);
+    #[test]
+    fn test_esc_pops_view_before_quitting() {
+        let mut app = test_app(vec![test_session("dev")]);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+
+        app.push_view(View::Preview);
+        assert!(app.show_preview());
+        assert!(matches!(
+            handle_input(&mut app, esc).unwrap(),
+            InputResult::Continue
+        ));
+        assert_eq!(app.current_view(), View::Sessions);
 
-    // Render popup if showing
-    if app.show_new_session_popup {
-        draw_new_session_popup(f, app);
+        app.push_view(View::Preview);
+        handle_input(
+            &mut app,
+            KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+        )
+        .unwrap();
+        assert_eq!(app.current_view(), View::Sessions);
+        // Backspace at the top level is a no-op rather than a quit
+        assert!(matches!(
+            handle_input(
+                &mut app,
+                KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE)
+            )
+            .unwrap(),
+            InputResult::Continue
+        ));
+
+        assert!(matches!(
+            handle_input(&mut app, esc).unwrap(),
+            InputResult::Quit
+        ));
     }
-    if app.show_new_host_popup {
-        draw_new_host_popup(f, app);
+
+    #[test]
+    fn test_parse_vm_swap_kb() {
+        let status = "Name:\ttmux: server\nVmRSS:\t    4200 kB\nVmSwap:\t    1536 kB\n";
+        assert_eq!(parse_vm_swap_kb(status), Some(1536));
+        assert_eq!(parse_vm_swap_kb("Name:\tkthreadd\n"), None);
     }
-    if app.show_kill_confirm {
-        draw_kill_confirm_popup(f, app);
+
+    #[test]
+    fn test_resource_extras_span() {
+        let mut app = test_app(Vec::new());
+        let mut session = test_session("dev");
+        session.resource_info = Some(ResourceInfo {
+            memory_mb: 1.0,
+            cpu_percent: 0.0,
+            swap_kb: Some(2048),
+            open_fds: None,
+        });
+
+        assert_eq!(
+            resource_extras_span(&app, &session, Style::default()).content,
+            ""
+        );
+        app.columns.swap = true;
+        app.columns.fds = true;
+        assert_eq!(
+            resource_extras_span(&app, &session, Style::default()).content,
+            " sw:2.0M   fd:N/A "
+        );
+        assert_eq!(format_kb(512), "512K");
+        assert_eq!(format_kb(3 * 1024 * 1024), "3.0G");
     }
-}
 
-fn draw_new_session_popup(f: &mut Frame, app: &App) {
-    let popup_area = centered_rect(50, 20, f.size());
+    #[test]
+    fn test_double_press_window() {
+        let mut app = test_app(vec![test_session("dev")]);
+        assert!(!app.take_pending_delete());
 
-    // Clear the area
-    f.render_widget(Clear, popup_area);
+        app.pending_delete = Some(Instant::now());
+        assert!(app.take_pending_delete());
+        // The pending press is consumed either way
+        assert!(!app.take_pending_delete());
 
-    let popup_block = Block::default()
-        .title("New Session")
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        app.pending_delete = Some(Instant::now() - Duration::from_secs(5));
+        assert!(!app.take_pending_delete());
+    }
 
-    let popup_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(3),
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-        ])
-        .split(popup_area);
+    #[test]
+    fn test_list_format_string_validation() {
+        assert_eq!(list_format_string(&[]).unwrap(), None);
 
-    f.render_widget(popup_block, popup_area);
+        let fields: Vec<String> = ["session_name", "session_windows", "session_path"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(
+            list_format_string(&fields).unwrap().as_deref(),
+            Some("#{session_name}:|:#{session_windows}:|:#{session_path}|;|")
+        );
 
-    let input_text = Paragraph::new("Enter session name (or press Enter for default):")
-        .style(Style::default().fg(Color::White));
-    f.render_widget(input_text, popup_chunks[0]);
+        let err =
+            list_format_string(&["session_name".to_string(), "pane_pid".to_string()]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown list_format field 'pane_pid'"));
+        let err = list_format_string(&["session_path".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("must include session_name"));
+    }
 
-    let input_display = with_cursor(&app.new_session_input, app.new_session_cursor, true);
-    let input_field = Paragraph::new(input_display)
-        .style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(input_field, popup_chunks[1]);
+    #[test]
+    fn test_parse_tmux_sessions_with_fields() {
+        let fields: Vec<String> = [
+            "session_name",
+            "session_attached",
+            "session_created",
+            "session_path",
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+        let output = "dev:|:2:|:1640995200:|:/home/me/dev\nlogs:|:0:|:1640995300:|:/var/log:old\n";
+        let sessions = parse_tmux_sessions_with_fields(output, &fields);
 
-    let target_label = match &app.new_session_target {
-        NewSessionTarget::Local => "Target: local".to_string(),
-        NewSessionTarget::Remote(host) => format!("Target: {} ({})", host.name, host.host),
-    };
-    let target_text = Paragraph::new(target_label).style(Style::default().fg(Color::Gray));
-    f.render_widget(target_text, popup_chunks[2]);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "dev");
+        assert!(sessions[0].attached);
+        assert_eq!(sessions[0].attached_clients, 2);
+        assert_eq!(sessions[0].activity, "1640995200");
+        assert_eq!(sessions[0].extra_fields["session_path"], "/home/me/dev");
+        assert_eq!(sessions[1].extra_fields["session_path"], "/var/log:old");
+        assert!(!sessions[1].attached);
+    }
 
-    let default_name = format!("Default: session-{}", chrono::Local::now().format("%H%M%S"));
-    let default_text = Paragraph::new(default_name).style(Style::default().fg(Color::Gray));
-    f.render_widget(default_text, popup_chunks[3]);
+    #[test]
+    fn test_idle_filter_cycles_and_filters_entries() {
+        let now = chrono::Utc::now().timestamp();
+        let mut busy = test_session("busy");
+        busy.activity = (now - 30).to_string();
+        let mut stale = test_session("stale");
+        stale.activity = (now - 2 * 60 * 60).to_string();
+        let mut unknown = test_session("unknown");
+        unknown.activity = String::new();
+        let mut app = test_app(vec![busy, stale, unknown]);
+        assert_eq!(app.build_entries().len(), 3);
+
+        app.cycle_idle_filter();
+        assert_eq!(app.idle_filter, IdleFilter::FiveMinutes);
+        let entries = app.build_entries();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(&entries[0], ListEntry::Session(e) if e.session.name == "stale"));
+
+        app.cycle_idle_filter();
+        assert_eq!(app.build_entries().len(), 1);
+        app.cycle_idle_filter();
+        assert_eq!(app.idle_filter, IdleFilter::Day);
+        assert!(app.build_entries().is_empty());
+        app.cycle_idle_filter();
+        assert_eq!(app.idle_filter, IdleFilter::Off);
+    }
 
-    let help_text = Paragraph::new("Enter: Create  Esc: Cancel")
-        .style(Style::default().fg(Color::Gray))
-        .alignment(Alignment::Center);
-    f.render_widget(help_text, popup_chunks[4]);
-}
+    #[test]
+    fn test_kill_target_warns_about_own_session() {
+        let mut app = test_app(Vec::new());
+        app.current_session = Some("dev".to_string());
+        let mut dev = test_session("dev");
+        dev.attached_clients = 1;
+        let entry = SessionEntry {
+            origin: SessionOrigin::Local,
+            session: dev,
+        };
 
-fn draw_new_host_popup(f: &mut Frame, app: &App) {
-    let popup_area = centered_rect(70, 40, f.size());
+        let target = KillTarget::from_entry(&app, &entry);
+        assert!(target.is_current);
+        assert_eq!(
+            target.question(),
+            "You are attached to 'dev'. Killing it will detach you. Continue?"
+        );
 
-    f.render_widget(Clear, popup_area);
+        app.current_session = Some("other".to_string());
+        let target = KillTarget::from_entry(&app, &entry);
+        assert!(!target.is_current);
+        assert_eq!(
+            target.question(),
+            "Kill session 'dev' with 1 attached client(s)?"
+        );
+    }
 
-    let popup_block = Block::default()
-        .title("Add Remote Host")
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+    #[test]
+    fn test_merge_snapshots_dedupes_and_keeps_later_timestamp() {
+        let mut shared_b = test_session("shared");
+        shared_b.windows = 7;
+        let a = SessionSnapshot {
+            sessions: vec![test_session("work"), test_session("shared")],
+            timestamp: "2024-01-02T10:00:00+00:00".to_string(),
+            layouts: BTreeMap::new(),
+            order: Vec::new(),
+        };
+        let b = SessionSnapshot {
+            sessions: vec![shared_b, test_session("laptop")],
+            timestamp: "2024-03-01T08:00:00+00:00".to_string(),
+            layouts: BTreeMap::new(),
+            order: Vec::new(),
+        };
 
-    let popup_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(3),
-            Constraint::Length(1),
-            Constraint::Length(3),
-            Constraint::Length(1),
-            Constraint::Length(1),
-        ])
-        .split(popup_area);
+        let merged = merge_snapshots(a.clone(), b.clone(), SnapshotSide::A);
+        let names: Vec<_> = merged.sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "shared", "laptop"]);
+        assert_eq!(merged.sessions[1].windows, a.sessions[1].windows);
+        assert_eq!(merged.timestamp, "2024-03-01T08:00:00+00:00");
 
-    f.render_widget(popup_block, popup_area);
+        let merged = merge_snapshots(a, b, SnapshotSide::B);
+        assert_eq!(merged.sessions.len(), 3);
+        assert_eq!(merged.sessions[1].windows, 7);
+    }
 
-    let input_text = Paragraph::new("Add a remote host (name optional; key via config or CLI)")
-        .style(Style::default().fg(Color::White));
-    f.render_widget(input_text, popup_chunks[0]);
+    #[test]
+    fn test_parse_tmux_sessions_reports_rejected_lines() {
+        let output = "work:2:0:1700000000:1700000100\nhalf:1:0\n\nplay:1:1:1700000000:1700000200\n";
+        let (sessions, rejected) = parse_tmux_sessions_with_diagnostics(output);
+        let names: Vec<_> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "play"]);
+        assert_eq!(rejected, vec!["half:1:0".to_string()]);
+    }
 
-    let name_active = app.new_host_active_field == HostField::Name;
-    let host_active = app.new_host_active_field == HostField::Host;
+    #[test]
+    fn test_session_prefix_is_applied_and_resolved() {
+        assert_eq!(apply_session_prefix(Some("team-"), "api"), "team-api");
+        assert_eq!(apply_session_prefix(Some("team-"), "team-api"), "team-api");
+        assert_eq!(apply_session_prefix(None, "api"), "api");
+
+        let sessions = vec![test_session("team-api"), test_session("web")];
+        assert_eq!(
+            resolve_prefixed_name(Some("team-"), "api", &sessions),
+            "team-api"
+        );
+        assert_eq!(
+            resolve_prefixed_name(Some("team-"), "team-api", &sessions),
+            "team-api"
+        );
+        assert_eq!(
+            resolve_prefixed_name(Some("team-"), "web", &sessions),
+            "web"
+        );
+        assert_eq!(
+            resolve_prefixed_name(Some("team-"), "missing", &sessions),
+            "missing"
+        );
+    }
 
-    let name_label = Paragraph::new("Name (optional)").style(Style::default().fg(Color::Gray));
-    f.render_widget(name_label, popup_chunks[1]);
+    #[test]
+    fn test_parse_window_layouts_groups_by_session() {
+        let output = "work:|:2:|:b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}:|:editor\n\
+                      work:|:1:|:c1a0,80x24,0,0,3:|:logs\n\
+                      play:|:1:|:c1a1,80x24,0,0,4:|:shell\n";
+        let layouts = parse_window_layouts(output);
+        assert_eq!(layouts.len(), 2);
+        assert_eq!(layouts["work"].len(), 2);
+        assert_eq!(layouts["work"][0].name, "editor");
+        assert_eq!(layouts["work"][0].panes, 2);
+        assert_eq!(layouts["play"][0].layout, "c1a1,80x24,0,0,4");
+    }
 
-    let name_display = with_cursor(
-        &app.new_host_name_input,
-        app.new_host_name_cursor,
-        name_active,
-    );
-    let name_field = Paragraph::new(name_display)
-        .style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(if name_active {
-                    Color::Yellow
-                } else {
-                    Color::DarkGray
-                })),
+    #[test]
+    fn test_restore_window_layouts_falls_back_to_single_pane() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec![
+                "display-message",
+                "-p",
+                "-t",
+                "=work:",
+                "#{window_id} #{pane_id}",
+            ],
+            "@1 %1\n",
+            "",
+            true,
         );
-    f.render_widget(name_field, popup_chunks[2]);
+        executor.add_response(vec!["rename-window", "-t", "@1", "editor"], "", "", true);
+        executor.add_response(vec!["split-window", "-d", "-t", "@1"], "", "", true);
+        executor.add_response(
+            vec!["select-layout", "-t", "@1", "bad-layout"],
+            "",
+            "invalid layout",
+            false,
+        );
+        executor.add_response(vec!["kill-pane", "-a", "-t", "%1"], "", "", true);
 
-    let host_label = Paragraph::new("Host (user@host)").style(Style::default().fg(Color::Gray));
-    f.render_widget(host_label, popup_chunks[3]);
+        let windows = vec![WindowLayout {
+            name: "editor".to_string(),
+            panes: 2,
+            layout: "bad-layout".to_string(),
+        }];
+        let warnings = restore_window_layouts_with_executor(&executor, "work", &windows);
+        assert_eq!(
+            warnings,
+            vec!["window 'editor': select-layout failed: invalid layout; left as a single pane"]
+        );
+    }
 
-    let host_display = with_cursor(
-        &app.new_host_host_input,
-        app.new_host_host_cursor,
-        host_active,
-    );
-    let host_field = Paragraph::new(host_display)
-        .style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(if host_active {
-                    Color::Yellow
-                } else {
-                    Color::DarkGray
-                })),
+    #[test]
+    fn test_snapshot_windows_round_trip_through_restore() {
+        let windows = parse_snapshot_windows("/home/me/src:|:editor\n:|:logs:|:tail\n");
+        assert_eq!(
+            windows,
+            vec![
+                SnapshotWindow {
+                    name: "editor".to_string(),
+                    path: Some("/home/me/src".to_string()),
+                },
+                SnapshotWindow {
+                    name: "logs:|:tail".to_string(),
+                    path: None,
+                },
+            ]
         );
-    f.render_widget(host_field, popup_chunks[4]);
 
-    let help_text = Paragraph::new("Tab/Shift+Tab: Switch  Enter: Save  Esc: Cancel")
-        .style(Style::default().fg(Color::Gray))
-        .alignment(Alignment::Center);
-    f.render_widget(help_text, popup_chunks[5]);
+        let mut session = test_session("work");
+        assert_eq!(
+            restore_session_args(&session),
+            vec!["new-session", "-d", "-s", "work"]
+        );
+        session.window_list = windows;
+        assert_eq!(
+            restore_session_args(&session),
+            vec![
+                "new-session",
+                "-d",
+                "-s",
+                "work",
+                "-n",
+                "editor",
+                "-c",
+                "/home/me/src"
+            ]
+        );
 
-    if let Some(ref error) = app.new_host_error {
-        let error_text = Paragraph::new(error.as_str())
-            .style(Style::default().fg(Color::Red))
-            .alignment(Alignment::Left);
-        f.render_widget(error_text, popup_chunks[6]);
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["new-window", "-d", "-t", "=work:", "-n", "logs:|:tail"],
+            "",
+            "",
+            true,
+        );
+        let warnings = restore_windows_with_executor(&executor, "work", &session.window_list);
+        // "editor" has no mock response, standing in for a failed new-window
+        assert_eq!(warnings, vec!["could not create window 'editor'"]);
     }
-}
 
-fn draw_kill_confirm_popup(f: &mut Frame, app: &App) {
-    let Some(ref target) = app.kill_confirm_target else {
-        return;
-    };
+    #[test]
+    fn test_snapshot_without_window_list_still_parses() {
+        let json = r#"{"sessions": [{"name": "work", "windows": 2, "attached": false,
+            "created": "1", "activity": "1", "process_info": null, "resource_info": null}],
+            "timestamp": "2024-01-01T00:00:00Z"}"#;
+        let snapshot: SessionSnapshot = serde_json::from_str(json).unwrap();
+        assert!(snapshot.sessions[0].window_list.is_empty());
+        assert!(snapshot_window_layouts(&snapshot.sessions[0]).is_none());
+    }
 
-    let popup_area = centered_rect(60, 25, f.size());
-    f.render_widget(Clear, popup_area);
+    #[test]
+    fn test_plain_picker_renders_and_parses_choices() {
+        let mut work = test_session("work");
+        work.attached = true;
+        let sessions = vec![work, test_session("play")];
+        let rendered = render_plain_picker(&sessions);
+        assert!(rendered.starts_with("1) work ("));
+        assert!(rendered.contains(", attached)\n2) play ("));
+
+        assert_eq!(parse_picker_choice("2\n", 2).unwrap(), Some(1));
+        assert_eq!(parse_picker_choice(" q ", 2).unwrap(), None);
+        assert_eq!(parse_picker_choice("\n", 2).unwrap(), None);
+        assert!(parse_picker_choice("3", 2).is_err());
+        assert!(parse_picker_choice("zero", 2).is_err());
+    }
 
-    let popup_block = Block::default()
-        .title("Confirm Kill")
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Red));
+    #[test]
+    fn test_window_plan_validates_counts_and_names() {
+        assert_eq!(window_plan(Some(2), &[]).unwrap(), vec![None, None]);
+        let names = vec!["edit".to_string(), "".to_string()];
+        assert_eq!(
+            window_plan(None, &names).unwrap(),
+            vec![Some("edit".to_string()), None]
+        );
+        assert_eq!(window_plan(Some(2), &names).unwrap().len(), 2);
+        let err = window_plan(Some(3), &names).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--window-names has 2 name(s) but --windows is 3"
+        );
+    }
 
-    let popup_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-        ])
-        .split(popup_area);
+    #[test]
+    fn test_new_session_with_windows_adds_named_windows() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec!["new-session", "-d", "-s", "proj", "-n", "edit"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(vec!["new-window", "-d", "-t", "=proj:"], "", "", true);
+        executor.add_response(
+            vec!["new-window", "-d", "-t", "=proj:", "-n", "logs"],
+            "",
+            "",
+            true,
+        );
+        let plan = vec![Some("edit".to_string()), None, Some("logs".to_string())];
+        new_session_with_windows_with_executor(&executor, "proj", &plan, false).unwrap();
+    }
 
-    f.render_widget(popup_block, popup_area);
+    #[test]
+    fn test_parse_interval_secs() {
+        assert_eq!(parse_interval_secs("0"), Ok(0));
+        assert_eq!(parse_interval_secs("5"), Ok(5));
+        assert!(parse_interval_secs("-1").is_err());
+        assert!(parse_interval_secs("2s").is_err());
+        assert!(parse_interval_secs("100000").is_err());
+    }
 
-    let origin_label = match &target.origin {
-        SessionOrigin::Local => "Local session".to_string(),
-        SessionOrigin::Remote(host) => format!("Remote: {} ({})", host.name, host.host),
-    };
-    let line1 = Paragraph::new(origin_label).style(Style::default().fg(Color::Gray));
-    f.render_widget(line1, popup_chunks[0]);
+    #[test]
+    fn test_check_snapshot_path_rejects_directories_and_missing_parents() {
+        let dir = tempfile::tempdir().unwrap();
 
-    let line2 = Paragraph::new(format!(
-        "Kill session '{}' with {} attached client(s)?",
-        target.session_name, target.attached_clients
-    ))
-    .style(
-        Style::default()
-            .fg(Color::White)
-            .add_modifier(Modifier::BOLD),
-    );
-    f.render_widget(line2, popup_chunks[1]);
+        let err = check_snapshot_path(dir.path(), false).unwrap_err();
+        assert!(err.to_string().starts_with("Snapshot path is a directory"));
 
-    let help_text = Paragraph::new("Enter/Y: Kill  N/Esc: Cancel")
-        .style(Style::default().fg(Color::Gray))
-        .alignment(Alignment::Center);
-    f.render_widget(help_text, popup_chunks[2]);
-}
+        let missing = dir.path().join("nope").join("snap.json");
+        let err = check_snapshot_path(&missing, true).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Parent directory does not exist"));
+        assert!(check_snapshot_path(&missing, false).is_ok());
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
+        assert!(check_snapshot_path(&dir.path().join("snap.json"), true).is_ok());
+        assert!(check_snapshot_path(Path::new("snap.json"), true).is_ok());
+    }
 
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
-}
+    #[test]
+    fn test_mouse_mode_toggles_current_value() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(vec!["has-session", "-t", "=dev"], "", "", true);
+        executor.add_response(
+            vec!["show-options", "-v", "-t", "=dev:", "mouse"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(vec!["show-options", "-gv", "mouse"], "on\n", "", true);
+        executor.add_response(
+            vec!["set-option", "-t", "=dev:", "mouse", "off"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(
+            vec!["set-option", "-t", "=dev:", "mouse", "on"],
+            "",
+            "",
+            true,
+        );
+        executor.add_response(vec!["has-session", "-t", "=gone"], "", "", false);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[cfg(unix)]
-    use std::os::unix::process::ExitStatusExt;
-    #[cfg(windows)]
-    use std::os::windows::process::ExitStatusExt;
-    use std::process::{ExitStatus, Output};
+        assert!(!set_mouse_mode_with_executor(&executor, "dev", None).unwrap());
+        assert!(set_mouse_mode_with_executor(&executor, "dev", Some(Toggle::On)).unwrap());
+        let err = set_mouse_mode_with_executor(&executor, "gone", None).unwrap_err();
+        assert_eq!(err.to_string(), "Session 'gone' not found");
+    }
 
-    // Mock tmux executor for testing
-    struct MockTmuxExecutor {
-        responses: HashMap<String, Result<Output>>,
+    #[test]
+    fn test_attach_print_cmd_resolves_aliases_and_quotes() {
+        let sessions = vec![test_session("team-api"), test_session("my project")];
+        let aliases = HashMap::from([("p".to_string(), "my project".to_string())]);
+
+        let target = resolve_attach_name("p", &sessions, &aliases, None);
+        assert_eq!(target, "my project");
+        assert_eq!(
+            resolve_attach_name("api", &sessions, &aliases, Some("team-")),
+            "team-api"
+        );
+
+        let options = AttachOptions {
+            read_only: true,
+            ..AttachOptions::default()
+        };
+        assert_eq!(
+            attach_command_line(&target, &options),
+            "tmux attach-session -t 'my project' -r"
+        );
     }
 
-    impl MockTmuxExecutor {
-        fn new() -> Self {
-            MockTmuxExecutor {
-                responses: HashMap::new(),
-            }
-        }
+    #[test]
+    fn test_rapid_refresh_requests_are_coalesced() {
+        let mut app = test_app(vec![test_session("work")]);
+        let now = Instant::now();
+        assert_eq!(app.refresh_wait(now), None);
+
+        app.last_refresh_attempt = Some(now);
+        assert!(app.refresh_wait(now).is_some());
+        assert!(!app.request_refresh().unwrap());
+        assert!(app.refresh_pending);
+        // Still inside the window, so nothing runs yet
+        assert!(!app.run_pending_refresh().unwrap());
+        assert_eq!(app.refresh_wait(now + MIN_REFRESH_INTERVAL), None);
+    }
 
-        fn add_response(&mut self, args: Vec<&str>, stdout: &str, stderr: &str, success: bool) {
-            let key = args.join(" ");
-            let output = Output {
-                stdout: stdout.as_bytes().to_vec(),
-                stderr: stderr.as_bytes().to_vec(),
-                status: ExitStatus::from_raw(if success { 0 } else { 1 }),
-            };
-            self.responses.insert(key, Ok(output));
-        }
+    #[test]
+    fn test_top_csv_gets_timestamp_column() {
+        let mut work = test_session("work");
+        work.resource_info = Some(ResourceInfo {
+            memory_mb: 12.5,
+            cpu_percent: 3.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        let csv = render_sessions(&[work], OutputFormat::Csv, None, &ListStyle::default()).unwrap();
+        let stamped = prepend_csv_column(&csv, "timestamp", "2024-01-01T00:00:00+00:00");
+        assert_eq!(
+            stamped,
+            "timestamp,name,windows,attached,memory_mb,cpu_percent\n\
+             2024-01-01T00:00:00+00:00,work,1,false,12.5,3.0\n"
+        );
+        assert_eq!(
+            without_first_line(&stamped),
+            "2024-01-01T00:00:00+00:00,work,1,false,12.5,3.0\n"
+        );
+    }
 
-        #[allow(dead_code)]
-        fn add_error_response(&mut self, args: Vec<&str>) {
-            let key = args.join(" ");
-            self.responses
-                .insert(key, Err(anyhow::anyhow!("Command failed")));
-        }
+    #[test]
+    fn test_merge_session_order_appends_new_names() {
+        let current = vec!["b".to_string(), "x".to_string()];
+        let incoming = vec!["c".to_string(), "b".to_string(), "a".to_string()];
+        assert_eq!(
+            merge_session_order(&current, &incoming),
+            vec!["b", "x", "c", "a"]
+        );
+        assert_eq!(merge_session_order(&[], &incoming), incoming);
     }
 
-    impl TmuxExecutor for MockTmuxExecutor {
-        fn execute_command(&self, args: &[&str]) -> Result<Output> {
-            let key = args.join(" ");
-            match self.responses.get(&key) {
-                Some(Ok(output)) => Ok(output.clone()),
-                Some(Err(e)) => Err(anyhow::anyhow!("{}", e)),
-                None => Err(anyhow::anyhow!("No mock response for: {}", key)),
-            }
-        }
+    #[test]
+    fn test_typing_a_letter_jumps_to_next_matching_session() {
+        let mut app = test_app(vec![
+            test_session("alpha"),
+            test_session("beta"),
+            test_session("Apple"),
+        ]);
+        let row_of = |app: &App, name: &str| {
+            app.build_entries()
+                .iter()
+                .position(|entry| matches!(entry, ListEntry::Session(e) if e.session.name == name))
+                .unwrap()
+        };
+        app.selected = row_of(&app, "alpha");
+        let a = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+
+        handle_input(&mut app, a).unwrap();
+        assert_eq!(app.selected, row_of(&app, "Apple"));
+        // Wraps back around to the first match
+        handle_input(&mut app, a).unwrap();
+        assert_eq!(app.selected, row_of(&app, "alpha"));
+
+        let z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE);
+        handle_input(&mut app, z).unwrap();
+        assert_eq!(app.selected, row_of(&app, "alpha"));
     }
 
     #[test]
-    fn test_parse_tmux_sessions() {
-        let output = "main:3:2:1234567890:1234567890\ndev:1:0:1234567891:1234567891\ntest:2:1:1234567892:1234567892";
-        let sessions = parse_tmux_sessions(output);
+    fn test_quit_key_and_quit_confirm() {
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+
+        let mut app = test_app(vec![test_session("work")]);
+        app.quit_key = QuitKey::Q;
+        assert!(matches!(
+            handle_input(&mut app, esc).unwrap(),
+            InputResult::Continue
+        ));
+        assert!(matches!(
+            handle_input(&mut app, q).unwrap(),
+            InputResult::Quit
+        ));
 
-        assert_eq!(sessions.len(), 3);
+        app.quit_confirm = true;
+        assert!(matches!(
+            handle_input(&mut app, q).unwrap(),
+            InputResult::Continue
+        ));
+        assert!(app.quit_prompt);
+        // Anything but `y` cancels
+        assert!(matches!(
+            handle_input(&mut app, esc).unwrap(),
+            InputResult::Continue
+        ));
+        assert!(!app.quit_prompt);
+        handle_input(&mut app, q).unwrap();
+        let y = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert!(matches!(
+            handle_input(&mut app, y).unwrap(),
+            InputResult::Quit
+        ));
 
-        assert_eq!(sessions[0].name, "main");
-        assert_eq!(sessions[0].windows, 3);
-        assert!(sessions[0].attached);
-        assert_eq!(sessions[0].attached_clients, 2);
+        // Ctrl+C skips the prompt
+        assert!(matches!(
+            handle_input(&mut app, ctrl_c).unwrap(),
+            InputResult::Quit
+        ));
+    }
 
-        assert_eq!(sessions[1].name, "dev");
-        assert_eq!(sessions[1].windows, 1);
-        assert!(!sessions[1].attached);
-        assert_eq!(sessions[1].attached_clients, 0);
+    #[test]
+    fn test_lazy_enrich_targets_follow_selection() {
+        let names: Vec<String> = (0..10).map(|i| format!("s{}", i)).collect();
+        let mut app = test_app(names.iter().map(|name| test_session(name)).collect());
+        assert!(app.lazy_enrich_targets().is_empty());
 
-        assert_eq!(sessions[2].name, "test");
-        assert_eq!(sessions[2].windows, 2);
-        assert!(sessions[2].attached);
-        assert_eq!(sessions[2].attached_clients, 1);
+        app.lazy_enrich = true;
+        assert_eq!(app.lazy_enrich_targets(), vec!["s0", "s1", "s2", "s3"]);
+
+        app.selected = 6;
+        app.sessions[5].resource_info = Some(ResourceInfo {
+            memory_mb: 1.0,
+            cpu_percent: 0.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        assert_eq!(
+            app.lazy_enrich_targets(),
+            vec!["s3", "s4", "s6", "s7", "s8", "s9"]
+        );
     }
 
     #[test]
-    fn test_parse_tmux_sessions_empty() {
-        let output = "";
-        let sessions = parse_tmux_sessions(output);
-        assert_eq!(sessions.len(), 0);
+    fn test_windows_style_paths() {
+        assert!(is_windows_path("C:\\Users\\me\\proj"));
+        assert!(is_windows_path("d:/work"));
+        assert!(is_windows_path("\\\\wsl$\\Ubuntu\\home"));
+        assert!(!is_windows_path("/mnt/c/Users/me"));
+        assert!(!is_windows_path("C:"));
+
+        assert_eq!(collapse_home("/home/me/proj", "/home/me"), "~/proj");
+        assert_eq!(collapse_home("/home/me", "/home/me/"), "~");
+        assert_eq!(
+            collapse_home("/home/meg/proj", "/home/me"),
+            "/home/meg/proj"
+        );
+        assert_eq!(
+            collapse_home("C:\\Users\\me\\proj", "/home/me"),
+            "C:\\Users\\me\\proj"
+        );
+        assert_eq!(
+            collapse_home("C:\\Users\\me\\proj", "C:/Users/me"),
+            "~\\proj"
+        );
+        assert_eq!(collapse_home("/mnt/c/Users/me", ""), "/mnt/c/Users/me");
     }
 
     #[test]
-    fn test_parse_tmux_sessions_invalid_format() {
-        let output = "invalid:format\nmain:3:1:1234567890:1234567890\nincomplete:data";
-        let sessions = parse_tmux_sessions(output);
-        assert_eq!(sessions.len(), 1);
-        assert_eq!(sessions[0].name, "main");
+    fn test_timing_percentiles() {
+        let samples: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        assert_eq!(
+            timing_percentiles(samples),
+            [
+                Duration::from_millis(1),
+                Duration::from_millis(10),
+                Duration::from_millis(19)
+            ]
+        );
+        assert_eq!(
+            timing_percentiles(vec![Duration::from_millis(7)]),
+            [Duration::from_millis(7); 3]
+        );
+        assert_eq!(timing_percentiles(Vec::new()), [Duration::ZERO; 3]);
     }
 
     #[test]
-    fn test_get_tmux_sessions_with_mock() {
+    fn test_session_option_findings() {
         let mut executor = MockTmuxExecutor::new();
         executor.add_response(
-            vec!["list-sessions", "-F", "#{session_name}:#{session_windows}:#{session_attached}:#{session_created}:#{session_activity}"],
-            "main:3:1:1234567890:1234567890\ndev:1:0:1234567891:1234567891",
+            vec!["show-options", "-v", "-t", "=dev:", "mouse"],
+            "off\n",
             "",
             true,
         );
-        // Add mock response for session info enrichment
         executor.add_response(
-            vec!["list-sessions", "-t", "main", "-F", "#{session_id}"],
-            "$0",
+            vec!["show-options", "-v", "-t", "=dev:", "history-limit"],
+            "100\n",
             "",
             true,
         );
         executor.add_response(
-            vec!["list-sessions", "-t", "dev", "-F", "#{session_id}"],
-            "$1",
+            vec!["show-options", "-wv", "-t", "=dev:", "remain-on-exit"],
+            "on\n",
+            "",
+            true,
+        );
+
+        let expect = SessionExpectations::default();
+        assert_eq!(
+            session_option_findings(&executor, "dev", &expect).unwrap(),
+            vec![
+                "history-limit is 100 (expected at least 1000)",
+                "remain-on-exit is on (expected off)"
+            ]
+        );
+
+        let expect = SessionExpectations {
+            mouse: Some(true),
+            min_history_limit: 50,
+            remain_on_exit: true,
+        };
+        assert_eq!(
+            session_option_findings(&executor, "dev", &expect).unwrap(),
+            vec!["mouse is off (expected on)"]
+        );
+    }
+
+    #[test]
+    fn test_session_for_dir_prefers_most_recent_match() {
+        let work = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let work_path = work.path().to_string_lossy().to_string();
+        let output = format!(
+            "old:|:100:|:{work}\nother:|:300:|:{other}\nnew:|:200:|:{work}/\nblank:|:400:|:\n",
+            work = work_path,
+            other = other.path().display()
+        );
+        let dirs = parse_session_dirs(&output);
+        assert_eq!(dirs.len(), 4);
+
+        assert_eq!(session_for_dir(&dirs, work.path()), Some("new".to_string()));
+        assert_eq!(session_for_dir(&dirs, &work.path().join("missing")), None);
+    }
+
+    #[test]
+    fn test_here_session_name() {
+        let existing = vec!["crabmux".to_string(), "my_app".to_string()];
+        assert_eq!(
+            here_session_name(Path::new("/src/crabmux"), &existing, None),
+            "crabmux-2"
+        );
+        assert_eq!(
+            here_session_name(Path::new("/src/my.app"), &existing, None),
+            "my_app-2"
+        );
+        assert_eq!(
+            here_session_name(Path::new("/src/site"), &existing, Some("team-")),
+            "team-site"
+        );
+        assert!(here_session_name(Path::new("/"), &existing, None).starts_with("session-"));
+    }
+
+    #[test]
+    fn test_restore_diff() {
+        let window = |name: &str| WindowLayout {
+            name: name.to_string(),
+            panes: 1,
+            layout: String::new(),
+        };
+        let current = vec![window("editor"), window("scratch")];
+        let snapshot = vec![
+            window("editor"),
+            window("logs"),
+            window("server"),
+            window("git"),
+        ];
+
+        assert_eq!(
+            restore_diff(&current, Some(&snapshot), false).as_deref(),
+            Some("2 windows → 4 windows, +logs, +server, +git, -scratch")
+        );
+        assert_eq!(
+            restore_diff(&current, None, false).as_deref(),
+            Some("2 windows → 1 window")
+        );
+        assert_eq!(restore_diff(&current, Some(&current), false), None);
+        assert_eq!(restore_diff(&current[..1], None, true), None);
+        assert_eq!(
+            restore_diff(&current[..1], Some(&current), true).as_deref(),
+            Some("\x1b[31m1 window\x1b[0m → \x1b[32m2 windows\x1b[0m, \x1b[32m+scratch\x1b[0m")
+        );
+    }
+
+    #[test]
+    fn test_top_summary_line() {
+        let mut busy = test_session("busy");
+        busy.attached = true;
+        busy.resource_info = Some(ResourceInfo {
+            memory_mb: 12.5,
+            cpu_percent: 3.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        assert_eq!(
+            top_summary_line(&[busy, test_session("idle")]),
+            "Summary: 2 total, 1 active, 12.5MB, 3.0% CPU"
+        );
+    }
+
+    #[test]
+    fn test_parse_sessions_with_newline_in_name() {
+        // Older tmux prints the newline raw; the record end keeps the row whole
+        let output = "a\nb:1:0:1700000000:1700000100|;|\nwork:2:1:1700000000:1700000200|;|\n";
+        let (sessions, rejected) = parse_tmux_sessions_with_diagnostics(output);
+        let names: Vec<_> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["a\nb", "work"]);
+        assert_eq!(sessions[1].windows, 2);
+        assert!(rejected.is_empty());
+
+        // tmux 3.x escapes it instead
+        let sessions = parse_tmux_sessions("a\\nb:1:0:1700000000:1700000100|;|\n");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "a\\nb");
+
+        let fields: Vec<String> = ["session_name", "session_windows"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        let sessions = parse_tmux_sessions_with_fields("x\ny:|:3|;|\nz:|:1|;|\n", &fields);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "x\ny");
+        assert_eq!(sessions[0].windows, 3);
+    }
+
+    #[test]
+    fn test_parse_sessions_with_separators_in_names() {
+        let output = "server:8080:|:2:|:1:|:1700000000:|:1700000100|;|\n\
+                      tab\there:|:1:|:0:|:1700000000:|:1700000200|;|\n\
+                      odd:|:name:|:3:|:0:|:1700000000:|:1700000300|;|\n\
+                      short:|:1:|:0|;|\n";
+        let (sessions, rejected) = parse_tmux_sessions_with_diagnostics(output);
+        let names: Vec<_> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["server:8080", "tab\there", "odd:|:name"]);
+        assert_eq!(sessions[0].windows, 2);
+        assert!(sessions[0].attached);
+        assert_eq!(sessions[2].windows, 3);
+        assert_eq!(sessions[2].activity, "1700000300");
+        assert_eq!(rejected, vec!["short:|:1:|:0".to_string()]);
+    }
+
+    #[test]
+    fn test_plan_bulk_rename() {
+        let names: Vec<String> = ["api", "web", "old_db", "scratch"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let plan = plan_bulk_rename(&names, &BulkRename::Prefix("old_".to_string()), None).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                ("api".to_string(), "old_api".to_string()),
+                ("web".to_string(), "old_web".to_string()),
+                ("scratch".to_string(), "old_scratch".to_string()),
+            ]
+        );
+
+        let plan =
+            plan_bulk_rename(&names, &BulkRename::Prefix("old_".to_string()), Some("a")).unwrap();
+        assert_eq!(plan.len(), 2);
+
+        let rule = BulkRename::Replace("old_".to_string(), String::new());
+        assert_eq!(
+            plan_bulk_rename(&names, &rule, None).unwrap(),
+            vec![("old_db".to_string(), "db".to_string())]
+        );
+
+        // Two sessions landing on one name is refused up front
+        let rule = BulkRename::Replace("old_".to_string(), "w".to_string());
+        let names_with_clash: Vec<String> =
+            ["old_eb", "web"].iter().map(|n| n.to_string()).collect();
+        let err = plan_bulk_rename(&names_with_clash, &rule, None).unwrap_err();
+        assert!(err.to_string().contains("would both be named 'web'"));
+
+        // Chains rename the end of the chain first
+        let chain: Vec<String> = ["x", "xx"].iter().map(|n| n.to_string()).collect();
+        let rule = BulkRename::Replace("x".to_string(), "xx".to_string());
+        assert_eq!(
+            plan_bulk_rename(&chain, &rule, None).unwrap(),
+            vec![
+                ("xx".to_string(), "xxxx".to_string()),
+                ("x".to_string(), "xx".to_string()),
+            ]
+        );
+
+        let rule = BulkRename::Replace("api".to_string(), String::new());
+        assert!(plan_bulk_rename(&names, &rule, None).is_err());
+    }
+
+    #[test]
+    fn test_session_windows_parses_list_windows() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(
+            vec![
+                "list-windows",
+                "-t",
+                "=dev:",
+                "-F",
+                "#{window_index}:|:#{window_panes}:|:#{window_active}:|:#{window_name}",
+            ],
+            "0:|:1:|:0:|:editor\n1:|:3:|:1:|:logs:|:server\n",
             "",
             true,
         );
+        let windows = session_windows_with_executor(&executor, "dev").unwrap();
+        assert_eq!(
+            windows,
+            vec![
+                TmuxWindow {
+                    index: 0,
+                    name: "editor".to_string(),
+                    panes: 1,
+                    active: false,
+                },
+                TmuxWindow {
+                    index: 1,
+                    name: "logs:|:server".to_string(),
+                    panes: 3,
+                    active: true,
+                },
+            ]
+        );
 
-        let sessions = get_tmux_sessions_with_executor(&executor).unwrap();
-        assert_eq!(sessions.len(), 2);
-        assert_eq!(sessions[0].name, "main");
-        assert_eq!(sessions[1].name, "dev");
-        assert!(sessions[0].process_info.is_some());
-        assert!(sessions[0].resource_info.is_some());
+        let table = render_windows("dev", &windows, OutputFormat::Table).unwrap();
+        assert!(table.starts_with("Windows in 'dev':\n"));
+        assert!(table.contains("editor"));
+        assert!(table.lines().last().unwrap().ends_with("(active)"));
+        let csv = render_windows("dev", &windows, OutputFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "index,name,panes,active\n0,editor,1,false\n1,logs:|:server,3,true\n"
+        );
     }
 
     #[test]
-    fn test_get_tmux_sessions_no_server() {
+    fn test_session_windows_reports_missing_session() {
         let mut executor = MockTmuxExecutor::new();
         executor.add_response(
-            vec!["list-sessions", "-F", "#{session_name}:#{session_windows}:#{session_attached}:#{session_created}:#{session_activity}"],
+            vec![
+                "list-windows",
+                "-t",
+                "=gone:",
+                "-F",
+                "#{window_index}:|:#{window_panes}:|:#{window_active}:|:#{window_name}",
+            ],
             "",
-            "no server running on /tmp/tmux-1000/default",
+            "can't find session: gone",
             false,
         );
+        let err = session_windows_with_executor(&executor, "gone").unwrap_err();
+        assert!(err.to_string().contains("can't find session"));
+    }
 
-        let sessions = get_tmux_sessions_with_executor(&executor).unwrap();
-        assert_eq!(sessions.len(), 0);
+    #[test]
+    fn test_pad_display_aligns_wide_names() {
+        for name in ["plain", "测试会话", "🚀rocket", "café"] {
+            let padded = pad_display(&truncate_display(name, 12), 12);
+            assert_eq!(UnicodeWidthStr::width(padded.as_str()), 12, "{}", name);
+        }
+        assert_eq!(pad_display("toolong", 3), "toolong");
     }
 
     #[test]
-    fn test_tmux_session_struct() {
-        let session = TmuxSession {
-            name: "test".to_string(),
-            windows: 2,
-            attached: true,
-            attached_clients: 1,
-            attached_users: Vec::new(),
-            created: "1234567890".to_string(),
-            activity: "1234567890".to_string(),
-            process_info: None,
-            resource_info: None,
+    fn test_session_table_aligns_wide_names() {
+        let mut wide = test_session("测试会话");
+        wide.windows = 2;
+        let plain = test_session("dev");
+        let out = render_sessions(
+            &[wide, plain],
+            OutputFormat::Table,
+            None,
+            &ListStyle::default(),
+        )
+        .unwrap();
+        let rows: Vec<&str> = out.lines().skip(3).collect();
+        assert_eq!(rows.len(), 2);
+        let status_column = |row: &str| {
+            let at = row.find("detached").unwrap();
+            UnicodeWidthStr::width(&row[..at])
         };
-
-        assert_eq!(session.name, "test");
-        assert_eq!(session.windows, 2);
-        assert!(session.attached);
+        assert_eq!(status_column(rows[0]), status_column(rows[1]));
     }
 
     #[test]
-    fn test_app_navigation() {
-        let mut app = App {
-            sessions: vec![
-                TmuxSession {
-                    name: "session1".to_string(),
-                    windows: 1,
-                    attached: false,
-                    attached_clients: 0,
-                    attached_users: Vec::new(),
-                    created: "123".to_string(),
-                    activity: "123".to_string(),
-                    process_info: None,
-                    resource_info: None,
-                },
-                TmuxSession {
-                    name: "session2".to_string(),
-                    windows: 2,
-                    attached: false,
-                    attached_clients: 0,
-                    attached_users: Vec::new(),
-                    created: "124".to_string(),
-                    activity: "124".to_string(),
-                    process_info: None,
-                    resource_info: None,
-                },
-                TmuxSession {
-                    name: "session3".to_string(),
-                    windows: 3,
-                    attached: false,
-                    attached_clients: 0,
-                    attached_users: Vec::new(),
-                    created: "125".to_string(),
-                    activity: "125".to_string(),
-                    process_info: None,
-                    resource_info: None,
-                },
-            ],
-            remote_hosts: Vec::new(),
-            selected: 0,
-            show_help: false,
-            aliases: HashMap::new(),
-            hosts: Vec::new(),
-            show_new_session_popup: false,
-            new_session_input: String::new(),
-            new_session_cursor: 0,
-            new_session_target: NewSessionTarget::Local,
-            show_new_host_popup: false,
-            new_host_name_input: String::new(),
-            new_host_name_cursor: 0,
-            new_host_host_input: String::new(),
-            new_host_host_cursor: 0,
-            new_host_active_field: HostField::Host,
-            new_host_error: None,
-            show_kill_confirm: false,
-            kill_confirm_target: None,
-            status_message: None,
-            status_message_expires: None,
-            system: System::new_all(),
+    fn test_name_filter_narrows_navigation() {
+        let mut app = test_app(vec![
+            test_session("web-prod"),
+            test_session("db"),
+            test_session("Web-staging"),
+        ]);
+        let press = |app: &mut App, code: KeyCode| {
+            handle_input(app, KeyEvent::new(code, KeyModifiers::NONE)).unwrap()
         };
 
-        // Test next navigation
-        assert_eq!(app.selected, 0);
-        app.next();
-        assert_eq!(app.selected, 1);
-        app.next();
-        assert_eq!(app.selected, 2);
-        app.next();
-        assert_eq!(app.selected, 0); // Should wrap around
+        press(&mut app, KeyCode::Char('/'));
+        assert!(app.editing_name_filter);
+        for c in "web".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        let names = |app: &App| -> Vec<String> {
+            app.build_entries()
+                .iter()
+                .filter_map(|entry| match entry {
+                    ListEntry::Session(entry) => Some(entry.session.name.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(names(&app), vec!["web-prod", "Web-staging"]);
 
-        // Test previous navigation
-        app.previous();
-        assert_eq!(app.selected, 2); // Should wrap around
-        app.previous();
-        assert_eq!(app.selected, 1);
-        app.previous();
+        // Enter leaves the prompt with the filter kept, so j/k walk the matches
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.editing_name_filter);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
         assert_eq!(app.selected, 0);
+        assert!(matches!(
+            press(&mut app, KeyCode::Enter),
+            InputResult::AttachSession(AttachTarget::Local(name)) if name == "web-prod"
+        ));
+
+        // Esc clears the filter instead of quitting
+        assert!(matches!(
+            press(&mut app, KeyCode::Esc),
+            InputResult::Continue
+        ));
+        assert!(app.name_filter.is_empty());
+        assert_eq!(names(&app).len(), 3);
     }
 
     #[test]
-    fn test_app_navigation_empty() {
-        let mut app = App {
-            sessions: vec![],
-            remote_hosts: Vec::new(),
-            selected: 0,
-            show_help: false,
-            aliases: HashMap::new(),
-            hosts: Vec::new(),
-            show_new_session_popup: false,
-            new_session_input: String::new(),
-            new_session_cursor: 0,
-            new_session_target: NewSessionTarget::Local,
-            show_new_host_popup: false,
-            new_host_name_input: String::new(),
-            new_host_name_cursor: 0,
-            new_host_host_input: String::new(),
-            new_host_host_cursor: 0,
-            new_host_active_field: HostField::Host,
-            new_host_error: None,
-            show_kill_confirm: false,
-            kill_confirm_target: None,
-            status_message: None,
-            status_message_expires: None,
-            system: System::new_all(),
-        };
-
-        // Navigation should not crash with empty sessions
-        app.next();
-        assert_eq!(app.selected, 0);
-        app.previous();
-        assert_eq!(app.selected, 0);
+    fn test_name_filter_esc_while_typing_restores_list() {
+        let mut app = test_app(vec![test_session("alpha"), test_session("beta")]);
+        app.open_name_filter();
+        insert_char_at(&mut app.name_filter, 'z', &mut app.name_filter_cursor);
+        app.name_filter_changed();
+        assert!(app.build_entries().is_empty());
+
+        handle_input(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)).unwrap();
+        assert!(!app.editing_name_filter);
+        assert_eq!(app.build_entries().len(), 2);
     }
 
     #[test]
-    fn test_toggle_help() {
-        let mut app = App {
-            sessions: vec![],
-            remote_hosts: Vec::new(),
-            selected: 0,
-            show_help: false,
-            aliases: HashMap::new(),
-            hosts: Vec::new(),
-            show_new_session_popup: false,
-            new_session_input: String::new(),
-            new_session_cursor: 0,
-            new_session_target: NewSessionTarget::Local,
-            show_new_host_popup: false,
-            new_host_name_input: String::new(),
-            new_host_name_cursor: 0,
-            new_host_host_input: String::new(),
-            new_host_host_cursor: 0,
-            new_host_active_field: HostField::Host,
-            new_host_error: None,
-            show_kill_confirm: false,
-            kill_confirm_target: None,
-            status_message: None,
-            status_message_expires: None,
-            system: System::new_all(),
+    fn test_name_filter_regex_sigil_keeps_last_valid_pattern() {
+        let mut app = test_app(vec![
+            test_session("prod-web"),
+            test_session("preprod-web"),
+            test_session("dev"),
+        ]);
+        let listed = |app: &App| -> Vec<String> {
+            app.build_entries()
+                .iter()
+                .filter_map(|entry| match entry {
+                    ListEntry::Session(entry) => Some(entry.session.name.clone()),
+                    _ => None,
+                })
+                .collect()
         };
 
-        assert!(!app.show_help);
-        app.toggle_help();
-        assert!(app.show_help);
-        app.toggle_help();
-        assert!(!app.show_help);
+        app.name_filter = "~^prod-".to_string();
+        app.name_filter_changed();
+        assert_eq!(listed(&app), vec!["prod-web"]);
+        assert!(app.name_filter_error.is_none());
+
+        // Case-insensitive like the substring filter
+        app.name_filter = "~^PROD-".to_string();
+        app.name_filter_changed();
+        assert_eq!(listed(&app), vec!["prod-web"]);
+
+        // An unfinished pattern reports the error but keeps the old match
+        app.name_filter = "~^prod-(".to_string();
+        app.name_filter_changed();
+        assert!(app.name_filter_error.is_some());
+        assert_eq!(listed(&app), vec!["prod-web"]);
+
+        // Without the sigil the text is a plain substring again
+        app.name_filter = "prod-".to_string();
+        app.name_filter_changed();
+        assert!(app.name_filter_error.is_none());
+        assert_eq!(listed(&app), vec!["prod-web", "preprod-web"]);
     }
 
     #[test]
-    fn test_session_snapshot_serialization() {
-        let sessions = vec![TmuxSession {
-            name: "main".to_string(),
-            windows: 3,
-            attached: true,
-            attached_clients: 1,
-            attached_users: Vec::new(),
-            created: "123".to_string(),
-            activity: "456".to_string(),
-            process_info: None,
-            resource_info: None,
-        }];
+    fn test_sessions_changed_ignores_resource_drift() {
+        let old = vec![test_session("dev"), test_session("ops")];
+
+        let mut new = old.clone();
+        new[0].resource_info = Some(ResourceInfo {
+            memory_mb: 512.0,
+            cpu_percent: 42.0,
+            swap_kb: None,
+            open_fds: None,
+        });
+        assert!(!sessions_changed(&old, &new));
 
-        let snapshot = SessionSnapshot {
-            sessions: sessions.clone(),
-            timestamp: "2024-01-01T00:00:00".to_string(),
-        };
+        new[1].activity = "1640995999".to_string();
+        assert!(sessions_changed(&old, &new));
 
-        // Test serialization
-        let json = serde_json::to_string(&snapshot).unwrap();
-        assert!(json.contains("\"name\":\"main\""));
-        assert!(json.contains("\"windows\":3"));
-        assert!(json.contains("\"attached\":true"));
+        let mut attached = old.clone();
+        attached[0].attached = true;
+        assert!(sessions_changed(&old, &attached));
+        assert!(sessions_changed(&old, &old[..1]));
+    }
 
-        // Test deserialization
-        let deserialized: SessionSnapshot = serde_json::from_str(&json).unwrap();
-        assert_eq!(deserialized.sessions.len(), 1);
-        assert_eq!(deserialized.sessions[0].name, "main");
-        assert_eq!(deserialized.timestamp, "2024-01-01T00:00:00");
+    #[test]
+    fn test_sort_sessions_puts_unmeasured_last() {
+        let with_memory = |name: &str, memory_mb: f64| {
+            let mut session = test_session(name);
+            session.resource_info = Some(ResourceInfo {
+                memory_mb,
+                cpu_percent: 0.0,
+                swap_kb: None,
+                open_fds: None,
+            });
+            session
+        };
+        let mut sessions = vec![
+            test_session("unmeasured"),
+            with_memory("small", 10.0),
+            with_memory("big", 900.0),
+        ];
+        sort_sessions(&mut sessions, SortKey::Memory);
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["big", "small", "unmeasured"]);
+
+        sessions[0].activity = "not a time".to_string();
+        sessions[1].activity = "1700000000".to_string();
+        sessions[2].activity = "1700000500".to_string();
+        sort_sessions(&mut sessions, SortKey::Activity);
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["unmeasured", "small", "big"]);
+
+        sort_sessions(&mut sessions, SortKey::Name);
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["big", "small", "unmeasured"]);
     }
 
     #[test]
-    fn test_input_result_variants() {
-        // Test that InputResult enum variants work correctly
-        let result1 = InputResult::Continue;
-        let result2 = InputResult::Quit;
-        let result3 = InputResult::AttachSession(AttachTarget::Local("test".to_string()));
-        let result4 = InputResult::Refreshed;
+    fn test_tui_sort_cycles_and_keeps_selection() {
+        let mut few = test_session("few");
+        few.windows = 1;
+        let mut many = test_session("many");
+        many.windows = 5;
+        let mut app = test_app(vec![few, many]);
+        app.select_local_session("many");
+        assert_eq!(app.selected, 1);
 
-        match result1 {
-            InputResult::Continue => {}
-            _ => panic!("Expected Continue"),
-        }
+        app.cycle_sort();
+        assert_eq!(app.sort, Some(SortKey::Name));
+        app.cycle_sort();
+        assert_eq!(app.sort, Some(SortKey::Windows));
+        assert_eq!(app.sessions[0].name, "many");
+        assert_eq!(app.selected, 0);
 
-        match result2 {
-            InputResult::Quit => {}
-            _ => panic!("Expected Quit"),
-        }
+        // Manual reordering is refused while a sort is active
+        handle_input(&mut app, KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT)).unwrap();
+        assert_eq!(app.sessions[0].name, "many");
 
-        match result3 {
-            InputResult::AttachSession(AttachTarget::Local(name)) => assert_eq!(name, "test"),
-            _ => panic!("Expected AttachSession"),
+        for _ in 0..4 {
+            app.cycle_sort();
         }
+        assert_eq!(app.sort, None);
+        assert_eq!(app.sessions[0].name, "few");
+        assert_eq!(app.selected, 1);
+    }
 
-        match result4 {
-            InputResult::Refreshed => {}
-            _ => panic!("Expected Refreshed"),
-        }
+    #[test]
+    fn test_session_list_title_marks_sorted_column() {
+        assert_eq!(
+            session_list_title(None),
+            "Sessions │ Name        │ Win │ Memory │ CPU   │ Clients "
+        );
+        assert!(session_list_title(Some(SortKey::Name)).contains("│ Name ▲      │"));
+        assert!(session_list_title(Some(SortKey::Memory)).contains("│ Memory ▼ │"));
+        assert!(session_list_title(Some(SortKey::Activity)).ends_with("│ Activity ▼ "));
+    }
+
+    #[test]
+    fn test_parse_tmux_version() {
+        assert_eq!(parse_tmux_version("tmux 3.3a\n"), Some((3, 3)));
+        assert_eq!(parse_tmux_version("tmux 3.2"), Some((3, 2)));
+        assert_eq!(parse_tmux_version("tmux next-3.5"), Some((3, 5)));
+        assert_eq!(parse_tmux_version("tmux 2.9a"), Some((2, 9)));
+        assert_eq!(parse_tmux_version("tmux master"), None);
+        assert!(parse_tmux_version("tmux 3.1c").unwrap() < POPUP_MIN_TMUX);
+    }
+
+    #[test]
+    fn test_popup_args_run_this_binary_quoted() {
+        assert_eq!(
+            popup_args("/opt/my tools/cmux", "80%", "30", 2),
+            vec![
+                "display-popup",
+                "-E",
+                "-e",
+                "CMUX_DEPTH=2",
+                "-w",
+                "80%",
+                "-h",
+                "30",
+                "'/opt/my tools/cmux' --switch-client",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_client_uses_exact_target() {
+        let mut executor = MockTmuxExecutor::new();
+        executor.add_response(vec!["switch-client", "-t", "=dev"], "", "", true);
+        executor.add_response(
+            vec!["switch-client", "-t", "=gone"],
+            "",
+            "can't find session: gone",
+            false,
+        );
+        assert!(switch_client_with_executor(&executor, "dev").is_ok());
+        let err = switch_client_with_executor(&executor, "gone").unwrap_err();
+        assert_eq!(err.to_string(), "can't find session: gone");
+    }
+
+    #[test]
+    fn test_check_session_limit() {
+        assert!(Config::default().check_session_limit(1000).is_ok());
+        let config: Config = serde_json::from_str(r#"{"max_sessions": 3}"#).unwrap();
+        assert!(config.check_session_limit(2).is_ok());
+        let err = config.check_session_limit(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Refusing to create a session: 3 already running and max_sessions is 3 \
+             (pass --force to create it anyway)"
+        );
+        assert!(config.check_session_limit(40).is_err());
+    }
+
+    #[test]
+    fn test_auto_snapshot_interval_and_footer_label() {
+        assert_eq!(Config::default().auto_snapshot_interval(), None);
+        let config: Config = serde_json::from_str(r#"{"auto_snapshot_secs": 0}"#).unwrap();
+        assert_eq!(config.auto_snapshot_interval(), None);
+        let config: Config = serde_json::from_str(r#"{"auto_snapshot_secs": 300}"#).unwrap();
+        assert_eq!(
+            config.auto_snapshot_interval(),
+            Some(Duration::from_secs(300))
+        );
+
+        use chrono::TimeZone;
+        let at = chrono::Local
+            .with_ymd_and_hms(2024, 5, 1, 9, 3, 7)
+            .single()
+            .unwrap();
+        assert_eq!(AutoSnapshotStatus::Saved(at).label(), "last saved 09:03:07");
+        assert_eq!(
+            AutoSnapshotStatus::Skipped(at).label(),
+            "no sessions at 09:03:07, kept last snapshot"
+        );
+        assert_eq!(
+            AutoSnapshotStatus::Failed("Permission denied".to_string()).label(),
+            "snapshot failed: Permission denied"
+        );
     }
 }
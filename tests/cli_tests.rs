@@ -335,3 +335,266 @@ fn test_empty_arguments() {
     // So we just check that it doesn't crash
     assert!(output.status.success() || !String::from_utf8_lossy(&output.stderr).is_empty());
 }
+
+#[test]
+#[cfg(unix)]
+fn test_capture_command_nonexistent_session() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("capture")
+        .arg("nonexistent-capture-session-12345")
+        .arg("--no-indicator")
+        .assert()
+        .failure();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list_null_separator() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd.arg("list").arg("--null").output().unwrap();
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // No table header, and every name is NUL-terminated
+        assert!(!stdout.contains("Active tmux sessions"));
+        assert!(!stdout.contains("No tmux sessions found"));
+        assert!(stdout.is_empty() || stdout.ends_with('\0'));
+    }
+}
+
+#[test]
+fn test_list_only_requires_json() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("list")
+        .arg("--only")
+        .arg("name")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--json"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list_json_only_unknown_field() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd
+        .arg("list")
+        .arg("--json")
+        .arg("--only")
+        .arg("name,bogus")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown field 'bogus'") || stderr.contains("tmux"));
+}
+
+#[test]
+fn test_rename_current_outside_tmux() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("rename-current")
+        .arg("new-name")
+        .env_remove("TMUX")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not inside a tmux session"));
+}
+
+#[test]
+fn test_switch_outside_tmux() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("switch")
+        .arg("work")
+        .env_remove("TMUX")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "cmux switch only works inside tmux",
+        ));
+}
+
+#[test]
+fn test_new_with_missing_shell() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("new")
+        .arg("shell-test-session")
+        .arg("--shell")
+        .arg("/nonexistent/bin/zsh")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Shell '/nonexistent/bin/zsh' does not exist",
+        ));
+}
+
+#[test]
+fn test_top_interval_zero_prints_once() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd.arg("top").arg("--interval").arg("0").output().unwrap();
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("crabmux - Overview"));
+        assert!(stdout.contains("total"));
+    }
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("top")
+        .arg("--interval")
+        .arg("-1")
+        .assert()
+        .failure();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list_output_csv_header() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd.arg("list").arg("--output").arg("csv").output().unwrap();
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with("name,windows,attached,memory_mb,cpu_percent\n"));
+    }
+}
+
+#[test]
+fn test_list_output_rejects_unknown_format() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("list")
+        .arg("--output")
+        .arg("xml")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'xml'"));
+}
+
+#[test]
+fn test_exists_command() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("exists")
+        .arg("nonexistent-exists-session-12345")
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_set_history_rejects_invalid_limit() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("set-history")
+        .arg("some-session")
+        .arg("lots")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'lots'"));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["set-history", "some-session", "--", "-5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '-5'"));
+}
+
+#[test]
+fn test_version_full_reports_build_info() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("version")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("target:").not());
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["version", "--full"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tmux:"))
+        .stdout(predicate::str::contains("target:"))
+        .stdout(predicate::str::contains("ratatui:"))
+        .stdout(predicate::str::contains("crossterm:"));
+}
+
+#[test]
+fn test_new_json_conflicts_with_shell() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["new", "--json", "--shell", "/bin/sh"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_snapshot_schema_describes_snapshot_fields() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd.arg("snapshot-schema").output().unwrap();
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["title"], "SessionSnapshot");
+    let properties = &schema["properties"];
+    assert!(properties.get("sessions").is_some());
+    assert!(properties.get("timestamp").is_some());
+    assert!(properties.get("layouts").is_some());
+}
+
+#[test]
+fn test_bench_is_hidden_and_needs_iterations() {
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bench").not());
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["bench", "-n", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list_format_alias_without_server() {
+    let socket_dir = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["list", "--format", "json"])
+        .env_remove("TMUX")
+        .env("TMUX_TMPDIR", socket_dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("[]\n");
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.args(["list", "--format", "csv"])
+        .env_remove("TMUX")
+        .env("TMUX_TMPDIR", socket_dir.path())
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("name,windows,attached,memory_mb,cpu_percent\n");
+}
+
+#[test]
+fn test_config_prints_resolved_defaults() {
+    let home = tempfile::tempdir().unwrap();
+    std::fs::write(
+        home.path().join(".cmux_config.json"),
+        r#"{"default_sort": "cpu"}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("config")
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""default_sort": "cpu""#))
+        .stdout(predicate::str::contains(r#""refresh_secs": 5"#))
+        .stdout(predicate::str::contains(r#""confirm_kill": false"#));
+}
@@ -250,3 +250,53 @@ fn test_concurrent_session_operations() {
     assert!(output1.status.success() || !output1.stderr.is_empty());
     assert!(output2.status.success() || !output2.stderr.is_empty());
 }
+
+#[test]
+fn test_restore_reports_parse_error_location() {
+    let temp_dir = TempDir::new().unwrap();
+    let typo_file = temp_dir.path().join("typo.json");
+
+    // "windows" is misspelled, so the session object on lines 3-8 is missing a field
+    let typo_content = r#"{
+    "sessions": [
+        {
+            "name": "work",
+            "windos": 2,
+            "attached": false,
+            "created": "1640995200",
+            "activity": "1640995200"
+        }
+    ],
+    "timestamp": "2024-01-01T00:00:00Z"
+}"#;
+    fs::write(&typo_file, typo_content).unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("restore")
+        .arg(typo_file.to_str().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse snapshot file"))
+        .stderr(predicate::str::contains("line 9"))
+        .stderr(predicate::str::contains("missing field `windows`"));
+}
+
+#[test]
+fn test_nested_invocation_limit() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .env("HOME", temp_dir.path())
+        .env("CMUX_DEPTH", "5")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nested 5 levels deep"));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .env("HOME", temp_dir.path())
+        .env("CMUX_DEPTH", "1")
+        .assert()
+        .success();
+}
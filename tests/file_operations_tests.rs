@@ -112,6 +112,60 @@ fn test_alias_overwrite_existing() {
         .stdout(predicate::str::contains("test-alias -> session2"));
 }
 
+#[test]
+fn test_alias_delete_and_rename() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for (alias, session) in [("work", "session1"), ("play", "session2")] {
+        let mut cmd = Command::cargo_bin("cmux").unwrap();
+        cmd.arg("alias")
+            .arg(alias)
+            .arg(session)
+            .env("HOME", temp_dir.path())
+            .assert()
+            .success();
+    }
+
+    // Rename keeps the session
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .args(["--rename", "work", "job"])
+        .env("HOME", temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renamed alias 'work' to 'job'"));
+
+    // Renaming onto an existing alias is refused
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .args(["--rename", "job", "play"])
+        .env("HOME", temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Alias 'play' already exists"));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .args(["--delete", "play"])
+        .env("HOME", temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted alias 'play'"));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .args(["--delete", "play"])
+        .env("HOME", temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Alias 'play' not found"));
+
+    let content = fs::read_to_string(temp_dir.path().join(".cmux_aliases.json")).unwrap();
+    assert!(content.contains("\"job\": \"session1\""));
+    assert!(!content.contains("work"));
+    assert!(!content.contains("play"));
+}
+
 #[test]
 fn test_snapshot_restore_with_valid_file() {
     let temp_dir = TempDir::new().unwrap();
@@ -361,3 +415,217 @@ fn test_home_directory_fallback() {
         .assert()
         .success(); // Should use "." as fallback
 }
+
+#[test]
+fn test_tag_file_operations() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("tag")
+        .arg("prod")
+        .arg("red")
+        .env("HOME", temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tagged session 'prod' as red"));
+
+    let content = fs::read_to_string(temp_dir.path().join(".cmux_tags.json")).unwrap();
+    assert!(content.contains("\"prod\": \"red\""));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("tag")
+        .arg("prod")
+        .arg("purple")
+        .env("HOME", temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown tag color 'purple'"));
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("tag")
+        .arg("prod")
+        .arg("none")
+        .env("HOME", temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed tag from session 'prod'"));
+}
+
+#[test]
+fn test_confirm_requires_terminal() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("--confirm")
+        .arg("kill")
+        .arg("nonexistent-confirm-session")
+        .env("HOME", temp_dir.path())
+        .write_stdin("y\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("stdin is not a terminal"));
+
+    // The config file turns the same guard on without the flag
+    fs::write(
+        temp_dir.path().join(".cmux_config.json"),
+        r#"{"confirm_all": true}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("kill")
+        .arg("nonexistent-confirm-session")
+        .env("HOME", temp_dir.path())
+        .write_stdin("y\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("stdin is not a terminal"));
+}
+
+#[test]
+fn test_config_export_import_roundtrip() {
+    let source_home = TempDir::new().unwrap();
+    let target_home = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("alias")
+        .arg("w")
+        .arg("work")
+        .env("HOME", source_home.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("tag")
+        .arg("work")
+        .arg("green")
+        .env("HOME", source_home.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd
+        .arg("config-export")
+        .env("HOME", source_home.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let backup_file = source_home.path().join("backup.json");
+    fs::write(&backup_file, &output.stdout).unwrap();
+
+    // An existing alias with a different target is reported as overwritten
+    fs::write(
+        target_home.path().join(".cmux_aliases.json"),
+        r#"{"w": "old"}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("config-import")
+        .arg(backup_file.to_str().unwrap())
+        .env("HOME", target_home.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Aliases: 0 added, 1 overwritten"))
+        .stdout(predicate::str::contains("Tags: 1 added, 0 overwritten"));
+
+    let aliases = fs::read_to_string(target_home.path().join(".cmux_aliases.json")).unwrap();
+    assert!(aliases.contains("\"w\": \"work\""));
+    let tags = fs::read_to_string(target_home.path().join(".cmux_tags.json")).unwrap();
+    assert!(tags.contains("\"work\": \"green\""));
+}
+
+#[test]
+fn test_config_import_rejects_unknown_keys() {
+    let temp_dir = TempDir::new().unwrap();
+    let backup_file = temp_dir.path().join("backup.json");
+    fs::write(&backup_file, r#"{"aliasses": {"w": "work"}}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    cmd.arg("config-import")
+        .arg(backup_file.to_str().unwrap())
+        .env("HOME", temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse backup file"));
+    assert!(!temp_dir.path().join(".cmux_aliases.json").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_restore_progress_and_summary() {
+    let temp_dir = TempDir::new().unwrap();
+    let snapshot_file = temp_dir.path().join("progress_snapshot.json");
+    let session_name = format!("restore-progress-{}", std::process::id());
+
+    // The same session twice: the second entry is skipped once the first exists
+    let snapshot_content = format!(
+        r#"{{
+        "sessions": [
+            {{"name": "{0}", "windows": 1, "attached": false, "created": "1", "activity": "1"}},
+            {{"name": "{0}", "windows": 1, "attached": false, "created": "1", "activity": "1"}}
+        ],
+        "timestamp": "2024-01-01T00:00:00Z"
+    }}"#,
+        session_name
+    );
+    fs::write(&snapshot_file, snapshot_content).unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd
+        .arg("restore")
+        .arg(snapshot_file.to_str().unwrap())
+        .env("HOME", temp_dir.path())
+        .output()
+        .unwrap();
+
+    let _ = std::process::Command::new("tmux")
+        .args(["kill-session", "-t", &session_name])
+        .output();
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(&format!("[1/2] Restored session: {}", session_name)));
+        assert!(stdout.contains("[2/2] Session"));
+        assert!(stdout.contains("Restored 1, skipped 1"));
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_restore_dry_run_creates_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    let snapshot_file = temp_dir.path().join("dry_run_snapshot.json");
+    let session_name = format!("restore-dry-run-{}", std::process::id());
+
+    let snapshot_content = format!(
+        r#"{{
+        "sessions": [
+            {{"name": "{}", "windows": 1, "attached": false, "created": "1", "activity": "1"}}
+        ],
+        "timestamp": "2024-01-01T00:00:00Z"
+    }}"#,
+        session_name
+    );
+    fs::write(&snapshot_file, snapshot_content).unwrap();
+
+    let mut cmd = Command::cargo_bin("cmux").unwrap();
+    let output = cmd
+        .arg("restore")
+        .arg(snapshot_file.to_str().unwrap())
+        .arg("--dry-run")
+        .env("HOME", temp_dir.path())
+        .output()
+        .unwrap();
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(&format!("[1/1] Would restore session: {}", session_name)));
+        assert!(stdout.contains("Would restore 1, skip 0"));
+
+        let exists = std::process::Command::new("tmux")
+            .args(["has-session", "-t", &format!("={}", session_name)])
+            .output()
+            .unwrap();
+        assert!(!exists.status.success());
+    }
+}
@@ -12,6 +12,7 @@ mod parsing_tests {
         name: String,
         windows: usize,
         attached: bool,
+        attached_clients: usize,
         created: String,
         activity: String,
     }
@@ -22,10 +23,13 @@ mod parsing_tests {
             .filter_map(|line| {
                 let parts: Vec<&str> = line.split(':').collect();
                 if parts.len() >= 5 {
+                    // #{session_attached} is a client count, not a flag
+                    let attached_clients = parts[2].parse::<usize>().unwrap_or(0);
                     Some(TmuxSession {
                         name: parts[0].to_string(),
                         windows: parts[1].parse().unwrap_or(0),
-                        attached: parts[2] == "1",
+                        attached: attached_clients > 0,
+                        attached_clients,
                         created: parts[3].to_string(),
                         activity: parts[4].to_string(),
                     })
@@ -97,13 +101,19 @@ mod parsing_tests {
 
     #[test]
     fn test_parse_attached_status_variations() {
-        let output = "attached:1:1:123:456\ndetached:1:0:123:456\ninvalid:1:2:123:456";
-        let sessions = parse_tmux_sessions(output);
-
-        assert_eq!(sessions.len(), 3);
-        assert!(sessions[0].attached); // "1" = attached
-        assert!(!sessions[1].attached); // "0" = detached
-        assert!(!sessions[2].attached); // anything else = detached
+        let output =
+            "attached:1:1:123:456\ndetached:1:0:123:456\nshared:1:2:123:456\ninvalid:1:x:123:456";
+        let sessions = parse_tmux_sessions(output);
+
+        assert_eq!(sessions.len(), 4);
+        assert!(sessions[0].attached); // one client
+        assert_eq!(sessions[0].attached_clients, 1);
+        assert!(!sessions[1].attached); // no clients
+        assert_eq!(sessions[1].attached_clients, 0);
+        assert!(sessions[2].attached); // two clients is still attached
+        assert_eq!(sessions[2].attached_clients, 2);
+        assert!(!sessions[3].attached); // unparseable count = detached
+        assert_eq!(sessions[3].attached_clients, 0);
     }
 
     #[test]